@@ -0,0 +1,89 @@
+use fastrand::Rng;
+
+use crate::{
+    cell_type_porous, grid::GridWindow, set_cell, Cell, CellType, TrackingWindow, DIRECTIONS,
+    NEIGHBOURS, WATER_BOILING_TEMPERATURE,
+};
+
+// chance a tick of a soaked cell passing a cell of water on to a drier one of its own kind beside it
+const WICK_CHANCE: f32 = 0.25;
+// heat used up boiling off a cell of soaked up water
+const BOIL_COOLING: u8 = 16;
+
+// something full of holes that water soaks into, wood and sponge for now. it keeps how many cells of water it holds in
+// its lifetime, soaks up water touching it, passes it on to drier cells of itself so it spreads through the inside, and
+// boils it off as steam once it is hot. it wont catch fire until it has dried out
+pub struct Porous {
+    // chance a tick of soaking up a cell of water touching it
+    pub soak_chance: f32,
+    // most cells of water one cell of it can hold
+    pub capacity: u8,
+}
+
+pub fn absorb(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    porous: &Porous,
+    rng: &Rng,
+) {
+    let cell = cells[pos];
+    if cell.lifetime > 0 && cell.temperature >= WATER_BOILING_TEMPERATURE {
+        boil(cells, tracking, pos, rng);
+        return;
+    }
+
+    let direction = DIRECTIONS[rng.usize(0..DIRECTIONS.len())];
+    let Some((neighbour_pos, neighbour)) = cells.ray(pos, direction, 1).next() else {
+        return;
+    };
+
+    if neighbour.ty == CellType::Water
+        && cell.lifetime < porous.capacity
+        && rng.f32() < porous.soak_chance
+    {
+        set_cell(
+            cells,
+            tracking,
+            neighbour_pos,
+            Cell::from(CellType::Air, rng),
+        );
+        cells[pos].lifetime += 1;
+    } else if neighbour.ty == cell.ty
+        && neighbour.lifetime + 1 < cell.lifetime
+        && rng.f32() < WICK_CHANCE
+    {
+        cells[neighbour_pos].lifetime += 1;
+        cells[pos].lifetime -= 1;
+        tracking.mark_dirty(neighbour_pos.0, neighbour_pos.1);
+    } else {
+        return;
+    }
+
+    // drawn darker the more it holds
+    tracking.mark_dirty(pos.0, pos.1);
+}
+
+// boiling off a cell of water cools it down, the steam comes out into any air beside it
+fn boil(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), rng: &Rng) {
+    let cell = &mut cells[pos];
+    cell.lifetime -= 1;
+    cell.temperature -= BOIL_COOLING;
+    tracking.mark_dirty(pos.0, pos.1);
+
+    let air = NEIGHBOURS.into_iter().find_map(|direction| {
+        cells
+            .ray(pos, direction, 1)
+            .next()
+            .filter(|(_, neighbour)| neighbour.ty == CellType::Air)
+            .map(|(air, _)| air)
+    });
+    if let Some(air) = air {
+        set_cell(cells, tracking, air, Cell::from(CellType::Steam, rng));
+    }
+}
+
+// still holding water, it has to dry out before it can burn
+pub fn soaked(cell: &Cell) -> bool {
+    cell_type_porous(cell.ty).is_some() && cell.lifetime > 0
+}
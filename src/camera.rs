@@ -0,0 +1,87 @@
+use crate::{HEIGHT, WIDTH};
+
+pub const MAX_ZOOM: usize = 8;
+
+// which part of the grid fills the frame, at zoom n every cell is drawn n by n pixels so only a 1/n of the grid is seen
+pub struct Camera {
+    pub zoom: usize,
+    // top left cell in view
+    pub position: (usize, usize),
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Camera {
+            zoom: 1,
+            position: (0, 0),
+        }
+    }
+
+    fn view_size(&self) -> (usize, usize) {
+        (WIDTH / self.zoom, HEIGHT / self.zoom)
+    }
+
+    // the cell drawn at a pixel of the frame
+    pub fn cell_at(&self, pixel: (usize, usize)) -> (usize, usize) {
+        (
+            (self.position.0 + pixel.0 / self.zoom).min(WIDTH - 1),
+            (self.position.1 + pixel.1 / self.zoom).min(HEIGHT - 1),
+        )
+    }
+
+    // doubles or halves the zoom keeping the cell under pixel where it is on screen
+    pub fn zoom_at(&mut self, pixel: (usize, usize), zoom_in: bool) {
+        let cell = self.cell_at(pixel);
+
+        self.zoom = if zoom_in {
+            (self.zoom * 2).min(MAX_ZOOM)
+        } else {
+            (self.zoom / 2).max(1)
+        };
+
+        self.position = (
+            cell.0.saturating_sub(pixel.0 / self.zoom),
+            cell.1.saturating_sub(pixel.1 / self.zoom),
+        );
+        self.clamp();
+    }
+
+    // moves by a quarter of the view in each direction given, returns how far past the edges of the grid that would
+    // have taken it
+    pub fn pan(&mut self, direction: (isize, isize)) -> (isize, isize) {
+        let view_size = self.view_size();
+        let step = (
+            direction.0 * (view_size.0 / 4) as isize,
+            direction.1 * (view_size.1 / 4) as isize,
+        );
+        let target = (
+            self.position.0 as isize + step.0,
+            self.position.1 as isize + step.1,
+        );
+
+        self.move_by(step);
+
+        (
+            target.0 - self.position.0 as isize,
+            target.1 - self.position.1 as isize,
+        )
+    }
+
+    // moves by offset cells, stopping at the edges of the grid
+    pub fn move_by(&mut self, offset: (isize, isize)) {
+        self.position = (
+            self.position.0.saturating_add_signed(offset.0),
+            self.position.1.saturating_add_signed(offset.1),
+        );
+        self.clamp();
+    }
+
+    fn clamp(&mut self) {
+        let view_size = self.view_size();
+
+        self.position = (
+            self.position.0.min(WIDTH - view_size.0),
+            self.position.1.min(HEIGHT - view_size.1),
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use fastrand::Rng;
+
+use crate::{grid::GridWindow, set_cell, Cell, CellType, TrackingWindow, NEIGHBOURS};
+
+// something that spreads by turning what it touches into more of itself, a virus for now. each cell keeps how long it
+// has left in its lifetime and only counts it down while there is nothing around it to take, so the inside of an
+// outbreak dies off behind the edge still eating its way out
+pub struct Contagion {
+    pub infects: &'static [CellType],
+    pub body: CellType,
+    // chance a tick of taking one of the cells around it that it infects
+    pub spread_chance: f32,
+    // ticks it lasts with nothing left to take before it is gone
+    pub burnout: u8,
+}
+
+pub fn spread(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    contagion: &Contagion,
+    rng: &Rng,
+) {
+    let prey: Vec<_> = NEIGHBOURS
+        .into_iter()
+        .filter_map(|direction| cells.ray(pos, direction, 1).next())
+        .filter(|(_, cell)| contagion.infects.contains(&cell.ty))
+        .map(|(neighbour, _)| neighbour)
+        .collect();
+
+    if prey.is_empty() {
+        cells[pos].lifetime = cells[pos].lifetime.saturating_sub(1);
+        if cells[pos].lifetime == 0 {
+            set_cell(cells, tracking, pos, Cell::from(CellType::Air, rng));
+        }
+        return;
+    }
+
+    if rng.f32() < contagion.spread_chance {
+        let mut infected = Cell::from(contagion.body, rng);
+        infected.set_moved(tracking.generation());
+        set_cell(cells, tracking, prey[rng.usize(..prey.len())], infected);
+    }
+}
@@ -0,0 +1,53 @@
+use fastrand::Rng;
+
+use crate::{
+    cell_type_corrosion_resistance, grid::GridWindow, set_cell, Cell, CellType, TrackingWindow,
+    NEIGHBOURS,
+};
+
+// something that eats away the cells around it, acid for now but rust and rot work the same way just slower
+pub struct Corrosive {
+    // chance a tick of eating a random neighbour with no corrosion resistance
+    pub strength: f32,
+    // chance an eaten cell gives off gas instead of leaving air
+    pub byproduct_chance: f32,
+    pub byproduct: CellType,
+    // chance the corrosive is used up each time it eats something, it leaves air behind
+    pub used_up_chance: f32,
+}
+
+// picks one of the 8 neighbours at random and eats it unless it resists, true if the corrosive was used up
+pub fn corrode(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    corrosive: &Corrosive,
+    rng: &Rng,
+) -> bool {
+    let direction = NEIGHBOURS[rng.usize(0..NEIGHBOURS.len())];
+    let Some((target, cell)) = cells.ray(pos, direction, 1).next() else {
+        return false;
+    };
+    let Some(resistance) = cell_type_corrosion_resistance(cell.ty) else {
+        return false;
+    };
+
+    if rng.f32() >= corrosive.strength * (1.0 - resistance) {
+        return false;
+    }
+
+    let eaten = if rng.f32() < corrosive.byproduct_chance {
+        corrosive.byproduct
+    } else {
+        CellType::Air
+    };
+    set_cell(cells, tracking, target, Cell::from(eaten, rng));
+    cells[target].set_moved(tracking.generation());
+
+    if rng.f32() < corrosive.used_up_chance {
+        set_cell(cells, tracking, pos, Cell::from(CellType::Air, rng));
+        return true;
+    }
+
+    false
+}
@@ -0,0 +1,143 @@
+use fastrand::Rng;
+
+use crate::{
+    generic_fall, grid::GridWindow, set_cell, swap_cells, Cell, CellType, TrackingWindow,
+    ACCELERATION, MAX_VELOCITY, NEIGHBOURS,
+};
+
+// a critter that lives in the grid as a cell of its own but gets about by itself instead of just falling, ants and fish
+// for now. they keep the way they are heading in the bottom 3 bits of their lifetime, an index into NEIGHBOURS, and how
+// much breath they have left above that
+pub struct Creature {
+    // what it moves through, swapping places with it, and has to stay next to to breathe. ants dig through sand the same
+    // way they walk through air
+    pub moves_through: &'static [CellType],
+    // whether it can only step where there is something solid to hold onto and falls once there is none around it. the
+    // rest fall through anything that isnt moves_through
+    pub clings: bool,
+    // chance a tick of taking a step, and of setting off a new way when it does
+    pub speed: f32,
+    pub turn_chance: f32,
+    // chance a tick of losing a breath with none of moves_through around, with none left it dies into remains
+    pub suffocation_chance: f32,
+    pub remains: CellType,
+}
+
+// anything caught in honey is held fast, only getting to move with this chance a tick
+const HONEY_HOLD: f32 = 0.9;
+
+const MAX_BREATH: u8 = 31;
+// what new ones start out with, all their breath
+pub const RESTED: u8 = MAX_BREATH << 3;
+
+fn breath(cell: &Cell) -> u8 {
+    cell.lifetime >> 3
+}
+
+fn heading(cell: &Cell) -> usize {
+    (cell.lifetime & 7) as usize
+}
+
+pub fn crawl(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    creature: &Creature,
+    falls_through: &[CellType],
+    rng: &Rng,
+) {
+    // toxic gas around it chokes it even with something to breathe
+    let breathing = touches(cells, pos, |cell| creature.moves_through.contains(&cell.ty))
+        && !touches(cells, pos, |cell| cell.ty == CellType::ToxicGas);
+    let breath = if breathing {
+        MAX_BREATH
+    } else {
+        breath(&cells[pos]).saturating_sub((rng.f32() < creature.suffocation_chance) as u8)
+    };
+    if breath == 0 {
+        set_cell(cells, tracking, pos, Cell::from(creature.remains, rng));
+        return;
+    }
+    cells[pos].lifetime = breath << 3 | heading(&cells[pos]) as u8;
+
+    if rng.f32() < HONEY_HOLD && touches(cells, pos, |cell| cell.ty == CellType::Honey) {
+        return;
+    }
+
+    // thrown ones fly whatever they are next to
+    let falling = cells[pos].velocity_x != 0
+        || !creature.clings
+        || !touches(cells, pos, |cell| solid(cell.ty));
+    if falling
+        && generic_fall(
+            cells,
+            tracking,
+            pos,
+            falls_through,
+            MAX_VELOCITY,
+            ACCELERATION,
+            false,
+            rng,
+        )
+        .is_some()
+    {
+        return;
+    }
+
+    if rng.f32() >= creature.speed {
+        return;
+    }
+
+    let open = |cells: &GridWindow, heading: usize| {
+        cells
+            .ray(pos, NEIGHBOURS[heading], 1)
+            .next()
+            .filter(|(to, cell)| {
+                creature.moves_through.contains(&cell.ty)
+                    && (!creature.clings || solid(cell.ty) || holds(cells, *to, pos))
+            })
+            .map(|(to, _)| to)
+    };
+
+    let mut heading = heading(&cells[pos]);
+    if rng.f32() < creature.turn_chance || open(cells, heading).is_none() {
+        let ways: Vec<_> = (0..NEIGHBOURS.len())
+            .filter(|&way| open(cells, way).is_some())
+            .collect();
+        if ways.is_empty() {
+            return;
+        }
+        heading = ways[rng.usize(..ways.len())];
+        cells[pos].lifetime = breath << 3 | heading as u8;
+    }
+
+    let to = open(cells, heading).unwrap();
+    swap_cells(cells, tracking, pos, to);
+}
+
+// something to hold onto, water and anything lighter doesnt count
+fn solid(cell_type: CellType) -> bool {
+    !matches!(
+        cell_type,
+        CellType::Air | CellType::Smoke | CellType::Steam | CellType::Fire | CellType::Water
+    )
+}
+
+fn touches(cells: &GridWindow, pos: (usize, usize), found: impl Fn(&Cell) -> bool) -> bool {
+    NEIGHBOURS.into_iter().any(|direction| {
+        cells
+            .ray(pos, direction, 1)
+            .next()
+            .is_some_and(|(_, cell)| found(cell))
+    })
+}
+
+// whether there is something solid next to to other than the creature stepping there from from
+fn holds(cells: &GridWindow, to: (usize, usize), from: (usize, usize)) -> bool {
+    NEIGHBOURS.into_iter().any(|direction| {
+        cells
+            .ray(to, direction, 1)
+            .next()
+            .is_some_and(|(next, cell)| next != from && solid(cell.ty))
+    })
+}
@@ -0,0 +1,110 @@
+use fastrand::Rng;
+
+use crate::{
+    grid::GridWindow,
+    particles::{Particle, ParticleKind},
+    set_cell, Cell, CellType, TrackingWindow, HEIGHT,
+};
+
+// chance of each loose cell in the outer half of a blast being picked up and thrown, the rest are only pushed
+const DEBRIS_CHANCE: f32 = 0.5;
+// pull of gravity on debris in cells a tick every tick, heavier than an ember so it comes down in a few seconds
+pub const DEBRIS_GRAVITY: f32 = 0.15;
+// debris still in the air after this long comes down wherever it is
+const DEBRIS_LIFETIME: u16 = 240;
+
+// what a blast can pick up and throw
+fn flung(cell_type: CellType) -> bool {
+    matches!(
+        cell_type,
+        CellType::Sand
+            | CellType::Salt
+            | CellType::Soap
+            | CellType::Seed
+            | CellType::Ash
+            | CellType::Snow
+            | CellType::Confetti
+            | CellType::Dirt
+            | CellType::Mud
+            | CellType::Stone
+            | CellType::Water
+            | CellType::Acid
+            | CellType::Oil
+            | CellType::Honey
+            | CellType::Napalm
+    )
+}
+
+// loose cells in the outer half of the blast are lifted out of the grid and thrown straight away from the centre as
+// debris, faster the closer they were. the inner half is left for explode to turn into fire
+pub fn throw(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    center: (usize, usize),
+    radius: usize,
+    power: f32,
+    debris: &mut Vec<Particle>,
+    rng: &Rng,
+) {
+    let x_range = center.0.saturating_sub(radius)..(center.0 + radius + 1).min(cells.width());
+    let y_range = center.1.saturating_sub(radius)..(center.1 + radius + 1).min(HEIGHT);
+
+    for y in y_range {
+        for x in x_range.clone() {
+            let (dx, dy) = (x as f32 - center.0 as f32, y as f32 - center.1 as f32);
+            let distance = (dx * dx + dy * dy).sqrt();
+            if distance < radius as f32 / 2.0 || distance > radius as f32 {
+                continue;
+            }
+
+            let cell_type = cells[(x, y)].ty;
+            if !flung(cell_type) || rng.f32() >= DEBRIS_CHANCE {
+                continue;
+            }
+
+            let strength = power * (1.0 - distance / radius as f32);
+            debris.push(Particle::moving(
+                ParticleKind::Debris(cell_type),
+                (x, y),
+                (dx / distance * strength, dy / distance * strength),
+                DEBRIS_LIFETIME,
+            ));
+            set_cell(cells, tracking, (x, y), Cell::from(CellType::Air, rng));
+        }
+    }
+}
+
+// debris entering the cell at pos from the cell at from flies through air, gases and fire and comes down on anything
+// else, landing back in the grid in the cell it came from. true if it landed
+pub fn debris_strikes(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    from: (usize, usize),
+    cell_type: CellType,
+    rng: &Rng,
+) -> bool {
+    match cells[pos].ty {
+        CellType::Air | CellType::Smoke | CellType::Steam | CellType::Fire => false,
+        _ => {
+            land(cells, tracking, from, cell_type, rng);
+            true
+        }
+    }
+}
+
+// debris lands as the cell it was thrown as, unless somewhere a cell already is
+pub fn land(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    cell_type: CellType,
+    rng: &Rng,
+) {
+    if matches!(
+        cells[pos].ty,
+        CellType::Air | CellType::Smoke | CellType::Steam | CellType::Fire
+    ) {
+        set_cell(cells, tracking, pos, Cell::from(cell_type, rng));
+    }
+}
@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use enum_iterator::{next, previous, Sequence};
+
+use crate::{to_1d_index_pixel_buffer, write_to_pixel_buffer, WIDTH};
+
+// how long a tick can take to update before the sim starts cutting corners, leaves room to draw inside 60 fps
+const UPDATE_BUDGET: Duration = Duration::from_millis(12);
+// back off a level once the update is comfortably under budget, low enough that undoing a level that halved the work
+// wont put it straight back over
+const RECOVER_FRACTION: f32 = 0.4;
+const SMOOTHING: f32 = 0.1;
+// ticks to wait after changing level so the average catches up before deciding again
+const SETTLE_TICKS: u32 = 60;
+// furthest liquids can spread sideways in one tick once the spread is cut down
+pub const SHORT_SPREAD: usize = 2;
+
+const INDICATOR_COLOR: [u8; 3] = [0xff, 0x8c, 0x00];
+const INDICATOR_SIZE: usize = 4;
+const INDICATOR_SPACING: usize = 2;
+
+// each level keeps everything the levels before it do
+#[derive(Clone, Copy, PartialEq, PartialOrd, Sequence)]
+pub enum Degradation {
+    None,
+    // liquids spread at most SHORT_SPREAD cells a tick instead of as far as their velocity takes them
+    ShortSpread,
+    // each strip is only updated every other tick
+    HalfStrips,
+    // the whole sim only ticks every other frame
+    HalfRate,
+}
+
+// watches how long the update takes and steps the degradation up or down to keep the app responsive under load
+pub struct Governor {
+    pub level: Degradation,
+    smoothed_millis: f32,
+    ticks_since_change: u32,
+}
+
+impl Governor {
+    pub fn new() -> Self {
+        Governor {
+            level: Degradation::None,
+            smoothed_millis: 0.0,
+            ticks_since_change: 0,
+        }
+    }
+
+    // time the last tick took to update, added up across every frame it was spread over
+    pub fn record(&mut self, update_time: Duration) {
+        let millis = update_time.as_secs_f32() * 1000.0;
+        self.smoothed_millis = self.smoothed_millis * (1.0 - SMOOTHING) + millis * SMOOTHING;
+        self.ticks_since_change += 1;
+
+        if self.ticks_since_change < SETTLE_TICKS {
+            return;
+        }
+
+        let budget_millis = UPDATE_BUDGET.as_secs_f32() * 1000.0;
+
+        let new_level = if self.smoothed_millis > budget_millis {
+            next(&self.level)
+        } else if self.smoothed_millis < budget_millis * RECOVER_FRACTION {
+            previous(&self.level)
+        } else {
+            None
+        };
+
+        if let Some(new_level) = new_level {
+            self.level = new_level;
+            self.ticks_since_change = 0;
+        }
+    }
+
+    // one square per level under the paused indicator
+    pub fn draw(&self, frame: &mut [u8]) {
+        let squares = self.level as usize;
+
+        let top_right = (WIDTH - 6, 22);
+        let top_left = (
+            top_right.0 - squares * (INDICATOR_SIZE + INDICATOR_SPACING) + INDICATOR_SPACING,
+            top_right.1,
+        );
+
+        for square in 0..squares {
+            let left = top_left.0 + square * (INDICATOR_SIZE + INDICATOR_SPACING);
+
+            for y in top_left.1..top_left.1 + INDICATOR_SIZE {
+                for x in left..left + INDICATOR_SIZE {
+                    write_to_pixel_buffer(frame, to_1d_index_pixel_buffer(x, y), &INDICATOR_COLOR);
+                }
+            }
+        }
+    }
+}
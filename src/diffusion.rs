@@ -0,0 +1,74 @@
+use fastrand::Rng;
+
+use crate::{generic_fall, grid::GridWindow, swap_cells, CellType, TrackingWindow, NEIGHBOURS};
+
+// how a gas spreads out, each from its own table. instead of pooling under a ceiling like an upside down liquid a gas
+// rises or wanders a cell in a random direction, so it bunches up unevenly under a roof, slowly fills the room below it
+// and finds its way out through any gap. a heavy gas does the same the other way up, settling along the floor. a gas cell
+// is only as thick as its lifetime and it fades with it, so thinning out works by wisps losing lifetime faster
+pub struct Gas {
+    // chance an update of wandering instead of rising
+    pub wander: f32,
+    // extra lifetime lost an update by a cell with fewer than THICK_NEIGHBOURS of its own kind around it
+    pub dilution: u8,
+    // sinks instead of rising
+    pub heavy: bool,
+}
+
+// a cell with this many of its own kind around it is part of a plume and doesnt thin out
+const THICK_NEIGHBOURS: usize = 2;
+
+#[allow(clippy::too_many_arguments)]
+pub fn diffuse(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    gas: &Gas,
+    empty_types: &[CellType],
+    max_velocity: f32,
+    acceleration: f32,
+    rng: &Rng,
+) -> Option<(usize, usize)> {
+    if own_kind_around(cells, pos) < THICK_NEIGHBOURS {
+        let lifetime = &mut cells[pos].lifetime;
+        *lifetime = lifetime.saturating_sub(gas.dilution);
+    }
+
+    if rng.f32() >= gas.wander {
+        if let Some(risen) = generic_fall(
+            cells,
+            tracking,
+            pos,
+            empty_types,
+            max_velocity,
+            acceleration,
+            !gas.heavy,
+            rng,
+        ) {
+            return Some(risen);
+        }
+    }
+
+    let direction = NEIGHBOURS[rng.usize(0..NEIGHBOURS.len())];
+    let (target, cell) = cells.ray(pos, direction, 1).next()?;
+    if !empty_types.contains(&cell.ty) {
+        return None;
+    }
+
+    swap_cells(cells, tracking, pos, target);
+    Some(target)
+}
+
+fn own_kind_around(cells: &GridWindow, pos: (usize, usize)) -> usize {
+    let cell_type = cells[pos].ty;
+
+    NEIGHBOURS
+        .into_iter()
+        .filter(|&direction| {
+            cells
+                .ray(pos, direction, 1)
+                .next()
+                .is_some_and(|(_, neighbour)| neighbour.ty == cell_type)
+        })
+        .count()
+}
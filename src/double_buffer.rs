@@ -0,0 +1,287 @@
+use std::sync::atomic::Ordering;
+
+use fastrand::Rng;
+use rayon::prelude::*;
+
+use crate::{
+    burnt_out, catch_fire, cell_type_ignition_temperature, grid::Grid, reactions, stream_seed,
+    Cell, CellType, Tracking, FIRE_HEAT, HEIGHT, WIDTH,
+};
+
+// alternative to the in place update, every tick reads only from the front grid and writes a whole new back grid. the
+// rules work on 2x2 margolus blocks like the gpu version, each block only writes its own 4 cells so every block row can
+// be done on its own thread with no moved flags and no odd/even passes. cells move at most one step a tick so it
+// behaves differently from the in place update, which stays the reference
+pub fn step(front: &Grid, back: &mut Grid, tracking: &Tracking, seed: u64, tick: u64) {
+    // alternate the block grid between even and odd cells so material can cross block edges
+    let offset = (tick % 2) as usize;
+
+    // with the odd offset the top row is a block row on its own, the row above it is outside the grid
+    let (top_row, rest) = back.cells_mut().split_at_mut(offset * WIDTH);
+    let mut block_rows = Vec::new();
+    if offset == 1 {
+        block_rows.push((-1, top_row));
+    }
+    for (i, rows) in rest.chunks_mut(2 * WIDTH).enumerate() {
+        block_rows.push(((offset + i * 2) as isize, rows));
+    }
+
+    block_rows.into_par_iter().for_each(|(block_top, rows)| {
+        let rng = Rng::with_seed(stream_seed(seed, tick, (block_top + 1) as u64));
+        step_block_row(front, rows, block_top, tracking, offset, &rng);
+    });
+}
+
+fn step_block_row(
+    front: &Grid,
+    rows: &mut [Cell],
+    block_top: isize,
+    tracking: &Tracking,
+    offset: usize,
+    rng: &Rng,
+) {
+    let first_y = block_top.max(0) as usize;
+    let row_count = rows.len() / WIDTH;
+
+    // nothing but air in these rows of the front grid so nothing can move into or out of them
+    if (first_y..first_y + row_count).all(|y| tracking.row_counts[y].load(Ordering::Relaxed) == 0) {
+        for (i, row) in rows.chunks_exact_mut(WIDTH).enumerate() {
+            row.copy_from_slice(front.row(first_y + i));
+        }
+        return;
+    }
+
+    let tracking_window = tracking.window(0);
+
+    for block_left in (0..=WIDTH / 2).map(|i| (i * 2) as isize - offset as isize) {
+        let positions = [
+            (block_left, block_top),
+            (block_left + 1, block_top),
+            (block_left, block_top + 1),
+            (block_left + 1, block_top + 1),
+        ];
+
+        // None is outside the grid and acts like an immovable wall
+        let mut block =
+            positions.map(|(x, y)| in_grid(x, y).then(|| front.row(y as usize)[x as usize]));
+
+        step_block(&mut block, rng);
+
+        for ((x, y), cell) in positions.into_iter().zip(block) {
+            let Some(cell) = cell else {
+                continue;
+            };
+
+            let (x, y) = (x as usize, y as usize);
+            if cell != front.row(y)[x] {
+                tracking_window.mark_dirty(x, y);
+            }
+            rows[(y - first_y) * WIDTH + x] = cell;
+        }
+    }
+
+    // every row belongs to exactly one block row so the counts can be stored outright
+    for (i, row) in rows.chunks_exact(WIDTH).enumerate() {
+        let count = row.iter().filter(|cell| cell.ty != CellType::Air).count();
+        tracking.row_counts[first_y + i].store(count as u32, Ordering::Relaxed);
+    }
+}
+
+fn in_grid(x: isize, y: isize) -> bool {
+    x >= 0 && y >= 0 && x < WIDTH as isize && y < HEIGHT as isize
+}
+
+// top left, top right, bottom left, bottom right
+fn step_block(block: &mut [Option<Cell>; 4], rng: &Rng) {
+    for cell in block.iter_mut().flatten() {
+        age(cell, rng);
+    }
+
+    react(block, rng);
+
+    // fire reacts with everything in its block, over two ticks that covers all 8 neighbours. a block only sees half
+    // the neighbours at a time so fire isnt put out for lack of air here, it just cant light anything without air
+    let has_air = block.iter().flatten().any(|cell| cell.ty == CellType::Air);
+    for i in 0..4 {
+        if block[i].is_some_and(|cell| cell.ty == CellType::Fire) {
+            burn(block, i, has_air, rng);
+        }
+    }
+
+    for cell in block.iter_mut().flatten() {
+        burn_out(cell, rng);
+    }
+
+    // straight down, gases are lighter than air so the same rule floats them up
+    let mut moved = false;
+    for (top, bottom) in [(0, 2), (1, 3)] {
+        if heavier(block[top], block[bottom]) {
+            block.swap(top, bottom);
+            moved = true;
+        }
+    }
+
+    // diagonals, pick a side at random like generic_fall
+    if !moved {
+        let diagonals = if rng.bool() {
+            [(0, 3), (1, 2)]
+        } else {
+            [(1, 2), (0, 3)]
+        };
+
+        if let Some((top, bottom)) = diagonals.into_iter().find(|&(top, bottom)| {
+            heavier(block[top], block[bottom]) && !walled_off(block, top, bottom)
+        }) {
+            block.swap(top, bottom);
+            moved = true;
+        }
+    }
+
+    // liquids and gases spread sideways when they couldnt fall
+    if !moved && rng.bool() {
+        for (left, right) in [(0, 1), (2, 3)] {
+            if fluid(block[left]) && fluid(block[right]) {
+                block.swap(left, right);
+            }
+        }
+    }
+}
+
+// smoke and steam fade out, steam sometimes condenses back into water
+fn age(cell: &mut Cell, rng: &Rng) {
+    if cell.ty != CellType::Smoke && cell.ty != CellType::Steam {
+        return;
+    }
+
+    if cell.lifetime > 0 {
+        cell.lifetime -= 1;
+        return;
+    }
+
+    *cell = if cell.ty == CellType::Steam && rng.f32() < 0.5_f32.powf(6.0) {
+        Cell::from(CellType::Water, rng)
+    } else {
+        Cell::from(CellType::Air, rng)
+    };
+}
+
+// each cell reacts with the first other cell in its block the reaction table has it reacting with, over two ticks that
+// covers all 8 neighbours like fire
+fn react(block: &mut [Option<Cell>; 4], rng: &Rng) {
+    for index in 0..4 {
+        let Some(cell) = block[index] else {
+            continue;
+        };
+        if !reactions::starts_reaction(cell.ty) {
+            continue;
+        }
+
+        for other in (0..4).filter(|&other| other != index) {
+            let Some(reaction) =
+                block[other].and_then(|other_cell| reactions::reaction(cell.ty, other_cell.ty))
+            else {
+                continue;
+            };
+            if rng.f32() >= reaction.probability {
+                continue;
+            }
+
+            for (i, product) in [(index, reaction.products.0), (other, reaction.products.1)] {
+                if block[i].is_some_and(|cell| cell.ty != product) {
+                    block[i] = Some(Cell::from(product, rng));
+                }
+            }
+
+            for cell in block.iter_mut().flatten() {
+                if cell_type_ignition_temperature(cell.ty).is_some() {
+                    cell.temperature = cell.temperature.saturating_add(reaction.heat);
+                }
+            }
+            break;
+        }
+    }
+}
+
+// fire at index heats burnables in its block
+fn burn(block: &mut [Option<Cell>; 4], index: usize, has_air: bool, rng: &Rng) {
+    for other in (0..4).filter(|&other| other != index) {
+        let Some(other_cell) = &mut block[other] else {
+            continue;
+        };
+
+        let Some(ignition_temperature) = cell_type_ignition_temperature(other_cell.ty) else {
+            continue;
+        };
+
+        // twice the heat of the in place update, each neighbour only shares a block with the fire every other tick
+        other_cell.temperature = other_cell
+            .temperature
+            .saturating_add(rng.u8(0..=FIRE_HEAT * 4));
+
+        if other_cell.temperature >= ignition_temperature && has_air {
+            block[other] = Some(catch_fire(other_cell, rng));
+        }
+    }
+}
+
+// fire burns a fuel a tick and goes out once its out, leaving what the element it was burning leaves
+fn burn_out(cell: &mut Cell, rng: &Rng) {
+    if cell.ty != CellType::Fire {
+        return;
+    }
+
+    if cell.fuel > 0 {
+        cell.fuel -= 1;
+        return;
+    }
+
+    *cell = burnt_out(cell, rng);
+}
+
+// None for anything that never moves
+fn density(cell_type: CellType) -> Option<u8> {
+    match cell_type {
+        CellType::Smoke | CellType::Steam => Some(0),
+        CellType::Air => Some(1),
+        CellType::Water | CellType::Acid | CellType::Oil | CellType::Nitro => Some(2),
+        CellType::Sand
+        | CellType::Salt
+        | CellType::Gunpowder
+        | CellType::Seed
+        | CellType::Ash
+        | CellType::Snow
+        | CellType::Dirt => Some(3),
+        _ => None,
+    }
+}
+
+// a should sink below b
+fn heavier(a: Option<Cell>, b: Option<Cell>) -> bool {
+    match (a.and_then(|a| density(a.ty)), b.and_then(|b| density(b.ty))) {
+        (Some(a), Some(b)) => a > b,
+        _ => false,
+    }
+}
+
+// the other two cells of the block both never move, they touch at the corners so still make a wall between the
+// diagonal
+fn walled_off(block: &[Option<Cell>; 4], top: usize, bottom: usize) -> bool {
+    [top ^ 1, bottom ^ 1]
+        .into_iter()
+        .all(|i| block[i].is_none_or(|cell| density(cell.ty).is_none()))
+}
+
+fn fluid(cell: Option<Cell>) -> bool {
+    cell.is_some_and(|cell| {
+        matches!(
+            cell.ty,
+            CellType::Air
+                | CellType::Water
+                | CellType::Acid
+                | CellType::Oil
+                | CellType::Nitro
+                | CellType::Smoke
+                | CellType::Steam
+        )
+    })
+}
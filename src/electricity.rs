@@ -0,0 +1,243 @@
+use std::collections::BinaryHeap;
+use std::sync::atomic::Ordering;
+
+use enum_iterator::{all, Sequence};
+use fastrand::Rng;
+
+use crate::{
+    blowable, cell_type_charge_loss, cell_type_ignition_temperature, direction_index, grid::Grid,
+    heat_cell, spout, timer, Cell, CellType, Tracking, DIRECTIONS, HEIGHT, NEIGHBOURS, WIDTH,
+};
+
+pub const MAX_CHARGE: u8 = u8::MAX;
+// conductors at least this charged spark, heating the burnables touching them by up to twice SPARK_HEAT a tick
+const SPARK_CHARGE: u8 = 128;
+const SPARK_HEAT: u8 = 4;
+
+// a gate is fully charged when its inputs are right and otherwise empty, the charge only goes out of its front so it
+// never feeds back into its own inputs. an and gate needs both its sides charged, an or gate either side or its back and
+// a not gate an empty back
+#[derive(Clone, Copy, PartialEq, Sequence)]
+pub enum Gate {
+    And,
+    Or,
+    Not,
+}
+
+// gates dont age so keep the index of the direction they face in the bottom 2 bits of their lifetime and which gate they
+// are above that
+pub fn gate_lifetime(gate: Gate, facing: (isize, isize)) -> u8 {
+    direction_index(facing) | (gate as u8) << 2
+}
+
+pub fn gate(cell: &Cell) -> Gate {
+    all::<Gate>().nth((cell.lifetime >> 2) as usize).unwrap()
+}
+
+fn gate_facing(cell: &Cell) -> (isize, isize) {
+    DIRECTIONS[(cell.lifetime & 3) as usize]
+}
+
+// the way a gate, timer or delay gives off charge, timers and delays keep it like spouts
+pub fn one_way_facing(cell: &Cell) -> (isize, isize) {
+    if cell.ty == CellType::Gate {
+        gate_facing(cell)
+    } else {
+        spout::spout_facing(cell)
+    }
+}
+
+// gates, timers and delays only give charge to the cell in front of them
+fn one_way(cell: &Cell) -> bool {
+    matches!(cell.ty, CellType::Gate | CellType::Timer | CellType::Delay)
+}
+
+// switches keep whether they are on in their lifetime, see toggle_switches
+pub fn switch_on(cell: &Cell) -> bool {
+    cell.lifetime != 0
+}
+
+// charge carried by conductors, worked out fresh at the start of every in place tick by spreading out from everything
+// giving off charge, strongest first. each conductor gets the most charge anything can reach it with less what its
+// element loses passing it on, so charge fades along a wire and everything past a cut drops straight away. the charge
+// belongs to the position rather than the cell, a conductor that moves leaves it behind and picks up whatever is where
+// it lands instead.
+//
+// batteries are always fully charged and gates fully charged while they are on, working it out from what their inputs
+// held last tick so every gate in a circuit adds a tick of delay. timers and delays are fully charged for the tick they
+// go off, see timer, and sensors while what they look for rests on them. switches and pressure plates only conduct
+// while they are on or something is resting on them
+pub fn update(cells: &mut Grid, tracking: &mut Tracking, rng: &Rng) {
+    let mut changed = Vec::new();
+    let mut sparks = Vec::new();
+
+    {
+        let Tracking {
+            charge,
+            previous_charge,
+            row_counts,
+            gravity,
+            ..
+        } = &mut *tracking;
+        std::mem::swap(charge, previous_charge);
+        charge.fill(0);
+
+        let mut queue = BinaryHeap::new();
+        for y in 0..HEIGHT {
+            // nothing but air
+            if row_counts[y].load(Ordering::Relaxed) == 0 {
+                continue;
+            }
+
+            for (x, cell) in cells.row(y).iter().enumerate() {
+                let source = match cell.ty {
+                    CellType::Battery => true,
+                    CellType::Gate => gate_on(cells, previous_charge, cell, (x, y)),
+                    CellType::Timer | CellType::Delay => timer::charging(cell),
+                    CellType::Sensor => sensing(cells, cell, (x, y), *gravity),
+                    _ => false,
+                };
+
+                if source {
+                    charge[y * WIDTH + x] = MAX_CHARGE;
+                    queue.push((MAX_CHARGE, x, y));
+                }
+            }
+        }
+
+        while let Some((cell_charge, x, y)) = queue.pop() {
+            // reached again with more charge after this was queued
+            if cell_charge < charge[y * WIDTH + x] {
+                continue;
+            }
+
+            let cell = &cells.row(y)[x];
+            for direction in DIRECTIONS {
+                if one_way(cell) && direction != one_way_facing(cell) {
+                    continue;
+                }
+                let Some((x, y)) = beside((x, y), direction) else {
+                    continue;
+                };
+                let Some(loss) = charge_loss(cells, (x, y), *gravity) else {
+                    continue;
+                };
+
+                let neighbour_charge = cell_charge.saturating_sub(loss);
+                if neighbour_charge > charge[y * WIDTH + x] {
+                    charge[y * WIDTH + x] = neighbour_charge;
+                    queue.push((neighbour_charge, x, y));
+                }
+            }
+        }
+
+        for y in 0..HEIGHT {
+            // charge left behind in a row that is empty now doesnt change how anything is drawn
+            if row_counts[y].load(Ordering::Relaxed) == 0 {
+                continue;
+            }
+
+            let row = &charge[y * WIDTH..(y + 1) * WIDTH];
+            let previous_row = &previous_charge[y * WIDTH..(y + 1) * WIDTH];
+            for (x, (&charge, &previous_charge)) in row.iter().zip(previous_row).enumerate() {
+                if charge != previous_charge {
+                    changed.push((x, y));
+                }
+                if charge >= SPARK_CHARGE {
+                    sparks.push((x, y));
+                }
+            }
+        }
+    }
+
+    let mut cells = cells.window();
+    let tracking = tracking.window(0);
+
+    for (x, y) in changed {
+        tracking.mark_dirty(x, y);
+    }
+
+    for pos in sparks {
+        for direction in NEIGHBOURS {
+            let Some((neighbour_pos, neighbour)) = cells.ray(pos, direction, 1).next() else {
+                continue;
+            };
+            if cell_type_ignition_temperature(neighbour.ty).is_some() {
+                let heat = rng.u8(0..=SPARK_HEAT * 2);
+                heat_cell(&mut cells, &tracking, neighbour_pos, heat, rng);
+            }
+        }
+    }
+}
+
+fn beside((x, y): (usize, usize), (dx, dy): (isize, isize)) -> Option<(usize, usize)> {
+    let (x, y) = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+    (x < WIDTH && y < HEIGHT).then_some((x, y))
+}
+
+// the inputs are the charge last tick of the cells beside it, a gate only gives charge to the cell in front of it
+fn gate_on(cells: &Grid, previous_charge: &[u8], gate_cell: &Cell, pos: (usize, usize)) -> bool {
+    let input = |(dx, dy): (isize, isize)| {
+        let Some((x, y)) = beside(pos, (dx, dy)) else {
+            return false;
+        };
+        let cell = &cells.row(y)[x];
+        if one_way(cell) && one_way_facing(cell) != (-dx, -dy) {
+            return false;
+        }
+
+        previous_charge[y * WIDTH + x] > 0
+    };
+
+    let facing = gate_facing(gate_cell);
+    let back = input((-facing.0, -facing.1));
+    let left = input((facing.1, -facing.0));
+    let right = input((-facing.1, facing.0));
+
+    match gate(gate_cell) {
+        Gate::And => left && right,
+        Gate::Or => left || right || back,
+        Gate::Not => !back,
+    }
+}
+
+// None for anything that doesnt take charge from its neighbours, sources decide their own
+fn charge_loss(cells: &Grid, pos: (usize, usize), gravity: (isize, isize)) -> Option<u8> {
+    let cell = &cells.row(pos.1)[pos.0];
+    match cell.ty {
+        CellType::Battery
+        | CellType::Gate
+        | CellType::Timer
+        | CellType::Delay
+        | CellType::Sensor => None,
+        CellType::Switch if !switch_on(cell) => None,
+        CellType::Plate if !pressed(cells, pos, gravity) => None,
+        _ => cell_type_charge_loss(cell.ty),
+    }
+}
+
+// anything but air, gas or fire on the side of the plate gravity pushes it down onto
+fn pressed(cells: &Grid, pos: (usize, usize), gravity: (isize, isize)) -> bool {
+    beside(pos, (-gravity.0, -gravity.1)).is_some_and(|(x, y)| {
+        !matches!(
+            cells.row(y)[x].ty,
+            CellType::Air | CellType::Smoke | CellType::Steam | CellType::Fire
+        )
+    })
+}
+
+// sensors keep the element they look for in their lifetime, air for any powder, liquid or rigid body. it has to be on the
+// side of the sensor gravity pushes it down onto, like a plate
+fn sensing(cells: &Grid, sensor: &Cell, pos: (usize, usize), gravity: (isize, isize)) -> bool {
+    let Some((x, y)) = beside(pos, (-gravity.0, -gravity.1)) else {
+        return false;
+    };
+    let resting = cells.row(y)[x].ty;
+
+    if sensor.lifetime == CellType::Air as u8 {
+        resting == CellType::Body
+            || blowable(resting) && !matches!(resting, CellType::Smoke | CellType::Steam)
+    } else {
+        resting as u8 == sensor.lifetime
+    }
+}
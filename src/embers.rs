@@ -0,0 +1,101 @@
+use fastrand::Rng;
+
+use crate::{
+    across, cell_type_ignition_temperature,
+    grid::{Grid, GridWindow},
+    heat_cell,
+    particles::{self, Particle, ParticleKind},
+    set_cell, Cell, CellType, Tracking, TrackingWindow, HEIGHT, WIDTH,
+};
+
+// chance a tick of a flame with air above it throwing off an ember, a big fire throws a few a tick
+const EMBER_CHANCE: f32 = 0.02;
+// in cells a tick, embers are thrown up against gravity and scattered sideways and by the wind
+const EMBER_RISE: f32 = 1.0;
+const EMBER_SCATTER: f32 = 0.4;
+// pull of gravity on an ember in cells a tick every tick, it tops out about 12 cells above the flame
+pub const EMBER_GRAVITY: f32 = 0.04;
+const EMBER_MIN_LIFETIME: u16 = 40;
+const EMBER_MAX_LIFETIME: u16 = 80;
+// plants catch from a single ember landing on them, wood takes a couple
+const EMBER_HEAT: u8 = 64;
+
+// flames with room above them now and then throw off an ember
+pub fn update(cells: &mut Grid, tracking: &mut Tracking, rng: &Rng) {
+    let mut embers = Vec::new();
+
+    {
+        let cells = cells.window();
+        let tracking = tracking.window(0);
+
+        for y in 0..HEIGHT {
+            // nothing but air
+            if tracking.row_count(y) == 0 {
+                continue;
+            }
+
+            for x in 0..WIDTH {
+                if cells[(x, y)].ty != CellType::Fire || rng.f32() >= EMBER_CHANCE {
+                    continue;
+                }
+
+                let gravity = tracking.gravity_at((x, y));
+                let above = cells.ray((x, y), (-gravity.0, -gravity.1), 1).next();
+                if !above.is_some_and(|(_, cell)| cell.ty == CellType::Air) {
+                    continue;
+                }
+
+                let across = across(gravity);
+                let rise = EMBER_RISE * (0.5 + rng.f32());
+                let sideways = (rng.f32() * 2.0 - 1.0) * EMBER_SCATTER + tracking.wind();
+                let velocity = (
+                    across.0 as f32 * sideways - gravity.0 as f32 * rise,
+                    across.1 as f32 * sideways - gravity.1 as f32 * rise,
+                );
+                let lifetime = rng.u16(EMBER_MIN_LIFETIME..=EMBER_MAX_LIFETIME);
+
+                embers.push(Particle::moving(
+                    ParticleKind::Ember,
+                    (x, y),
+                    velocity,
+                    lifetime,
+                ));
+            }
+        }
+    }
+
+    for ember in embers {
+        particles::emit(&mut tracking.particles, ember);
+    }
+}
+
+// an ember entering the cell at pos from the cell at from, it flies through air, gases and fire. burnables it lands on
+// are heated, water puts it out and anything else it settles on as ash. true if it was used up
+pub fn ember_strikes(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    from: (usize, usize),
+    rng: &Rng,
+) -> bool {
+    let cell_type = cells[pos].ty;
+    match cell_type {
+        CellType::Air | CellType::Smoke | CellType::Steam | CellType::Fire => false,
+        CellType::Water => true,
+        _ if cell_type_ignition_temperature(cell_type).is_some() => {
+            heat_cell(cells, tracking, pos, EMBER_HEAT, rng);
+            true
+        }
+        _ => {
+            burn_out(cells, tracking, from, rng);
+            true
+        }
+    }
+}
+
+// an ember that has faded out leaves a speck of ash, unless it is somewhere a cell already is
+pub fn burn_out(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), rng: &Rng) {
+    if cells[pos].ty == CellType::Air {
+        set_cell(cells, tracking, pos, Cell::from(CellType::Ash, rng));
+    }
+}
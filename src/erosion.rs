@@ -0,0 +1,75 @@
+use fastrand::Rng;
+
+use crate::{
+    across, cell_type_erosion_resistance, grid::GridWindow, muddied, set_cell, Cell, CellType,
+    TrackingWindow, MAX_WETNESS,
+};
+
+// chance a tick of water flowing a cell across something with no erosion resistance washing a grain of it away, water
+// spreading further at once scours that many times harder
+const SCOUR_CHANCE: f32 = 0.01;
+// chance a tick of still water letting its grain sink a cell or settle out
+const SETTLE_CHANCE: f32 = 0.1;
+
+// water flowing across a bed of something soft now and then washes a grain of it out from under itself and carries it
+// along in its fuel, a grain at most. where the water comes to rest the grain sinks back out of it and is laid down as
+// wet sand, so running water cuts a channel and builds up a bank where it slows. dirt is churned up into mud where it
+// lies instead
+pub fn scour(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    from: (usize, usize),
+    to: (usize, usize),
+    rng: &Rng,
+) {
+    let down = tracking.gravity_at(to);
+    let across = across(down);
+    let sideways =
+        (to.0 as isize - from.0 as isize) * across.0 + (to.1 as isize - from.1 as isize) * across.1;
+    if sideways == 0 || cells[to].fuel > 0 {
+        return;
+    }
+
+    let Some((bed, cell)) = cells.ray(to, down, 1).next() else {
+        return;
+    };
+    let Some(resistance) = cell_type_erosion_resistance(cell.ty) else {
+        return;
+    };
+    if rng.f32() >= SCOUR_CHANCE * sideways.abs() as f32 * (1.0 - resistance) {
+        return;
+    }
+
+    if cell.ty == CellType::Dirt {
+        let mud = muddied(cell, rng);
+        set_cell(cells, tracking, bed, mud);
+        return;
+    }
+
+    set_cell(cells, tracking, bed, Cell::from(CellType::Air, rng));
+    cells[to].fuel = 1;
+    tracking.mark_dirty(to.0, to.1);
+}
+
+// the grain sinks through any clear water under it, once there is none it settles out where the water is
+pub fn settle(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), rng: &Rng) {
+    if cells[pos].fuel == 0 || rng.f32() >= SETTLE_CHANCE {
+        return;
+    }
+
+    match cells.ray(pos, tracking.gravity_at(pos), 1).next() {
+        Some((_, below)) if below.ty == CellType::Water && below.fuel > 0 => (),
+        Some((below, cell)) if cell.ty == CellType::Water => {
+            cells[below].fuel = 1;
+            cells[pos].fuel = 0;
+            tracking.mark_dirty(below.0, below.1);
+            tracking.mark_dirty(pos.0, pos.1);
+        }
+        _ => {
+            let mut grain = Cell::from(CellType::Sand, rng);
+            grain.lifetime = MAX_WETNESS;
+            set_cell(cells, tracking, pos, grain);
+            cells[pos].set_moved(tracking.generation());
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use fastrand::Rng;
+
+use crate::{
+    across,
+    grid::{Grid, GridWindow},
+    particles::{self, Particle, ParticleKind},
+    set_cell, Cell, CellType, Tracking, TrackingWindow, HEIGHT, NEIGHBOURS, WIDTH,
+};
+
+// what fungus feeds on, spores only take hold on these and the fungus creeps through them
+const FEEDS_ON: [CellType; 4] = [
+    CellType::Wood,
+    CellType::Plant,
+    CellType::Vine,
+    CellType::Seed,
+];
+// chance a tick of fungus turning one of the cells around it it feeds on into more of itself, it takes a minute or so to
+// get through a log
+const SPREAD_CHANCE: f32 = 0.002;
+// chance a tick of fungus with air beside it letting off a spore
+const SPORE_CHANCE: f32 = 0.001;
+// chance of a spore landing on something fungus feeds on taking hold, the rest die
+const TAKE_HOLD_CHANCE: f32 = 0.5;
+// spores rise slowly like smoke and wander about at random as well as drifting with the wind, in cells a tick
+const SPORE_RISE: f32 = 0.1;
+const SPORE_WANDER: f32 = 0.5;
+const SPORE_MIN_LIFETIME: u16 = 120;
+const SPORE_MAX_LIFETIME: u16 = 300;
+// chance a tick of fungus looking up to see if the sky is open above it, it dies in the sun
+const SUN_CHANCE: f32 = 0.01;
+
+// fungus with room beside it now and then lets off a spore into the air
+pub fn update(cells: &mut Grid, tracking: &mut Tracking, rng: &Rng) {
+    let mut spores = Vec::new();
+
+    {
+        let cells = cells.window();
+        let tracking = tracking.window(0);
+
+        for y in 0..HEIGHT {
+            // nothing but air
+            if tracking.row_count(y) == 0 {
+                continue;
+            }
+
+            for x in 0..WIDTH {
+                if cells[(x, y)].ty != CellType::Fungus || rng.f32() >= SPORE_CHANCE {
+                    continue;
+                }
+
+                let air = NEIGHBOURS.into_iter().find_map(|direction| {
+                    cells
+                        .ray((x, y), direction, 1)
+                        .next()
+                        .filter(|(_, neighbour)| neighbour.ty == CellType::Air)
+                        .map(|(air, _)| air)
+                });
+                if let Some(air) = air {
+                    let lifetime = rng.u16(SPORE_MIN_LIFETIME..=SPORE_MAX_LIFETIME);
+                    spores.push(Particle::moving(
+                        ParticleKind::Spore,
+                        air,
+                        (0.0, 0.0),
+                        lifetime,
+                    ));
+                }
+            }
+        }
+    }
+
+    for spore in spores {
+        particles::emit(&mut tracking.particles, spore);
+    }
+}
+
+// creeps through what it feeds on around it, and dies once the sun reaches it
+pub fn grow(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), rng: &Rng) {
+    if rng.f32() < SUN_CHANCE && in_the_sun(cells, tracking, pos) {
+        set_cell(cells, tracking, pos, Cell::from(CellType::Dirt, rng));
+        return;
+    }
+
+    if rng.f32() >= SPREAD_CHANCE {
+        return;
+    }
+
+    let direction = NEIGHBOURS[rng.usize(0..NEIGHBOURS.len())];
+    let food = cells
+        .ray(pos, direction, 1)
+        .next()
+        .filter(|(_, neighbour)| FEEDS_ON.contains(&neighbour.ty))
+        .map(|(food, _)| food);
+    if let Some(food) = food {
+        set_cell(cells, tracking, food, Cell::from(CellType::Fungus, rng));
+        cells[food].set_moved(tracking.generation());
+    }
+}
+
+// nothing but air and gases between pos and the edge of the grid above it
+fn in_the_sun(cells: &GridWindow, tracking: &TrackingWindow, pos: (usize, usize)) -> bool {
+    let gravity = tracking.gravity_at(pos);
+    cells
+        .ray(pos, (-gravity.0, -gravity.1), HEIGHT.max(WIDTH))
+        .all(|(_, cell)| passes_through(cell.ty))
+}
+
+fn passes_through(cell_type: CellType) -> bool {
+    matches!(cell_type, CellType::Air | CellType::Smoke | CellType::Steam)
+}
+
+// the velocity of a spore for the next tick, a new one each tick so it wanders
+pub fn drift(gravity: (isize, isize), wind: f32, rng: &Rng) -> (f32, f32) {
+    let across = across(gravity);
+    let sideways = (rng.f32() * 2.0 - 1.0) * SPORE_WANDER + wind;
+    let rise = SPORE_RISE + (rng.f32() * 2.0 - 1.0) * SPORE_WANDER;
+    (
+        across.0 as f32 * sideways - gravity.0 as f32 * rise,
+        across.1 as f32 * sideways - gravity.1 as f32 * rise,
+    )
+}
+
+// a spore entering the cell at pos drifts through air and gases. landing on something fungus feeds on it may take hold
+// and turn it into fungus, anything else kills it, fire included. true if it was used up
+pub fn spore_strikes(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    rng: &Rng,
+) -> bool {
+    let cell_type = cells[pos].ty;
+    if passes_through(cell_type) {
+        return false;
+    }
+
+    if FEEDS_ON.contains(&cell_type) && rng.f32() < TAKE_HOLD_CHANCE {
+        set_cell(cells, tracking, pos, Cell::from(CellType::Fungus, rng));
+    }
+    true
+}
@@ -0,0 +1,222 @@
+use std::f32::consts::TAU;
+
+use crate::{stream_seed, CellType};
+
+// a preset for --gen. the ground rolls along around a row of the world with layers of sand, dirt then stone under it,
+// caves, pockets of water and methane and veins of ore in the stone and trees on the dirt. every cell only depends on where it is and
+// the seed so chunks can be generated in any order as the grid reaches them
+pub struct Terrain {
+    // world row the surface rolls around, the grid starts at row 0
+    pub surface: f32,
+    // how far the surface rolls above and below that and about how wide the hills are
+    pub hill_height: f32,
+    pub hill_width: f32,
+    // most sand lying on top, some places have none
+    pub sand_depth: f32,
+    pub dirt_depth: f32,
+    // share of the stone that is water and methane and about how big the pockets are
+    pub water: f32,
+    pub methane: f32,
+    pub pocket_size: f32,
+    // how wide the tunnels winding through the stone are, 0 for none, and about how far they go before turning
+    pub caves: f32,
+    pub cave_size: f32,
+    // share of the stone that is coal in flat seams, and how wide the lines of gold are that only turn up deep down
+    pub coal: f32,
+    pub gold: f32,
+    pub gold_depth: f32,
+    // about how long a seam or vein runs
+    pub vein_size: f32,
+    // chance of a tree on each column of dirt
+    pub tree_chance: f32,
+}
+
+// noise layers each get their own stream from the seed
+const SURFACE_STREAM: u64 = 0;
+const SAND_STREAM: u64 = 1;
+const DIRT_STREAM: u64 = 2;
+const WATER_STREAM: u64 = 3;
+const TREE_STREAM: u64 = 4;
+const CAVE_STREAM: u64 = 5;
+const COAL_STREAM: u64 = 6;
+const GOLD_STREAM: u64 = 7;
+const METHANE_STREAM: u64 = 8;
+
+// trees are a trunk with a branch off alternating sides every few cells
+const MIN_TREE_HEIGHT: i64 = 6;
+const MAX_TREE_HEIGHT: i64 = 14;
+const BRANCH_SPACING: i64 = 3;
+const BRANCH_LENGTH: i64 = 3;
+// caves and pockets stay this far into the stone so the dirt over them has stone to rest on and water cant soak away
+const STONE_ROOF: f32 = 8.0;
+// coal seams are this many times wider than they are tall
+const SEAM_STRETCH: f32 = 3.0;
+
+pub fn generate(terrain: &Terrain, seed: u64, (x, y): (i64, i64)) -> CellType {
+    let surface = surface(terrain, seed, x);
+    let depth = y - surface;
+
+    if depth < 0 {
+        return if in_tree(terrain, seed, (x, y)) {
+            CellType::Wood
+        } else {
+            CellType::Air
+        };
+    }
+
+    let sand_depth = sand_depth(terrain, seed, x);
+    let dirt_depth =
+        terrain.dirt_depth * (0.5 + noise_1d(seed, DIRT_STREAM, x as f32 / terrain.hill_width));
+
+    if (depth as f32) < sand_depth {
+        return CellType::Sand;
+    } else if (depth as f32) < sand_depth + dirt_depth {
+        return CellType::Dirt;
+    }
+
+    let (x, y) = (x as f32, y as f32);
+    let under_roof = depth as f32 >= sand_depth + dirt_depth + STONE_ROOF;
+    let scaled = |size: f32| (x / size, y / size);
+
+    // the tunnels follow where the noise crosses 0
+    if under_roof && winding(seed, CAVE_STREAM, scaled(terrain.cave_size)).abs() < terrain.caves {
+        CellType::Air
+    } else if under_roof
+        && noise_2d(seed, WATER_STREAM, scaled(terrain.pocket_size)) < terrain.water
+    {
+        CellType::Water
+    } else if under_roof
+        && noise_2d(seed, METHANE_STREAM, scaled(terrain.pocket_size)) < terrain.methane
+    {
+        CellType::Methane
+    } else if depth as f32 >= terrain.gold_depth
+        && winding(seed, GOLD_STREAM, scaled(terrain.vein_size)).abs() < terrain.gold
+    {
+        CellType::Gold
+    } else if noise_2d(
+        seed,
+        COAL_STREAM,
+        (x / terrain.vein_size / SEAM_STRETCH, y / terrain.vein_size),
+    ) > 1.0 - terrain.coal
+    {
+        CellType::Coal
+    } else {
+        CellType::Stone
+    }
+}
+
+// the first row of ground in column x
+fn surface(terrain: &Terrain, seed: u64, x: i64) -> i64 {
+    // a few octaves so the hills have bumps on them
+    let x = x as f32 / terrain.hill_width;
+    let height = noise_1d(seed, SURFACE_STREAM, x)
+        + 0.5 * noise_1d(seed, SURFACE_STREAM, x * 2.0)
+        + 0.25 * noise_1d(seed, SURFACE_STREAM, x * 4.0);
+
+    (terrain.surface + (height / 1.75 - 0.5) * 2.0 * terrain.hill_height).round() as i64
+}
+
+fn sand_depth(terrain: &Terrain, seed: u64, x: i64) -> f32 {
+    // the bottom third of the noise has no sand at all
+    let sand = noise_1d(seed, SAND_STREAM, x as f32 / terrain.hill_width * 2.0);
+    terrain.sand_depth * ((sand - 1.0 / 3.0) * 1.5).max(0.0)
+}
+
+// any tree rooted close enough to reach pos covers it
+fn in_tree(terrain: &Terrain, seed: u64, (x, y): (i64, i64)) -> bool {
+    (x - BRANCH_LENGTH..=x + BRANCH_LENGTH).any(|root| {
+        let roll = hash(seed, TREE_STREAM, root, 0);
+        // only on bare dirt
+        if roll >= terrain.tree_chance || sand_depth(terrain, seed, root) >= 1.0 {
+            return false;
+        }
+
+        let height = MIN_TREE_HEIGHT
+            + (hash(seed, TREE_STREAM, root, 1) * (MAX_TREE_HEIGHT - MIN_TREE_HEIGHT) as f32)
+                as i64;
+        let up = surface(terrain, seed, root) - y;
+        if up < 1 || up > height {
+            return false;
+        }
+
+        if x == root {
+            return true;
+        }
+
+        // branches start off the ground and alternate sides, the side the first goes is picked per tree
+        let side = if hash(seed, TREE_STREAM, root, 2) < 0.5 {
+            1
+        } else {
+            -1
+        };
+        let branch = up / BRANCH_SPACING;
+        let branch_side = if branch % 2 == 0 { side } else { -side };
+
+        up % BRANCH_SPACING == 0
+            && branch > 0
+            && (x - root).signum() == branch_side
+            && (x - root).abs() <= BRANCH_LENGTH
+    })
+}
+
+// 0 to 1, the same for the same arguments
+fn hash(seed: u64, stream: u64, x: i64, y: i64) -> f32 {
+    let mixed = stream_seed(stream_seed(seed, stream, 0), x as u64, y as u64);
+    (mixed >> 40) as f32 / (1u64 << 24) as f32
+}
+
+// value noise, random values at whole x blended smoothly in between
+fn noise_1d(seed: u64, stream: u64, x: f32) -> f32 {
+    let (cell, t) = (x.floor() as i64, smooth(fract_floor(x)));
+    lerp(
+        hash(seed, stream, cell, 0),
+        hash(seed, stream, cell + 1, 0),
+        t,
+    )
+}
+
+fn noise_2d(seed: u64, stream: u64, (x, y): (f32, f32)) -> f32 {
+    let (cell_x, cell_y) = (x.floor() as i64, y.floor() as i64);
+    let (t_x, t_y) = (smooth(fract_floor(x)), smooth(fract_floor(y)));
+    let row = |cell_y| {
+        lerp(
+            hash(seed, stream, cell_x, cell_y),
+            hash(seed, stream, cell_x + 1, cell_y),
+            t_x,
+        )
+    };
+
+    lerp(row(cell_y), row(cell_y + 1), t_y)
+}
+
+// gradient noise, the places close to 0 wind about in lines. a second finer layer is offset by half a cell so the whole
+// points, where perlin noise is always 0, dont all end up on a line
+fn winding(seed: u64, stream: u64, (x, y): (f32, f32)) -> f32 {
+    perlin_2d(seed, stream, (x, y)) + 0.5 * perlin_2d(seed, stream, (x * 2.0 + 0.5, y * 2.0 + 0.5))
+}
+
+// a random slope at every whole point blended in between, 0 at every whole point and about -0.7 to 0.7 elsewhere
+fn perlin_2d(seed: u64, stream: u64, (x, y): (f32, f32)) -> f32 {
+    let (cell_x, cell_y) = (x.floor() as i64, y.floor() as i64);
+    let (t_x, t_y) = (smooth(fract_floor(x)), smooth(fract_floor(y)));
+    let slope = |corner_x: i64, corner_y: i64| {
+        let angle = hash(seed, stream, corner_x, corner_y) * TAU;
+        angle.cos() * (x - corner_x as f32) + angle.sin() * (y - corner_y as f32)
+    };
+    let row = |cell_y| lerp(slope(cell_x, cell_y), slope(cell_x + 1, cell_y), t_x);
+
+    lerp(row(cell_y), row(cell_y + 1), t_y)
+}
+
+fn smooth(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// fract rounds towards 0, this is what is left past the floor so it is still 0 to 1 for negative x
+fn fract_floor(x: f32) -> f32 {
+    x - x.floor()
+}
@@ -0,0 +1,592 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use fastrand::Rng;
+
+use crate::{
+    across, cell_type_goo, direction_index,
+    grid::{Grid, GridWindow},
+    swap_cells, CellType, Tracking, TrackingWindow, ACCELERATION, DIRECTIONS, HEIGHT, WIDTH,
+};
+
+// a blob falls no faster than this in cells a tick, it moves a cell at a time so it stays in one piece
+const GOO_MAX_VELOCITY: f32 = 4.0;
+// chance for each cell on the side of a blob landing of being squashed out a cell for every cell a tick it was falling
+const SPLAT_CHANCE: f32 = 0.15;
+
+pub const ALL_LINKS: u8 = 0b1111;
+
+// something soft that holds together, goo and slime, jelly and the like would link up the same way from their own
+// table. every cell keeps a link to each of the 4 cells around it of the same kind it is stuck to. a blob of linked
+// cells falls as one piece, wobbles when it lands and hangs off whatever it is stuck to, stretching into a strand under
+// its own weight until it tears
+pub struct Goo {
+    pub falls_through: &'static [CellType],
+    // weight in cells a link holds up before it tears
+    pub strength: f32,
+    // weight in cells hanging off a link before it stretches
+    pub stretch: f32,
+    // weight in cells a cell stuck to a wall or ceiling holds up before it peels off
+    pub adhesion: f32,
+    // chance a tick of a stretched blob sagging another cell
+    pub sag_chance: f32,
+    // chance a tick of a cell linking up with one of its kind it touches
+    pub heal_chance: f32,
+    // chance a tick of a cell moving in to somewhere it would touch more of its kind, it pulls stray bits back in and
+    // rounds blobs off
+    pub cohesion: f32,
+    // how many cells a blob bounces back up for every cell a tick it was falling when it landed
+    pub bounce: f32,
+    // embers, spores and debris flying into it are swallowed
+    pub absorbs: bool,
+}
+
+// a particle flying into the cell at pos is swallowed if it is something soft that absorbs them
+pub fn absorbs(cells: &GridWindow, pos: (usize, usize)) -> bool {
+    cell_type_goo(cells[pos].ty).is_some_and(|goo| goo.absorbs)
+}
+
+// the per cell part, see update for the blob as a whole. links the other side has forgotten are dropped, touching cells
+// that arent strained link up now and then and cells sticking out move in to where they touch more of the blob
+pub fn ooze(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    goo: &Goo,
+    rng: &Rng,
+) {
+    for (index, &direction) in DIRECTIONS.iter().enumerate() {
+        if linked(cells, pos, index).is_some() {
+            continue;
+        }
+        cells[pos].lifetime &= !link_bit(index);
+
+        let Some((neighbour_pos, neighbour)) = cells.ray(pos, direction, 1).next() else {
+            continue;
+        };
+        // a link torn under strain doesnt heal until the weight has moved off it
+        let strained = |fuel: u8| fuel as f32 > goo.stretch;
+        if neighbour.ty == cells[pos].ty
+            && !strained(cells[pos].fuel)
+            && !strained(neighbour.fuel)
+            && rng.f32() < goo.heal_chance
+        {
+            link(cells, pos, neighbour_pos, index);
+        }
+    }
+
+    if rng.f32() >= goo.cohesion {
+        return;
+    }
+
+    let direction = DIRECTIONS[rng.usize(0..DIRECTIONS.len())];
+    let Some((target, cell)) = cells.ray(pos, direction, 1).next() else {
+        return;
+    };
+    // it would touch itself from the target too
+    let cell_type = cells[pos].ty;
+    if !goo.falls_through.contains(&cell.ty)
+        || touching(cells, target, cell_type) <= touching(cells, pos, cell_type) + 1
+    {
+        return;
+    }
+
+    move_cell(cells, tracking, pos, target);
+}
+
+// every tick each blob of linked cells either falls as one piece if nothing holds it up, or works out the weight every
+// link carries from the cells resting on or stuck to something. links carrying more than they can tear and the most
+// stretched ones now and then sag, see sag. the weight on the links a cell hangs off is kept in its fuel to draw it
+pub fn update(cells: &mut Grid, tracking: &mut Tracking, rng: &Rng) {
+    let gravity = tracking.gravity;
+    let mut cells = cells.window();
+    let tracking = tracking.window(0);
+
+    let mut starts = Vec::new();
+    for y in 0..HEIGHT {
+        // nothing but air
+        if tracking.row_count(y) == 0 {
+            continue;
+        }
+
+        for x in 0..WIDTH {
+            if cell_type_goo(cells[(x, y)].ty).is_some() {
+                starts.push((x, y));
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for start in starts {
+        // already part of a blob, which might have moved off it
+        if seen.contains(&start) {
+            continue;
+        }
+
+        let blob = connected(&cells, start);
+        seen.extend(blob.iter().copied());
+        settle(&mut cells, &tracking, blob, gravity, rng);
+    }
+}
+
+fn settle(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    blob: Vec<(usize, usize)>,
+    gravity: (isize, isize),
+    rng: &Rng,
+) {
+    let goo = cell_type_goo(cells[blob[0]].ty).unwrap();
+    let inside: HashSet<_> = blob.iter().copied().collect();
+
+    let mut anchors: BTreeSet<_> = blob
+        .iter()
+        .copied()
+        .filter(|&pos| {
+            rests(cells, pos, &inside, goo, gravity) || sticks(cells, pos, &inside, goo, gravity)
+        })
+        .collect();
+
+    // cells only stuck to something let go when they are holding up too much, then the rest of the blob has to hold it
+    let strain = loop {
+        if anchors.is_empty() {
+            fall(cells, tracking, blob, gravity, goo);
+            return;
+        }
+
+        let strain = weigh(cells, &anchors, gravity);
+        // tearing comes first
+        if strain
+            .links
+            .iter()
+            .any(|&(_, _, share)| share > goo.strength)
+        {
+            break strain;
+        }
+
+        let peeled: Vec<_> = anchors
+            .iter()
+            .copied()
+            .filter(|&pos| {
+                strain.load[&pos] > goo.adhesion && !rests(cells, pos, &inside, goo, gravity)
+            })
+            .collect();
+        if peeled.is_empty() {
+            break strain;
+        }
+
+        for pos in peeled {
+            anchors.remove(&pos);
+        }
+    };
+
+    let impact = blob
+        .iter()
+        .map(|&pos| cells[pos].velocity())
+        .fold(0.0, f32::max);
+    if impact > 1.0 {
+        let mut blob = blob;
+        let height = (impact * goo.bounce) as usize;
+        shift(
+            cells,
+            tracking,
+            &mut blob,
+            (-gravity.0, -gravity.1),
+            height,
+            goo,
+        );
+        splat(cells, tracking, &blob, impact, gravity, goo, rng);
+        return;
+    }
+
+    let hanging = strain.links;
+    let mut tension = HashMap::new();
+    for &(pos, _, share) in &hanging {
+        let most = tension.entry(pos).or_insert(0.0f32);
+        *most = most.max(share);
+    }
+
+    for &pos in &blob {
+        let fuel = tension.get(&pos).map_or(0, |&share| share as u8);
+        if cells[pos].fuel != fuel {
+            cells[pos].fuel = fuel;
+            tracking.mark_dirty(pos.0, pos.1);
+        }
+    }
+
+    // only the link carrying the most tears, the weight it held moves onto the rest and they tear over the next few
+    // ticks if they cant take it either
+    let most_strained = hanging
+        .iter()
+        .max_by(|(_, _, share_1), (_, _, share_2)| share_1.total_cmp(share_2));
+    if let Some(&(pos, index, share)) = most_strained {
+        if share > goo.strength {
+            unlink(cells, pos, index);
+            return;
+        }
+    }
+
+    if rng.f32() >= goo.sag_chance {
+        return;
+    }
+
+    let up = (-gravity.0, -gravity.1);
+    let stretched: Vec<_> = hanging
+        .iter()
+        .filter(|&&(_, index, share)| DIRECTIONS[index] == up && share > goo.stretch)
+        .collect();
+    if !stretched.is_empty() {
+        let &(pos, index, _) = stretched[rng.usize(0..stretched.len())];
+        sag(
+            cells, tracking, pos, index, &inside, &anchors, gravity, goo, rng,
+        );
+    }
+}
+
+struct Strain {
+    // weight each cell holds up, its own included
+    load: HashMap<(usize, usize), f32>,
+    // every link holding weight up, as the cell hanging off it, the index of the direction it hangs from and the weight
+    links: Vec<((usize, usize), usize, f32)>,
+}
+
+// every cell hangs off the cell above it if that is closer to the anchors, or failing that shares its weight between the
+// cells beside it that are. a cell with neither is resting on the blob below it and doesnt pull on anything. going up
+// from the bottom row means every cell has all the weight hanging off it by the time it passes it on
+fn weigh(
+    cells: &GridWindow,
+    anchors: &BTreeSet<(usize, usize)>,
+    gravity: (isize, isize),
+) -> Strain {
+    let mut depth = HashMap::new();
+    let mut queue = VecDeque::new();
+    for &anchor in anchors {
+        depth.insert(anchor, 0);
+        queue.push_back(anchor);
+    }
+
+    let mut order = Vec::new();
+    while let Some(pos) = queue.pop_front() {
+        order.push(pos);
+
+        for index in 0..DIRECTIONS.len() {
+            let Some(neighbour) = linked(cells, pos, index) else {
+                continue;
+            };
+            if !depth.contains_key(&neighbour) {
+                depth.insert(neighbour, depth[&pos] + 1);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    let fall = |&(x, y): &(usize, usize)| x as isize * gravity.0 + y as isize * gravity.1;
+    order.sort_by_key(|pos| (-fall(pos), -(depth[pos] as isize)));
+
+    let up = direction_index((-gravity.0, -gravity.1)) as usize;
+    let mut load: HashMap<_, _> = order.iter().map(|&pos| (pos, 1.0)).collect();
+    let mut links = Vec::new();
+    for pos in order {
+        if depth[&pos] == 0 {
+            continue;
+        }
+
+        let closer = |neighbour: &(usize, usize)| depth[neighbour] < depth[&pos];
+        let parents: Vec<_> = match linked(cells, pos, up).filter(closer) {
+            Some(above) => vec![(up, above)],
+            None => (0..DIRECTIONS.len())
+                .filter(|&index| index != up && DIRECTIONS[index] != gravity)
+                .filter_map(|index| {
+                    linked(cells, pos, index)
+                        .filter(closer)
+                        .map(|neighbour| (index, neighbour))
+                })
+                .collect(),
+        };
+        let share = load[&pos] / parents.len().max(1) as f32;
+
+        for (index, parent) in parents {
+            *load.get_mut(&parent).unwrap() += share;
+            links.push((pos, index, share));
+        }
+    }
+
+    Strain { load, links }
+}
+
+// nothing holds the blob up, it falls a cell at a time speeding up like anything else
+fn fall(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    mut blob: Vec<(usize, usize)>,
+    gravity: (isize, isize),
+    goo: &Goo,
+) {
+    let velocity = (cells[blob[0]].velocity() + ACCELERATION).min(GOO_MAX_VELOCITY);
+    for &pos in &blob {
+        cells[pos].set_velocity(velocity);
+        cells[pos].fuel = 0;
+    }
+
+    shift(cells, tracking, &mut blob, gravity, velocity as usize, goo);
+}
+
+// moves the whole blob up to steps cells in direction, stopping as soon as any of it would run into something. blob is
+// left holding where the cells ended up
+fn shift(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    blob: &mut [(usize, usize)],
+    direction: (isize, isize),
+    steps: usize,
+    goo: &Goo,
+) {
+    // the leading cells move out of the way of the ones behind them
+    blob.sort_by_key(|&(x, y)| -(x as isize * direction.0 + y as isize * direction.1));
+
+    for _ in 0..steps {
+        let inside: HashSet<_> = blob.iter().copied().collect();
+        let mut targets = Vec::with_capacity(blob.len());
+        for &pos in blob.iter() {
+            let Some((target, cell)) = cells.ray(pos, direction, 1).next() else {
+                return;
+            };
+            if !inside.contains(&target) && !goo.falls_through.contains(&cell.ty) {
+                return;
+            }
+            targets.push(target);
+        }
+
+        for (pos, &target) in blob.iter_mut().zip(&targets) {
+            swap_cells(cells, tracking, *pos, target);
+            *pos = target;
+        }
+    }
+}
+
+// a blob landing faster than it settles is squashed out sideways, cohesion pulls the cells back in over the next few
+// ticks so it wobbles
+fn splat(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    blob: &[(usize, usize)],
+    impact: f32,
+    gravity: (isize, isize),
+    goo: &Goo,
+    rng: &Rng,
+) {
+    let across = across(gravity);
+    let sideways = |&(x, y): &(usize, usize)| x as isize * across.0 + y as isize * across.1;
+    let middle = blob.iter().map(sideways).sum::<isize>() / blob.len() as isize;
+
+    for pos in blob {
+        cells[*pos].set_velocity(1.0);
+        if rng.f32() >= SPLAT_CHANCE * impact {
+            continue;
+        }
+
+        let out = if sideways(pos) < middle {
+            (-across.0, -across.1)
+        } else {
+            across
+        };
+        let Some((target, cell)) = cells.ray(*pos, out, 1).next() else {
+            continue;
+        };
+        if goo.falls_through.contains(&cell.ty) {
+            move_cell(cells, tracking, *pos, target);
+        }
+    }
+}
+
+// the cell at pos is stretched too far from the one it hangs off, so it and the run of the blob straight below it drop a
+// cell. a cell from either side of the gap left behind is drawn into it so the blob gets longer and thinner, once there
+// is nothing left beside it the strand snaps
+#[allow(clippy::too_many_arguments)]
+fn sag(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    index: usize,
+    inside: &HashSet<(usize, usize)>,
+    anchors: &BTreeSet<(usize, usize)>,
+    gravity: (isize, isize),
+    goo: &Goo,
+    rng: &Rng,
+) {
+    let run: Vec<_> = std::iter::successors(Some(pos), |&pos| {
+        cells
+            .ray(pos, gravity, 1)
+            .next()
+            .map(|(below, _)| below)
+            .filter(|below| inside.contains(below))
+    })
+    .collect();
+    let end = *run.last().unwrap();
+    let room = cells.ray(end, gravity, 1).next();
+    if !room.is_some_and(|(_, cell)| goo.falls_through.contains(&cell.ty)) {
+        return;
+    }
+
+    // the run keeps the links along it and slides past the cells either side of it, linking up with the ones it ends up
+    // beside
+    let across = across(gravity);
+    let sides = [across, (-across.0, -across.1)].map(|side| direction_index(side) as usize);
+    unlink(cells, pos, index);
+    for &cell_pos in &run {
+        for side in sides {
+            unlink(cells, cell_pos, side);
+        }
+    }
+
+    for &cell_pos in run.iter().rev() {
+        let below = cells.ray(cell_pos, gravity, 1).next().unwrap().0;
+        swap_cells(cells, tracking, cell_pos, below);
+        link_touching(cells, below);
+    }
+
+    let donors: Vec<_> = sides
+        .into_iter()
+        .filter_map(|side| cells.ray(pos, DIRECTIONS[side], 1).next())
+        .map(|(donor, _)| donor)
+        .filter(|donor| inside.contains(donor) && !anchors.contains(donor))
+        .collect();
+    if !donors.is_empty() {
+        move_cell(cells, tracking, donors[rng.usize(0..donors.len())], pos);
+    }
+}
+
+// moves a cell somewhere empty next to it, it loses all its links and links up with everything of its kind it touches
+// there instead
+fn move_cell(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    target: (usize, usize),
+) {
+    for index in 0..DIRECTIONS.len() {
+        unlink(cells, pos, index);
+    }
+
+    swap_cells(cells, tracking, pos, target);
+    link_touching(cells, target);
+}
+
+fn link_touching(cells: &mut GridWindow, pos: (usize, usize)) {
+    for (index, &direction) in DIRECTIONS.iter().enumerate() {
+        let Some((neighbour_pos, neighbour)) = cells.ray(pos, direction, 1).next() else {
+            continue;
+        };
+        if neighbour.ty == cells[pos].ty {
+            link(cells, pos, neighbour_pos, index);
+        }
+    }
+}
+
+// the whole blob linked to start
+fn connected(cells: &GridWindow, start: (usize, usize)) -> Vec<(usize, usize)> {
+    let mut blob = vec![start];
+    let mut inside = HashSet::from([start]);
+    let mut i = 0;
+
+    while i < blob.len() {
+        let pos = blob[i];
+        i += 1;
+
+        for index in 0..DIRECTIONS.len() {
+            if let Some(neighbour) = linked(cells, pos, index) {
+                if inside.insert(neighbour) {
+                    blob.push(neighbour);
+                }
+            }
+        }
+    }
+
+    blob
+}
+
+// lying on something that isnt part of the blob, or the edge of the grid
+fn rests(
+    cells: &GridWindow,
+    pos: (usize, usize),
+    inside: &HashSet<(usize, usize)>,
+    goo: &Goo,
+    gravity: (isize, isize),
+) -> bool {
+    cells
+        .ray(pos, gravity, 1)
+        .next()
+        .is_none_or(|(below, cell)| {
+            !inside.contains(&below) && !goo.falls_through.contains(&cell.ty)
+        })
+}
+
+// stuck to the side of or hanging from something solid, or the edge of the grid. other blobs of the same kind dont count
+// or two blobs touching would hold each other up
+fn sticks(
+    cells: &GridWindow,
+    pos: (usize, usize),
+    inside: &HashSet<(usize, usize)>,
+    goo: &Goo,
+    gravity: (isize, isize),
+) -> bool {
+    let cell_type = cells[pos].ty;
+
+    DIRECTIONS
+        .into_iter()
+        .filter(|&direction| direction != gravity)
+        .any(|direction| {
+            cells
+                .ray(pos, direction, 1)
+                .next()
+                .is_none_or(|(neighbour, cell)| {
+                    !inside.contains(&neighbour)
+                        && cell.ty != cell_type
+                        && !goo.falls_through.contains(&cell.ty)
+                })
+        })
+}
+
+// how many cells of cell_type are around pos
+fn touching(cells: &GridWindow, pos: (usize, usize), cell_type: CellType) -> usize {
+    DIRECTIONS
+        .into_iter()
+        .filter(|&direction| {
+            cells
+                .ray(pos, direction, 1)
+                .next()
+                .is_some_and(|(_, neighbour)| neighbour.ty == cell_type)
+        })
+        .count()
+}
+
+fn link_bit(index: usize) -> u8 {
+    1 << index
+}
+
+fn opposite(index: usize) -> usize {
+    (index + 2) % DIRECTIONS.len()
+}
+
+// the cell linked to pos in DIRECTIONS[index], both of them have to hold the link
+fn linked(cells: &GridWindow, pos: (usize, usize), index: usize) -> Option<(usize, usize)> {
+    let cell = cells[pos];
+    if cell.lifetime & link_bit(index) == 0 {
+        return None;
+    }
+
+    let (neighbour_pos, neighbour) = cells.ray(pos, DIRECTIONS[index], 1).next()?;
+    (neighbour.ty == cell.ty && neighbour.lifetime & link_bit(opposite(index)) != 0)
+        .then_some(neighbour_pos)
+}
+
+fn link(cells: &mut GridWindow, pos: (usize, usize), neighbour_pos: (usize, usize), index: usize) {
+    cells[pos].lifetime |= link_bit(index);
+    cells[neighbour_pos].lifetime |= link_bit(opposite(index));
+}
+
+fn unlink(cells: &mut GridWindow, pos: (usize, usize), index: usize) {
+    if let Some(neighbour_pos) = linked(cells, pos, index) {
+        cells[neighbour_pos].lifetime &= !link_bit(opposite(index));
+    }
+    cells[pos].lifetime &= !link_bit(index);
+}
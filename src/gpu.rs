@@ -0,0 +1,315 @@
+use std::{borrow::Cow, num::NonZeroU32};
+
+use enum_iterator::{all, Sequence};
+use pixels::{
+    wgpu::{self, util::DeviceExt},
+    Pixels,
+};
+
+use crate::{
+    cell_type_color_fixed, cell_type_fuel, cell_type_ignition_temperature, cell_type_lifetime,
+    overlay::Overlay, CellType, ACID_COLORS, FIRE_COLORS, ROOM_TEMPERATURE, SALT_COLORS,
+    SAND_COLORS, SMOKE_COLOR_DARK, SMOKE_COLOR_LIGHT, STEAM_COLOR_DARK, STEAM_COLOR_LIGHT,
+    WATER_COLORS, WOOD_COLORS,
+};
+
+const WORKGROUP_SIZE: u32 = 8;
+// copy_buffer_to_texture needs every row of the color buffer to start on a 256 byte boundary
+const ROW_ALIGNMENT: u32 = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+const NO_BRUSH: u32 = u32::MAX;
+
+pub enum Brush {
+    Put(CellType),
+    Erase,
+}
+
+// runs the sim as margolus blocks in a compute shader, the cells only ever live on the gpu so drawing is done by a
+// brush pass and the ui is blended on top by the overlay
+pub struct GpuSim {
+    width: u32,
+    height: u32,
+    padded_width: u32,
+    seed: u32,
+    params_buffer: wgpu::Buffer,
+    color_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    step_pipeline: wgpu::ComputePipeline,
+    brush_pipeline: wgpu::ComputePipeline,
+    colorize_pipeline: wgpu::ComputePipeline,
+}
+
+impl GpuSim {
+    pub fn new(pixels: &Pixels, width: u32, height: u32, seed: u64) -> Self {
+        let device = pixels.device();
+        let padded_width = (width * 4).div_ceil(ROW_ALIGNMENT) * ROW_ALIGNMENT / 4;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("sand_sim_shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("gpu.wgsl"))),
+        });
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("sand_sim_params"),
+            size: 12 * 4,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // all air
+        let cell_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("sand_sim_cells"),
+            size: (width * height * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let palette_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("sand_sim_palette"),
+            contents: &to_bytes(&palette()),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let color_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("sand_sim_colors"),
+            size: (padded_width * height * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("sand_sim_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1, false),
+                storage_entry(2, true),
+                storage_entry(3, false),
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sand_sim_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: cell_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: palette_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: color_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("sand_sim_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point,
+            })
+        };
+
+        GpuSim {
+            width,
+            height,
+            padded_width,
+            seed: seed as u32,
+            step_pipeline: pipeline("step"),
+            brush_pipeline: pipeline("brush"),
+            colorize_pipeline: pipeline("colorize"),
+            params_buffer,
+            color_buffer,
+            bind_group,
+        }
+    }
+
+    // steps the sim once unless paused, then draws the cells with the ui on top
+    #[allow(clippy::too_many_arguments)]
+    pub fn render(
+        &self,
+        pixels: &Pixels,
+        overlay: &Overlay,
+        tick: u64,
+        paused: bool,
+        brush: Option<Brush>,
+        cursor_position: (usize, usize),
+        cursor_radius: f32,
+    ) -> Result<(), pixels::Error> {
+        let (brush_type, brush_erase) = match brush {
+            Some(Brush::Put(cell_type)) => (cell_type as u32, 0),
+            Some(Brush::Erase) => (0, 1),
+            None => (NO_BRUSH, 0),
+        };
+
+        let params = [
+            self.width,
+            self.height,
+            tick as u32,
+            self.seed,
+            // alternate the block grid between even and odd cells
+            (tick % 2) as u32,
+            cursor_position.0 as u32,
+            cursor_position.1 as u32,
+            cursor_radius as u32,
+            brush_type,
+            brush_erase,
+            self.padded_width,
+            0,
+        ];
+
+        let queue = pixels.queue();
+        queue.write_buffer(&self.params_buffer, 0, &to_bytes(&params));
+
+        pixels.render_with(|encoder, render_target, context| {
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("sand_sim_pass"),
+                });
+                pass.set_bind_group(0, &self.bind_group, &[]);
+
+                let cell_groups = (
+                    self.width.div_ceil(WORKGROUP_SIZE),
+                    self.height.div_ceil(WORKGROUP_SIZE),
+                );
+
+                if !paused {
+                    if brush_type != NO_BRUSH {
+                        pass.set_pipeline(&self.brush_pipeline);
+                        pass.dispatch_workgroups(cell_groups.0, cell_groups.1, 1);
+                    }
+
+                    // one invocation per 2x2 block, plus one for the partial blocks when the grid is shifted
+                    pass.set_pipeline(&self.step_pipeline);
+                    pass.dispatch_workgroups(
+                        (self.width / 2 + 1).div_ceil(WORKGROUP_SIZE),
+                        (self.height / 2 + 1).div_ceil(WORKGROUP_SIZE),
+                        1,
+                    );
+                }
+
+                pass.set_pipeline(&self.colorize_pipeline);
+                pass.dispatch_workgroups(cell_groups.0, cell_groups.1, 1);
+            }
+
+            // overwrites what pixels just uploaded from the frame
+            encoder.copy_buffer_to_texture(
+                wgpu::ImageCopyBuffer {
+                    buffer: &self.color_buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: NonZeroU32::new(self.padded_width * 4),
+                        rows_per_image: NonZeroU32::new(self.height),
+                    },
+                },
+                wgpu::ImageCopyTexture {
+                    texture: &context.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                context.texture_extent,
+            );
+
+            context.scaling_renderer.render(encoder, render_target);
+            overlay.render(encoder, render_target, context);
+
+            Ok(())
+        })
+    }
+}
+
+// room for this many cell types in the Palette struct in the shader
+const PALETTE_TYPES: usize = 128;
+const _: () = assert!(CellType::CARDINALITY <= PALETTE_TYPES);
+
+// matches the Palette struct in the shader
+fn palette() -> Vec<u32> {
+    let mut colors = [0; PALETTE_TYPES * 8];
+    let mut counts = [0; PALETTE_TYPES];
+    let mut lifetimes = [0; PALETTE_TYPES];
+    let mut fuels = [0; PALETTE_TYPES];
+    let mut ignition_temperatures = [0; PALETTE_TYPES];
+
+    for cell_type in all::<CellType>() {
+        let index = cell_type as usize;
+
+        let cell_type_colors: &[[u8; 3]] = match cell_type {
+            CellType::Sand => &SAND_COLORS,
+            CellType::Salt => &SALT_COLORS,
+            CellType::Acid => &ACID_COLORS,
+            CellType::Water => &WATER_COLORS,
+            CellType::Wood => &WOOD_COLORS,
+            CellType::Fire => &FIRE_COLORS,
+            CellType::Smoke => &[SMOKE_COLOR_LIGHT, SMOKE_COLOR_DARK],
+            CellType::Steam => &[STEAM_COLOR_LIGHT, STEAM_COLOR_DARK],
+            _ => &[cell_type_color_fixed(cell_type)],
+        };
+
+        for (i, color) in cell_type_colors.iter().enumerate() {
+            colors[index * 8 + i] =
+                color[0] as u32 | (color[1] as u32) << 8 | (color[2] as u32) << 16;
+        }
+
+        counts[index] = cell_type_colors.len() as u32;
+        lifetimes[index] = cell_type_lifetime(cell_type) as u32;
+        fuels[index] = cell_type_fuel(cell_type) as u32;
+        // cells on the gpu start at 0 rather than room temperature
+        ignition_temperatures[index] = cell_type_ignition_temperature(cell_type)
+            .map_or(0, |temperature| (temperature - ROOM_TEMPERATURE) as u32);
+    }
+
+    // smoke and steam only ever use the interpolated color so dont let new_cell pick the dark one
+    counts[CellType::Smoke as usize] = 1;
+    counts[CellType::Steam as usize] = 1;
+
+    [
+        &colors[..],
+        &counts[..],
+        &lifetimes[..],
+        &fuels[..],
+        &ignition_temperatures[..],
+    ]
+    .concat()
+}
+
+fn to_bytes(values: &[u32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_ne_bytes())
+        .collect()
+}
@@ -0,0 +1,375 @@
+// margolus neighbourhood version of the cpu rules, every invocation owns one 2x2 block so there are no write conflicts
+// and the block grid shifts by one cell every tick so material can cross block edges
+
+const AIR: u32 = 0u;
+const SAND: u32 = 1u;
+const WATER: u32 = 2u;
+const WOOD: u32 = 3u;
+const FIRE: u32 = 4u;
+const SMOKE: u32 = 5u;
+const STEAM: u32 = 6u;
+const ACID: u32 = 10u;
+
+const NO_BRUSH: u32 = 0xffffffffu;
+// same as the cpu
+const FIRE_HEAT: u32 = 4u;
+// anything outside the grid acts like an immovable wall
+const WALL: u32 = 0xffu;
+
+struct Params {
+    width: u32,
+    height: u32,
+    tick: u32,
+    seed: u32,
+    offset: u32,
+    brush_x: i32,
+    brush_y: i32,
+    brush_radius: i32,
+    brush_type: u32,
+    brush_erase: u32,
+    padded_width: u32,
+    _padding: u32,
+}
+
+struct Palette {
+    // 8 colors per cell type packed as 0x00bbggrr, smoke and steam store light then dark
+    colors: array<u32, 1024>,
+    counts: array<u32, 128>,
+    lifetimes: array<u32, 128>,
+    fuels: array<u32, 128>,
+    // 0 for anything that doesnt burn
+    ignition_temperatures: array<u32, 128>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> cells: array<u32>;
+@group(0) @binding(2) var<storage, read> palette: Palette;
+@group(0) @binding(3) var<storage, read_write> colors: array<u32>;
+
+// cells are packed as type | color variant << 8 | lifetime << 16, burnables and fire have no lifetime and keep
+// fuel << 16 | temperature << 24 there instead
+fn cell_type(cell: u32) -> u32 {
+    return cell & 0xffu;
+}
+
+fn cell_fuel(cell: u32) -> u32 {
+    return (cell >> 16u) & 0xffu;
+}
+
+fn with_fuel(cell: u32, fuel: u32) -> u32 {
+    return (cell & 0xff00ffffu) | (fuel << 16u);
+}
+
+fn cell_temperature(cell: u32) -> u32 {
+    return cell >> 24u;
+}
+
+fn with_temperature(cell: u32, temperature: u32) -> u32 {
+    return (cell & 0x00ffffffu) | (temperature << 24u);
+}
+
+fn cell_lifetime(cell: u32) -> u32 {
+    return cell >> 16u;
+}
+
+fn with_lifetime(cell: u32, lifetime: u32) -> u32 {
+    return (cell & 0xffffu) | (lifetime << 16u);
+}
+
+fn new_cell(ty: u32, random: u32) -> u32 {
+    let variant = random % max(palette.counts[ty], 1u);
+    return ty | (variant << 8u) | ((palette.lifetimes[ty] | palette.fuels[ty]) << 16u);
+}
+
+fn pcg_hash(input: u32) -> u32 {
+    let state = input * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn random(x: u32, y: u32, salt: u32) -> u32 {
+    return pcg_hash(params.seed ^ pcg_hash(x ^ pcg_hash(y ^ pcg_hash(params.tick ^ pcg_hash(salt)))));
+}
+
+// same odds as the cpu 1 in 64 rolls
+fn one_in_64(random: u32) -> bool {
+    return (random & 63u) == 0u;
+}
+
+fn density(ty: u32) -> u32 {
+    switch ty {
+        case 5u, 6u: { return 0u; }
+        case 0u: { return 1u; }
+        case 2u, 10u: { return 2u; }
+        case 1u, 9u, 22u, 24u, 29u: { return 3u; }
+        default: { return WALL; }
+    }
+}
+
+fn movable(ty: u32) -> bool {
+    return density(ty) != WALL;
+}
+
+fn fluid(ty: u32) -> bool {
+    return ty == AIR || ty == WATER || ty == ACID || ty == SMOKE || ty == STEAM;
+}
+
+// a should sink below b
+fn heavier(a: u32, b: u32) -> bool {
+    let ty_a = cell_type(a);
+    let ty_b = cell_type(b);
+    return movable(ty_a) && movable(ty_b) && density(ty_a) > density(ty_b);
+}
+
+// the other two cells of the block both never move, they touch at the corners so still make a wall between the
+// diagonal
+fn walled_off(a: u32, b: u32) -> bool {
+    return !movable(cell_type(a)) && !movable(cell_type(b));
+}
+
+fn in_bounds(x: i32, y: i32) -> bool {
+    return x >= 0 && y >= 0 && x < i32(params.width) && y < i32(params.height);
+}
+
+fn load(x: i32, y: i32) -> u32 {
+    if !in_bounds(x, y) {
+        return WALL;
+    }
+
+    return cells[u32(y) * params.width + u32(x)];
+}
+
+fn store(x: i32, y: i32, cell: u32) {
+    if in_bounds(x, y) {
+        cells[u32(y) * params.width + u32(x)] = cell;
+    }
+}
+
+fn age(cell: u32, random: u32) -> u32 {
+    let ty = cell_type(cell);
+
+    if ty != SMOKE && ty != STEAM {
+        return cell;
+    }
+
+    let lifetime = cell_lifetime(cell);
+    if lifetime == 0u {
+        if ty == STEAM && one_in_64(random) {
+            return new_cell(WATER, random >> 8u);
+        }
+        return AIR;
+    }
+
+    return with_lifetime(cell, lifetime - 1u);
+}
+
+// fire touching anything in its block, returns the new fire cell and writes the new neighbour through other
+fn burn(fire: u32, other: ptr<function, u32>, has_air: bool, random: u32) -> u32 {
+    let other_ty = cell_type(*other);
+
+    if other_ty == WATER {
+        return new_cell(STEAM, random);
+    }
+
+    if other_ty == WALL || palette.ignition_temperatures[other_ty] == 0u {
+        return fire;
+    }
+
+    // twice the heat of the cpu, each neighbour only shares a block with the fire every other tick
+    let temperature = min(cell_temperature(*other) + random % (FIRE_HEAT * 4u + 1u), 0xffu);
+
+    if temperature >= palette.ignition_temperatures[other_ty] && has_air {
+        *other = with_fuel(new_cell(FIRE, random >> 8u), cell_fuel(*other));
+    } else {
+        *other = with_temperature(*other, temperature);
+    }
+
+    return fire;
+}
+
+// fire burns a fuel a tick and goes out once its out, sometimes leaving smoke
+fn burn_out(cell: u32, random: u32) -> u32 {
+    if cell_type(cell) != FIRE {
+        return cell;
+    }
+
+    let fuel = cell_fuel(cell);
+    if fuel > 0u {
+        return with_fuel(cell, fuel - 1u);
+    }
+
+    if ((random >> 6u) & 7u) == 0u {
+        return new_cell(SMOKE, random >> 9u);
+    }
+
+    return AIR;
+}
+
+@compute @workgroup_size(8, 8)
+fn step(@builtin(global_invocation_id) id: vec3<u32>) {
+    let x = i32(id.x * 2u) - i32(params.offset);
+    let y = i32(id.y * 2u) - i32(params.offset);
+
+    if x >= i32(params.width) || y >= i32(params.height) {
+        return;
+    }
+
+    let r = random(id.x, id.y, 0u);
+
+    var top_left = age(load(x, y), random(id.x, id.y, 1u));
+    var top_right = age(load(x + 1, y), random(id.x, id.y, 2u));
+    var bottom_left = age(load(x, y + 1), random(id.x, id.y, 3u));
+    var bottom_right = age(load(x + 1, y + 1), random(id.x, id.y, 4u));
+
+    // fire reacts with everything in its block, over two ticks that covers all 8 neighbours. like the cpu double
+    // buffer, fire without air in its block cant light anything but isnt put out either
+    let has_air = cell_type(top_left) == AIR || cell_type(top_right) == AIR || cell_type(bottom_left) == AIR
+        || cell_type(bottom_right) == AIR;
+    if cell_type(top_left) == FIRE {
+        top_left = burn(top_left, &top_right, has_air, random(id.x, id.y, 5u));
+        top_left = burn(top_left, &bottom_left, has_air, random(id.x, id.y, 6u));
+        top_left = burn(top_left, &bottom_right, has_air, random(id.x, id.y, 7u));
+    }
+    if cell_type(top_right) == FIRE {
+        top_right = burn(top_right, &top_left, has_air, random(id.x, id.y, 8u));
+        top_right = burn(top_right, &bottom_left, has_air, random(id.x, id.y, 9u));
+        top_right = burn(top_right, &bottom_right, has_air, random(id.x, id.y, 10u));
+    }
+    if cell_type(bottom_left) == FIRE {
+        bottom_left = burn(bottom_left, &top_left, has_air, random(id.x, id.y, 11u));
+        bottom_left = burn(bottom_left, &top_right, has_air, random(id.x, id.y, 12u));
+        bottom_left = burn(bottom_left, &bottom_right, has_air, random(id.x, id.y, 13u));
+    }
+    if cell_type(bottom_right) == FIRE {
+        bottom_right = burn(bottom_right, &top_left, has_air, random(id.x, id.y, 14u));
+        bottom_right = burn(bottom_right, &top_right, has_air, random(id.x, id.y, 15u));
+        bottom_right = burn(bottom_right, &bottom_left, has_air, random(id.x, id.y, 16u));
+    }
+
+    top_left = burn_out(top_left, random(id.x, id.y, 17u));
+    top_right = burn_out(top_right, random(id.x, id.y, 18u));
+    bottom_left = burn_out(bottom_left, random(id.x, id.y, 19u));
+    bottom_right = burn_out(bottom_right, random(id.x, id.y, 20u));
+
+    var moved = false;
+
+    // straight down, gases are lighter than air so the same rule floats them up
+    if heavier(top_left, bottom_left) {
+        let temp = top_left;
+        top_left = bottom_left;
+        bottom_left = temp;
+        moved = true;
+    }
+    if heavier(top_right, bottom_right) {
+        let temp = top_right;
+        top_right = bottom_right;
+        bottom_right = temp;
+        moved = true;
+    }
+
+    // diagonals, pick a side at random like generic_fall
+    if !moved {
+        let left_first = (r & 1u) == 0u;
+
+        let left_open = !walled_off(top_right, bottom_left);
+        let right_open = !walled_off(top_left, bottom_right);
+
+        if left_first && left_open && heavier(top_left, bottom_right) {
+            let temp = top_left;
+            top_left = bottom_right;
+            bottom_right = temp;
+            moved = true;
+        } else if right_open && heavier(top_right, bottom_left) {
+            let temp = top_right;
+            top_right = bottom_left;
+            bottom_left = temp;
+            moved = true;
+        } else if !left_first && left_open && heavier(top_left, bottom_right) {
+            let temp = top_left;
+            top_left = bottom_right;
+            bottom_right = temp;
+            moved = true;
+        }
+    }
+
+    // liquids and gases spread sideways when they couldnt fall
+    if !moved && (r & 2u) == 0u {
+        if fluid(cell_type(top_left)) && fluid(cell_type(top_right)) {
+            let temp = top_left;
+            top_left = top_right;
+            top_right = temp;
+        }
+        if fluid(cell_type(bottom_left)) && fluid(cell_type(bottom_right)) {
+            let temp = bottom_left;
+            bottom_left = bottom_right;
+            bottom_right = temp;
+        }
+    }
+
+    store(x, y, top_left);
+    store(x + 1, y, top_right);
+    store(x, y + 1, bottom_left);
+    store(x + 1, y + 1, bottom_right);
+}
+
+@compute @workgroup_size(8, 8)
+fn brush(@builtin(global_invocation_id) id: vec3<u32>) {
+    if params.brush_type == NO_BRUSH || id.x >= params.width || id.y >= params.height {
+        return;
+    }
+
+    let dx = i32(id.x) - params.brush_x;
+    let dy = i32(id.y) - params.brush_y;
+
+    if dx * dx + dy * dy > params.brush_radius * params.brush_radius {
+        return;
+    }
+
+    let index = id.y * params.width + id.x;
+
+    if params.brush_erase != 0u {
+        cells[index] = AIR;
+        return;
+    }
+
+    let r = random(id.x, id.y, 21u);
+    let ty = params.brush_type;
+
+    // same as put_cell, loose materials are sprinkled rather than filled
+    if (ty == SAND || ty == WATER || ty == FIRE || ty == SMOKE) && (r & 7u) != 0u {
+        return;
+    }
+
+    // place cells only in fluids
+    let current = cell_type(cells[index]);
+    if current == AIR || current == SMOKE || current == WATER {
+        cells[index] = new_cell(ty, r >> 3u);
+    }
+}
+
+fn unpack_color(color: u32) -> vec3<f32> {
+    return vec3<f32>(f32(color & 0xffu), f32((color >> 8u) & 0xffu), f32((color >> 16u) & 0xffu));
+}
+
+@compute @workgroup_size(8, 8)
+fn colorize(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+
+    let cell = cells[id.y * params.width + id.x];
+    let ty = cell_type(cell);
+    var color: u32;
+
+    if ty == SMOKE || ty == STEAM {
+        let light = unpack_color(palette.colors[ty * 8u]);
+        let dark = unpack_color(palette.colors[ty * 8u + 1u]);
+        let factor = f32(cell_lifetime(cell)) / f32(max(palette.lifetimes[ty], 1u));
+        let mixed = vec3<u32>(mix(dark, light, factor));
+        color = mixed.x | (mixed.y << 8u) | (mixed.z << 16u);
+    } else {
+        color = palette.colors[ty * 8u + ((cell >> 8u) & 0xffu)];
+    }
+
+    colors[id.y * params.padded_width + id.x] = color | 0xff000000u;
+}
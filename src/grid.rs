@@ -0,0 +1,144 @@
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut, Range};
+
+use crate::{Cell, HEIGHT, WIDTH};
+
+// row major, the cell at (x, y) lives at y * WIDTH + x so every row is contiguous. the update sweeps rows bottom to top
+// and drawing walks the frame row by row so both read straight through memory
+pub struct Grid {
+    cells: Vec<Cell>,
+}
+
+impl Grid {
+    pub fn new(cell: Cell) -> Self {
+        Grid {
+            cells: vec![cell; WIDTH * HEIGHT],
+        }
+    }
+
+    pub fn row(&self, y: usize) -> &[Cell] {
+        &self.cells[y * WIDTH..(y + 1) * WIDTH]
+    }
+
+    pub fn cells_mut(&mut self) -> &mut [Cell] {
+        &mut self.cells
+    }
+
+    pub fn window(&mut self) -> GridWindow<'_> {
+        self.column_windows(std::iter::once(0..WIDTH))
+            .pop()
+            .unwrap()
+    }
+
+    // the column ranges have to be in order and not overlap, so no two windows can ever reach the same cell
+    pub fn column_windows(
+        &mut self,
+        column_ranges: impl IntoIterator<Item = Range<usize>>,
+    ) -> Vec<GridWindow<'_>> {
+        let cells = self.cells.as_mut_ptr();
+        let mut windows = Vec::new();
+        let mut previous_end = 0;
+
+        for columns in column_ranges {
+            assert!(previous_end <= columns.start && columns.start <= columns.end);
+            assert!(columns.end <= WIDTH);
+            previous_end = columns.end;
+
+            windows.push(GridWindow {
+                cells,
+                x_offset: columns.start,
+                width: columns.end - columns.start,
+                _grid: PhantomData,
+            });
+        }
+
+        windows
+    }
+}
+
+// a range of columns out of the grid, positions are relative to its left edge. the columns of a row major grid arent
+// contiguous so this cant be a slice, instead every access is checked to stay inside the columns it was given
+pub struct GridWindow<'a> {
+    cells: *mut Cell,
+    x_offset: usize,
+    width: usize,
+    _grid: PhantomData<&'a mut [Cell]>,
+}
+
+// windows handed out together never share a column and the grid stays mutably borrowed while they live, so each one is
+// as good as its own &mut
+unsafe impl Send for GridWindow<'_> {}
+
+impl GridWindow<'_> {
+    pub fn x_offset(&self) -> usize {
+        self.x_offset
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    fn index_of(&self, (x, y): (usize, usize)) -> usize {
+        assert!(x < self.width && y < HEIGHT);
+        y * WIDTH + self.x_offset + x
+    }
+
+    // the cells stepping out from pos in direction, up to steps of them and stopping at the edge of the window. the
+    // steps are clamped to the window once up front so the walk itself needs no bounds checks
+    pub fn ray(
+        &self,
+        (x, y): (usize, usize),
+        (dx, dy): (isize, isize),
+        steps: usize,
+    ) -> impl Iterator<Item = ((usize, usize), &Cell)> + '_ {
+        debug_assert!(dx.abs() <= 1 && dy.abs() <= 1);
+
+        let start = self.index_of((x, y));
+        let steps_x = match dx {
+            -1 => x,
+            0 => steps,
+            _ => self.width - 1 - x,
+        };
+        let steps_y = match dy {
+            -1 => y,
+            0 => steps,
+            _ => HEIGHT - 1 - y,
+        };
+        let stride = dy * WIDTH as isize + dx;
+
+        (1..=steps.min(steps_x).min(steps_y) as isize).map(move |i| {
+            let pos = (
+                (x as isize + dx * i) as usize,
+                (y as isize + dy * i) as usize,
+            );
+            let index = (start as isize + stride * i) as usize;
+            debug_assert_eq!(index, self.index_of(pos));
+
+            // safe, the steps were clamped so every position is inside this window's columns
+            (pos, unsafe { &*self.cells.add(index) })
+        })
+    }
+
+    pub fn swap(&mut self, pos_1: (usize, usize), pos_2: (usize, usize)) {
+        let (index_1, index_2) = (self.index_of(pos_1), self.index_of(pos_2));
+
+        // safe, both indices are inside this window's columns
+        unsafe { std::ptr::swap(self.cells.add(index_1), self.cells.add(index_2)) }
+    }
+}
+
+impl Index<(usize, usize)> for GridWindow<'_> {
+    type Output = Cell;
+
+    fn index(&self, pos: (usize, usize)) -> &Cell {
+        // safe, the index is inside this window's columns
+        unsafe { &*self.cells.add(self.index_of(pos)) }
+    }
+}
+
+impl IndexMut<(usize, usize)> for GridWindow<'_> {
+    fn index_mut(&mut self, pos: (usize, usize)) -> &mut Cell {
+        // safe, the index is inside this window's columns
+        unsafe { &mut *self.cells.add(self.index_of(pos)) }
+    }
+}
@@ -0,0 +1,197 @@
+use fastrand::Rng;
+
+use crate::{
+    across, cell_type_fuel, grid::GridWindow, set_cell, Cell, CellType, TrackingWindow, DIRECTIONS,
+    NEIGHBOURS,
+};
+
+// something that grows out of a seed, plants for now but coral and crystals grow the same way from their own table. the
+// seed sprouts once it touches its substrate, then the tips of the body keep growing away from gravity and now and then
+// branch off to one side until they are max_size cells from the seed. vines use the same table but creep instead
+pub struct Growth {
+    // what the seed has to touch to sprout or vines creep over, sand and dirt soaked with water count as water
+    pub substrates: &'static [CellType],
+    // the body holds water in its fuel, up to the fuel of a new cell of it. cells touching the substrate drink their
+    // fill, the rest draw it up from wetter cells of the body beside them, and a tip only grows while it has some,
+    // using one up for each cell it grows. so it stops growing once it is cut off from water for long
+    pub drinks: bool,
+    // what the body can grow into
+    pub grows_through: &'static [CellType],
+    pub body: CellType,
+    // chance a tick of the seed sprouting or a tip growing another cell
+    pub rate: f32,
+    // chance each time a tip grows of a branch growing off it to one side as well
+    pub branch_chance: f32,
+    pub max_size: u8,
+}
+
+// true if the seed sprouted, it turns into the first cell of the body
+pub fn sprout(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    growth: &Growth,
+    rng: &Rng,
+) -> bool {
+    if rng.f32() >= growth.rate || !touches_substrate(cells, pos, growth) {
+        return false;
+    }
+
+    let mut body = Cell::from(growth.body, rng);
+    body.lifetime = 1;
+    set_cell(cells, tracking, pos, body);
+    true
+}
+
+// body cells keep how many cells they are from the seed in their lifetime, the ones short of max_size with room in
+// front of them are the tips and the only ones that grow. only the stem branches
+pub fn grow(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    growth: &Growth,
+    rng: &Rng,
+) {
+    if growth.drinks {
+        drink(cells, pos, growth, rng);
+    }
+
+    let size = cells[pos].lifetime;
+    if size >= growth.max_size
+        || rng.f32() >= growth.rate
+        || (growth.drinks && cells[pos].fuel == 0)
+    {
+        return;
+    }
+
+    let gravity = tracking.gravity_at(pos);
+    let up = (-gravity.0, -gravity.1);
+    let across = across(gravity);
+
+    // the stem grows straight up from the cell under it, branches carry on away from the cell they grew off
+    let is_body = |direction: (isize, isize)| {
+        cells
+            .ray(pos, direction, 1)
+            .next()
+            .is_some_and(|(_, cell)| cell.ty == growth.body)
+    };
+    let forward = [across, (-across.0, -across.1)]
+        .into_iter()
+        .find(|side| !is_body(gravity) && is_body((gravity.0 + side.0, gravity.1 + side.1)))
+        .map_or(up, |side| (up.0 - side.0, up.1 - side.1));
+
+    // anything in front means this isnt a tip any more
+    let Some(front) = grows_into(cells, pos, forward, growth) else {
+        return;
+    };
+
+    let side = if rng.bool() {
+        across
+    } else {
+        (-across.0, -across.1)
+    };
+    // a branch growing off right above another would have it under it and grow as a second stem
+    let branch = (forward == up && !is_body(side) && rng.f32() < growth.branch_chance)
+        .then(|| grows_into(cells, pos, (up.0 + side.0, up.1 + side.1), growth))
+        .flatten();
+
+    for target in [Some(front), branch].into_iter().flatten() {
+        let mut body = Cell::from(growth.body, rng);
+        body.lifetime = size + 1;
+        if growth.drinks {
+            body.fuel = 0;
+            cells[pos].fuel = cells[pos].fuel.saturating_sub(1);
+        }
+        set_cell(cells, tracking, target, body);
+        cells[target].set_moved(tracking.generation());
+    }
+}
+
+// fills up on water from the substrate or draws a drop of it from a wetter neighbour, see Growth::drinks
+fn drink(cells: &mut GridWindow, pos: (usize, usize), growth: &Growth, rng: &Rng) {
+    if touches_substrate(cells, pos, growth) {
+        cells[pos].fuel = cell_type_fuel(growth.body);
+        return;
+    }
+
+    let direction = NEIGHBOURS[rng.usize(0..NEIGHBOURS.len())];
+    let wetter = cells
+        .ray(pos, direction, 1)
+        .next()
+        .filter(|(_, neighbour)| {
+            neighbour.ty == growth.body && neighbour.fuel > cells[pos].fuel + 1
+        })
+        .map(|(wetter, _)| wetter);
+
+    if let Some(wetter) = wetter {
+        cells[wetter].fuel -= 1;
+        cells[pos].fuel += 1;
+    }
+}
+
+// vines dont grow up on their own but creep over the surface of their substrate instead, a cell at a time in any
+// direction as long as the cell they grow into lies against it. like the plant body they keep how far they are from
+// where they started in their lifetime and stop at max_size
+pub fn creep(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    growth: &Growth,
+    rng: &Rng,
+) {
+    let size = cells[pos].lifetime;
+    if size >= growth.max_size || rng.f32() >= growth.rate {
+        return;
+    }
+
+    let direction = NEIGHBOURS[rng.usize(0..NEIGHBOURS.len())];
+    let Some(target) = grows_into(cells, pos, direction, growth) else {
+        return;
+    };
+    if !lies_against(cells, target, growth) {
+        return;
+    }
+
+    let mut body = Cell::from(growth.body, rng);
+    body.lifetime = size + 1;
+    set_cell(cells, tracking, target, body);
+    cells[target].set_moved(tracking.generation());
+}
+
+// the substrate is straight beside pos, corners dont count so vines cant hop across gaps
+fn lies_against(cells: &GridWindow, pos: (usize, usize), growth: &Growth) -> bool {
+    DIRECTIONS.into_iter().any(|direction| {
+        cells
+            .ray(pos, direction, 1)
+            .next()
+            .is_some_and(|(_, cell)| growth.substrates.contains(&cell.ty))
+    })
+}
+
+fn grows_into(
+    cells: &GridWindow,
+    pos: (usize, usize),
+    direction: (isize, isize),
+    growth: &Growth,
+) -> Option<(usize, usize)> {
+    cells
+        .ray(pos, direction, 1)
+        .next()
+        .filter(|(_, cell)| growth.grows_through.contains(&cell.ty))
+        .map(|(target, _)| target)
+}
+
+fn touches_substrate(cells: &GridWindow, pos: (usize, usize), growth: &Growth) -> bool {
+    NEIGHBOURS.into_iter().any(|direction| {
+        cells
+            .ray(pos, direction, 1)
+            .next()
+            .is_some_and(|(_, cell)| {
+                growth.substrates.contains(&cell.ty)
+                    || ((cell.ty == CellType::Mud
+                        || (matches!(cell.ty, CellType::Sand | CellType::Dirt)
+                            && cell.lifetime > 0))
+                        && growth.substrates.contains(&CellType::Water))
+            })
+    })
+}
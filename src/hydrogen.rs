@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+
+use fastrand::Rng;
+
+use crate::{
+    explode,
+    grid::{Grid, GridWindow},
+    set_cell, Cell, CellType, Tracking, NEIGHBOURS, TNT_POWER,
+};
+
+// every cell of a pocket goes off in a blast about as big as a grain of gunpowder, together they tear through everything
+// around the pocket
+const BLAST_RADIUS: usize = 3;
+const BLAST_POWER: f32 = TNT_POWER / 4.0;
+
+// hydrogen set alight last tick goes off along with every cell of hydrogen joined to it, so a pocket goes up all at once
+// instead of burning through from the end that was lit. over the whole grid so a pocket can be wider than a strip
+pub fn update(cells: &mut Grid, tracking: &mut Tracking, rng: &Rng) {
+    let mut ignitions = std::mem::take(tracking.ignitions.get_mut().unwrap());
+    if ignitions.is_empty() {
+        return;
+    }
+    // strips push these in whatever order they finish, sorted so the same seed goes up the same way every run
+    ignitions.sort_unstable();
+
+    let mut cells = cells.window();
+    let tracking = tracking.window(0);
+
+    for start in ignitions {
+        // already gone up with a pocket lit somewhere else
+        if cells[start].ty != CellType::Hydrogen {
+            continue;
+        }
+
+        let pocket = connected(&cells, start);
+        for &pos in &pocket {
+            set_cell(&mut cells, &tracking, pos, Cell::from(CellType::Fire, rng));
+        }
+        for pos in pocket {
+            explode(&mut cells, &tracking, pos, BLAST_RADIUS, BLAST_POWER, rng);
+        }
+    }
+}
+
+// every cell of hydrogen joined to start, diagonals included so a wisp only just touching the rest goes up with it
+fn connected(cells: &GridWindow, start: (usize, usize)) -> Vec<(usize, usize)> {
+    let mut pocket = vec![start];
+    let mut inside = HashSet::from([start]);
+    let mut i = 0;
+
+    while i < pocket.len() {
+        let pos = pocket[i];
+        i += 1;
+
+        for direction in NEIGHBOURS {
+            let Some((neighbour_pos, neighbour)) = cells.ray(pos, direction, 1).next() else {
+                continue;
+            };
+            if neighbour.ty == CellType::Hydrogen && inside.insert(neighbour_pos) {
+                pocket.push(neighbour_pos);
+            }
+        }
+    }
+
+    pocket
+}
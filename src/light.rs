@@ -0,0 +1,99 @@
+use std::sync::atomic::Ordering;
+
+use enum_iterator::{all, Sequence};
+use fastrand::Rng;
+
+use crate::{
+    cell_type_ignition_temperature, grid::Grid, heat_cell, Cell, CellType, Tracking, DIRECTIONS,
+    HEIGHT, WIDTH,
+};
+
+// far enough to cross the grid a few times between mirrors, and stops a beam caught between mirrors going forever
+const MAX_BEAM_LENGTH: usize = 4 * WIDTH;
+// heat a beam gives what it hits every tick, wood takes about a second to catch
+const LASER_HEAT: u8 = 2;
+
+// which way a mirror leans, rising is / and falling is \
+#[derive(Clone, Copy, PartialEq, Sequence)]
+pub enum Mirror {
+    Rising,
+    Falling,
+}
+
+// mirrors dont age so keep the way they lean in their lifetime
+pub fn mirror(cell: &Cell) -> Mirror {
+    all::<Mirror>().nth(cell.lifetime as usize).unwrap()
+}
+
+// lasers dont age so keep the index of the direction they face in their lifetime
+fn laser_facing(cell: &Cell) -> (isize, isize) {
+    DIRECTIONS[cell.lifetime as usize]
+}
+
+// every laser with charge casts a beam straight out of its front, through anything see through and off mirrors, until
+// it hits something. burnables it hits are heated until they catch. the cells a beam crosses are kept for drawing over
+// the frame
+pub fn update(cells: &mut Grid, tracking: &mut Tracking, rng: &Rng) {
+    let mut lasers = Vec::new();
+    for y in 0..HEIGHT {
+        // nothing but air
+        if tracking.row_counts[y].load(Ordering::Relaxed) == 0 {
+            continue;
+        }
+
+        for (x, cell) in cells.row(y).iter().enumerate() {
+            if cell.ty == CellType::Laser && tracking.charge[y * WIDTH + x] > 0 {
+                lasers.push(((x, y), laser_facing(cell)));
+            }
+        }
+    }
+
+    tracking.beams.clear();
+    let mut hits = Vec::new();
+
+    for (mut pos, mut direction) in lasers {
+        for _ in 0..MAX_BEAM_LENGTH {
+            let (Some(x), Some(y)) = (
+                pos.0.checked_add_signed(direction.0),
+                pos.1.checked_add_signed(direction.1),
+            ) else {
+                break;
+            };
+            if x >= WIDTH || y >= HEIGHT {
+                break;
+            }
+            pos = (x, y);
+
+            let cell = &cells.row(y)[x];
+            match cell.ty {
+                CellType::Air
+                | CellType::Smoke
+                | CellType::Steam
+                | CellType::Fire
+                | CellType::Water
+                | CellType::Glass => (),
+                CellType::Mirror => {
+                    direction = match mirror(cell) {
+                        Mirror::Rising => (-direction.1, -direction.0),
+                        Mirror::Falling => (direction.1, direction.0),
+                    };
+                }
+                _ => {
+                    hits.push(pos);
+                    break;
+                }
+            }
+
+            tracking.beams.push(pos);
+        }
+    }
+
+    let mut cells = cells.window();
+    let tracking = tracking.window(0);
+
+    for pos in hits {
+        if cell_type_ignition_temperature(cells[pos].ty).is_some() {
+            heat_cell(&mut cells, &tracking, pos, LASER_HEAT, rng);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use fastrand::Rng;
+
+use crate::{
+    across, cell_type_charge_loss, cell_type_ignition_temperature,
+    grid::{Grid, GridWindow},
+    heat_cell, set_cell, transform,
+    weather::Weather,
+    Cell, CellType, Tracking, TrackingWindow, HEIGHT, NEIGHBOURS, WIDTH,
+};
+
+// a bolt called down over a column strikes the nearest conductor sticking up within this many columns of it, or
+// otherwise whatever is highest
+const STRIKE_REACH: isize = 12;
+// after it hits the ground the bolt goes on this many cells down through sand, water and burnables
+const GROUND_DEPTH: usize = 6;
+// heat the bolt gives burnables it passes through or that touch the conductor it strikes
+const LIGHTNING_HEAT: u8 = u8::MAX;
+// ticks the bolt stays drawn
+const BOLT_FLASH: u8 = 6;
+// while it rains, the chance a tick of a bolt coming down somewhere at random for each bit of weather intensity. a
+// downpour has one every few seconds
+const STORM_CHANCE: f32 = 0.05;
+
+// the bolt called down last frame, or one now and then while it rains, comes down from the sky over the grid. the
+// cells it crosses are kept in tracking.bolt for drawing while it flashes
+pub fn update(cells: &mut Grid, tracking: &mut Tracking, rng: &Rng) {
+    tracking.bolt_flash = tracking.bolt_flash.saturating_sub(1);
+
+    let storm =
+        tracking.weather == Weather::Rain && rng.f32() < STORM_CHANCE * tracking.weather_intensity;
+    let Some(called_at) = tracking
+        .strike
+        .take()
+        .or(storm.then(|| (rng.usize(0..WIDTH), rng.usize(0..HEIGHT))))
+    else {
+        return;
+    };
+
+    let gravity = tracking.gravity;
+    let bolt = {
+        let mut cells = cells.window();
+        let tracking = tracking.window(0);
+        let sky = sky_above(called_at, gravity);
+        let bolt = trace(&cells, sky, gravity, rng);
+
+        if let Some(&end) = bolt.last() {
+            strike(&mut cells, &tracking, end, gravity, rng);
+        }
+
+        bolt
+    };
+
+    tracking.bolt = bolt;
+    tracking.bolt_flash = BOLT_FLASH;
+}
+
+// lightning goes through the air and gases, anything else stops it
+fn passes_through(cell_type: CellType) -> bool {
+    matches!(
+        cell_type,
+        CellType::Air | CellType::Smoke | CellType::Steam | CellType::Fire
+    )
+}
+
+// the cell on the edge of the grid gravity pulls everything away from, in line with pos
+fn sky_above(pos: (usize, usize), gravity: (isize, isize)) -> (usize, usize) {
+    match gravity {
+        (0, 1) => (pos.0, 0),
+        (0, _) => (pos.0, HEIGHT - 1),
+        (1, _) => (0, pos.1),
+        _ => (WIDTH - 1, pos.1),
+    }
+}
+
+fn step((x, y): (usize, usize), (dx, dy): (isize, isize)) -> Option<(usize, usize)> {
+    let x = x.checked_add_signed(dx).filter(|&x| x < WIDTH)?;
+    let y = y.checked_add_signed(dy).filter(|&y| y < HEIGHT)?;
+    Some((x, y))
+}
+
+// how far down from the sky the first thing a bolt would hit is in a column, and whether it conducts. None for a column
+// with nothing in it
+fn first_hit(
+    cells: &GridWindow,
+    sky: (usize, usize),
+    gravity: (isize, isize),
+) -> Option<(usize, bool)> {
+    std::iter::once((sky, &cells[sky]))
+        .chain(cells.ray(sky, gravity, HEIGHT.max(WIDTH)))
+        .enumerate()
+        .find(|(_, (_, cell))| !passes_through(cell.ty))
+        .map(|(distance, (_, cell))| (distance, cell_type_charge_loss(cell.ty).is_some()))
+}
+
+// picks what to strike, then zigzags down from the sky towards it a cell at a time, wandering either way as long as it
+// can still make it there. the last cell is the one it struck, unless the bolt went all the way through to the ground
+fn trace(
+    cells: &GridWindow,
+    sky: (usize, usize),
+    gravity: (isize, isize),
+    rng: &Rng,
+) -> Vec<(usize, usize)> {
+    let across = across(gravity);
+
+    // conductors first, then the highest, then the nearest the column it was called down over
+    let target = (-STRIKE_REACH..=STRIKE_REACH)
+        .filter_map(|offset| {
+            let column = step(sky, (across.0 * offset, across.1 * offset))?;
+            let (distance, conducts) = first_hit(cells, column, gravity)?;
+            (distance as isize >= offset.abs()).then_some((
+                !conducts,
+                distance,
+                offset.abs(),
+                offset,
+            ))
+        })
+        .min();
+    let (mut offset, distance) = match target {
+        Some((_, distance, _, offset)) => (offset, distance),
+        None => (0, HEIGHT.max(WIDTH)),
+    };
+
+    let mut pos = sky;
+    let mut bolt = vec![pos];
+    if !passes_through(cells[pos].ty) {
+        return bolt;
+    }
+
+    for remaining in (1..=distance).rev() {
+        let shift = if offset.abs() + 1 >= remaining as isize {
+            offset.signum()
+        } else {
+            rng.isize(-1..=1)
+        };
+        // wandering off the side of the grid it comes straight down instead
+        let (shift, next) = match step(
+            pos,
+            (gravity.0 + across.0 * shift, gravity.1 + across.1 * shift),
+        ) {
+            Some(next) => (shift, next),
+            None => match step(pos, gravity) {
+                Some(next) => (0, next),
+                None => break,
+            },
+        };
+
+        offset -= shift;
+        pos = next;
+        bolt.push(pos);
+
+        if !passes_through(cells[pos].ty) {
+            break;
+        }
+    }
+
+    bolt
+}
+
+// sand the bolt goes through is fused into glass, water flashes into steam and burnables are set alight. it carries on
+// into the ground until it reaches something else, and a conductor it reaches sparks the burnables around it
+fn strike(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    gravity: (isize, isize),
+    rng: &Rng,
+) {
+    let mut path = vec![pos];
+    path.extend(cells.ray(pos, gravity, GROUND_DEPTH).map(|(pos, _)| pos));
+
+    for pos in path {
+        let cell = cells[pos];
+        if passes_through(cell.ty) {
+            continue;
+        }
+
+        match cell.ty {
+            CellType::Sand => set_cell(cells, tracking, pos, Cell::from(CellType::Glass, rng)),
+            CellType::Water | CellType::Ice | CellType::Snow => {
+                let steam = transform(&cell, CellType::Steam, rng);
+                set_cell(cells, tracking, pos, steam);
+            }
+            _ if cell_type_ignition_temperature(cell.ty).is_some() => {
+                heat_cell(cells, tracking, pos, LIGHTNING_HEAT, rng)
+            }
+            _ => {
+                if cell_type_charge_loss(cell.ty).is_some() {
+                    spark(cells, tracking, pos, rng);
+                }
+                return;
+            }
+        }
+
+        cells[pos].set_moved(tracking.generation());
+    }
+}
+
+fn spark(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), rng: &Rng) {
+    for direction in NEIGHBOURS {
+        let Some((neighbour_pos, neighbour)) = cells.ray(pos, direction, 1).next() else {
+            continue;
+        };
+        if cell_type_ignition_temperature(neighbour.ty).is_some() {
+            heat_cell(cells, tracking, neighbour_pos, LIGHTNING_HEAT, rng);
+        }
+    }
+}
@@ -1,9 +1,68 @@
+mod absorption;
+mod camera;
+mod contagion;
+mod corrosion;
+mod creature;
+mod debris;
+mod degradation;
+mod diffusion;
+mod double_buffer;
+mod electricity;
+mod embers;
+mod erosion;
+mod fungus;
+mod generation;
+mod goo;
+mod gpu;
+mod grid;
+mod growth;
+mod hydrogen;
+mod light;
+mod lightning;
+mod moss;
+mod overlay;
+mod particles;
+mod portal;
+mod pressure;
+mod profiler;
+mod radioactivity;
+mod reactions;
+mod rigid;
+mod spout;
+mod timer;
+mod weather;
+mod world;
+
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
-use std::{f32::consts::PI, usize, vec};
 
-use enum_iterator::{all, Sequence};
+use absorption::Porous;
+use camera::Camera;
+use contagion::Contagion;
+use corrosion::Corrosive;
+use creature::Creature;
+use degradation::{Degradation, Governor, SHORT_SPREAD};
+use diffusion::Gas;
+use electricity::Gate;
+use enum_iterator::{all, next_cycle, Sequence};
 use fastrand::Rng;
-use pixels::{Pixels, SurfaceTexture};
+use generation::Terrain;
+use goo::Goo;
+use gpu::{Brush, GpuSim};
+use grid::{Grid, GridWindow};
+use growth::Growth;
+use light::Mirror;
+use overlay::Overlay;
+use particles::{Particle, ParticleKind};
+use pixels::{PixelsBuilder, SurfaceTexture};
+use profiler::{Profiler, Scope};
+use radioactivity::Radioactive;
+use rayon::prelude::*;
+use rigid::{Blast, Bodies, BodyShape};
+use weather::{Weather, WEATHER_INTENSITIES};
 use winit::event::{ElementState, KeyboardInput, MouseButton, MouseScrollDelta, WindowEvent};
 use winit::{
     dpi::LogicalSize,
@@ -11,22 +70,641 @@ use winit::{
     event_loop::EventLoop,
     window::WindowBuilder,
 };
+use world::World;
 
+// large-grid is a cell for every pixel of a 1080p screen, the target is 60 fps on a mid-range cpu
+#[cfg(not(feature = "large-grid"))]
 const WIDTH: usize = 400;
+#[cfg(not(feature = "large-grid"))]
 const HEIGHT: usize = 300;
+#[cfg(feature = "large-grid")]
+const WIDTH: usize = 1920;
+#[cfg(feature = "large-grid")]
+const HEIGHT: usize = 1080;
+// the large grid already fills a 1080p screen at one pixel per cell
+const WINDOW_SCALE: f64 = if cfg!(feature = "large-grid") {
+    1.0
+} else {
+    2.0
+};
 
 const ACCELERATION: f32 = 0.2;
 const MAX_VELOCITY: f32 = 10.0;
 
+// ash is light and drifts down slowly, fluttering a little way either way of the wind with this chance a tick
+const ASH_MAX_VELOCITY: f32 = 1.0;
+const ASH_FLUTTER_CHANCE: f32 = 0.2;
+const ASH_FLUTTER: f32 = 0.5;
+// ash coming to rest on fire with this much fuel left or less puts it out with this chance a tick
+const ASH_SMOTHER_FUEL: u8 = 8;
+const ASH_SMOTHER_CHANCE: f32 = 0.1;
+// snow flutters down even slower, blown along by the wind until it lands
+const SNOW_MAX_VELOCITY: f32 = 0.5;
+// soap touching water that moved this tick or the last lathers it into foam with this chance a tick, it is worn away
+// into foam itself after lathering this many cells
+const SOAP_LATHER_CHANCE: f32 = 0.05;
+const SOAP_LATHER: u8 = 32;
+// foam bubbles up through water and is so light it only drifts down through the air no faster than snow, and with this
+// chance a tick. each cell pops with this chance a tick, a few seconds on average
+const FOAM_RISE_VELOCITY: f32 = 1.0;
+const FOAM_FALL_CHANCE: f32 = 0.25;
+const FOAM_POP_CHANCE: f32 = 0.005;
+// confetti is lighter than snow and flutters hard either way every tick on the way down, with the wind pushing it along
+// this many times harder than it does anything else. flakes lying on top of a heap are lifted off it by the wind with
+// this chance a tick for each bit of wind, and blasts throw it this many times harder than sand
+const CONFETTI_MAX_VELOCITY: f32 = 0.4;
+const CONFETTI_FLUTTER: f32 = 1.0;
+const CONFETTI_WIND: f32 = 3.0;
+const CONFETTI_LIFT_CHANCE: f32 = 0.2;
+const CONFETTI_BLAST: f32 = 3.0;
+// it burns like paper, catching easily and gone in a flash
+const CONFETTI_FUEL: u8 = 2;
+const CONFETTI_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 16;
+
 const SMOKE_MAX_VELOCITY: f32 = 2.0;
 const SMOKE_ACCELERATION: f32 = 0.1;
 const STEAM_MAX_VELOCITY: f32 = 2.0;
 const STEAM_ACCELERATION: f32 = 0.1;
 
-const SMOKE_LIFETIME: u32 = 100;
-const STEAM_LIFETIME: u32 = 50;
+// gases dont need updating every tick, they update every few ticks and move and age that many times as far each time
+const SMOKE_TICK_INTERVAL: u64 = 3;
+const STEAM_TICK_INTERVAL: u64 = 2;
+const _: () = assert!(SMOKE_MAX_VELOCITY * SMOKE_TICK_INTERVAL as f32 <= MAX_VELOCITY);
+const _: () = assert!(STEAM_MAX_VELOCITY * STEAM_TICK_INTERVAL as f32 <= MAX_VELOCITY);
+
+const SMOKE_LIFETIME: u8 = 100;
+const STEAM_LIFETIME: u8 = 50;
+
+// smoke drifts about more than steam, which mostly rises. a stray wisp of either fades about half as fast again
+const SMOKE: Gas = Gas {
+    wander: 0.3,
+    dilution: 1,
+    heavy: false,
+};
+const STEAM: Gas = Gas {
+    wander: 0.2,
+    dilution: 1,
+    heavy: false,
+};
+// hydrogen is lighter than smoke and rises far faster, barely wandering so it gathers under a ceiling instead of
+// drifting about. it never fades or thins out, heated this far or touched by fire, lava or charge its whole pocket goes
+// up, see hydrogen::update
+const HYDROGEN_MAX_VELOCITY: f32 = 6.0;
+const HYDROGEN_ACCELERATION: f32 = 0.5;
+const HYDROGEN_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 32;
+const HYDROGEN: Gas = Gas {
+    wander: 0.05,
+    dilution: 0,
+    heavy: false,
+};
+const _: () = assert!(HYDROGEN_MAX_VELOCITY <= MAX_VELOCITY);
+// toxic gas is heavier than air, it sinks slowly and spreads out along the floor into a pool that lingers for half a
+// minute or so before it breaks down, unless water touching it takes it in first. plants it touches wither with this
+// chance a tick and creatures caught in it choke as if they had nothing to breathe
+const TOXIC_GAS_MAX_VELOCITY: f32 = 1.0;
+const TOXIC_GAS_ACCELERATION: f32 = 0.1;
+const TOXIC_GAS: Gas = Gas {
+    wander: 0.3,
+    dilution: 0,
+    heavy: true,
+};
+const TOXIC_GAS_DECAY_CHANCE: f32 = 0.0005;
+const TOXIC_GAS_DISSOLVE_CHANCE: f32 = 0.01;
+const TOXIC_GAS_WITHER_CHANCE: f32 = 0.05;
+// methane rises slowly and never thins out, so a pocket of it shut in underground waits there until it is dug into. it
+// lights at a touch even without air and burns out in a flash that lights the methane beside it, a flame front racing
+// through the pocket. it shimmers faintly, each cell picking a new shade with this chance a tick
+const METHANE_MAX_VELOCITY: f32 = 1.0;
+const METHANE_ACCELERATION: f32 = 0.1;
+const METHANE_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 16;
+const METHANE: Gas = Gas {
+    wander: 0.3,
+    dilution: 0,
+    heavy: false,
+};
+const METHANE_SHIMMER_CHANCE: f32 = 0.05;
+
+// what new cells start at, anything that changes phase drifts a degree an update back towards it. leaves room below for
+// things to be cooled
+const ROOM_TEMPERATURE: u8 = 64;
+// steam starts out hot enough that it would time out before cooling back into water on its own
+const STEAM_TEMPERATURE: u8 = WATER_BOILING_TEMPERATURE + 32;
+const _: () = assert!((STEAM_LIFETIME as u64).div_ceil(STEAM_TICK_INTERVAL) < 32);
+// steam touching a solid cooler than this, or the edge of the grid, has this chance each update of condensing into water
+const CONDENSING_TEMPERATURE: u8 = ROOM_TEMPERATURE + 16;
+const STEAM_CONDENSE_CHANCE: f32 = 0.1;
+// heaters warm and coolers chill everything touching them this much a tick, a lot faster than it drifts back
+const HEATER_HEAT: u8 = 4;
+const COOLER_CHILL: u8 = 4;
+// water freezes well below room temperature, the ice keeps at room temperature and only melts once something warms it
+const WATER_FREEZING_TEMPERATURE: u8 = ROOM_TEMPERATURE - 32;
+const ICE_MELTING_TEMPERATURE: u8 = ROOM_TEMPERATURE + 8;
+// stone only melts far hotter than fire gets it, the lava starts out as hot as anything gets and sets back into stone
+// once it has cooled about 100 degrees
+const STONE_MELTING_TEMPERATURE: u8 = ROOM_TEMPERATURE + 176;
+const LAVA_TEMPERATURE: u8 = u8::MAX;
+const LAVA_SETTING_TEMPERATURE: u8 = ROOM_TEMPERATURE + 96;
+// lava heats the burnables, water, ice, metal and sand touching it by up to twice this a tick, but not stone or it would
+// melt its way through the whole world
+const LAVA_HEAT: u8 = 4;
+// slag keeps itself hot enough to boil water and set light to what it touches, heating them by up to twice this a tick
+const SLAG_TEMPERATURE: u8 = ROOM_TEMPERATURE + 128;
+const SLAG_HEAT: u8 = 2;
+// sand fuses into glass a little cooler than stone melts, so it takes lava, a heater or lightning rather than fire. the
+// glass never melts back down
+const SAND_FUSING_TEMPERATURE: u8 = ROOM_TEMPERATURE + 160;
+// metal melts a little cooler than stone and sets again just below that. it glows from past boiling and lights the
+// burnables touching it once it is as hot as they catch
+const METAL_MELTING_TEMPERATURE: u8 = ROOM_TEMPERATURE + 160;
+const METAL_SETTING_TEMPERATURE: u8 = ROOM_TEMPERATURE + 144;
+const METAL_GLOW_TEMPERATURE: u8 = WATER_BOILING_TEMPERATURE;
+// metal touching water browns a stage at a time with this chance a tick and is rust after the last, a bar lying in a
+// puddle is gone in a few minutes. rust creeps on into the metal touching it, only a lot slower
+const RUST_STAGES: u8 = 4;
+const RUST_CHANCE: f32 = 0.002;
+const RUST_SPREAD_CHANCE: f32 = 0.0002;
+// rust holds together until it has nothing under it or is hit by a blast, then it crumbles into a powder that falls
+// like sand. crumbling rust shakes the rust around it loose with this chance a tick
+const RUST_CRUMBLE_CHANCE: f32 = 0.05;
+// with --day-length set anything changing phase that touches the air drifts towards an ambient temperature going from
+// this warm at midday, short of lighting anything, to this cold at midnight, cold enough to freeze the top of a pond.
+// once the air is colder than the dew temperature steam condenses on anything it touches
+const DAY_TEMPERATURE: u8 = ROOM_TEMPERATURE + 12;
+const NIGHT_TEMPERATURE: u8 = ROOM_TEMPERATURE - 40;
+const DEW_TEMPERATURE: u8 = ROOM_TEMPERATURE - 16;
+
+// fire burns one fuel a tick, a burning cell keeps the fuel of whatever caught fire
+const WOOD_FUEL: u8 = 64;
+// fire from the brush has nothing under it to burn
+const FIRE_FUEL: u8 = 32;
+const WOOD_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 128;
+// on the cpu burning wood chars where it stands instead of going straight up in flames, burning a fuel with this chance
+// a tick so it lasts a few times longer and now and then putting out a short flame into the air beside it. it blackens
+// over CHARRED_STAGES steps and once its fuel is gone collapses into a last flame that throws embers and leaves ash
+const CHARRED_BURN_CHANCE: f32 = 0.25;
+const CHARRED_STAGES: u8 = 4;
+const CHARRED_FLAME_CHANCE: f32 = 0.2;
+const CHARRED_FLAME_FUEL: u8 = 4;
+// plants catch a lot sooner than wood and burn out far quicker. the fuel is the water they hold, so one dried out goes
+// up quicker still
+const PLANT_FUEL: u8 = 8;
+const PLANT_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 24;
+// ants and fish catch as easily as plants but are gone in a few ticks
+const CREATURE_FUEL: u8 = 4;
+const CREATURE_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 24;
+// virus goes up in the lightest flame and burns out at once
+const VIRUS_FUEL: u8 = 2;
+const VIRUS_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 16;
+// oil lights easily and burns through quickly, giving off thick smoke
+const OIL_FUEL: u8 = 16;
+const OIL_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 40;
+const OIL_SMOKE_CHANCE: f32 = 0.6;
+// honey only catches once it is very hot, then burns a while and smokes like burnt sugar
+const HONEY_FUEL: u8 = 24;
+const HONEY_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 150;
+const HONEY_SMOKE_CHANCE: f32 = 0.5;
+// napalm lights about as easily as oil but burns where it is instead of turning into fire, giving off flames into the
+// air around it with this chance a tick and burning through a unit of fuel with this chance, most of a minute in all.
+// once its fuel is gone it goes up in a last flame that leaves thick smoke
+const NAPALM_FUEL: u8 = 120;
+const NAPALM_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 40;
+const NAPALM_BURNING: u8 = 1;
+const NAPALM_BURN_CHANCE: f32 = 0.05;
+const NAPALM_FLAME_CHANCE: f32 = 0.3;
+const NAPALM_FLAME_FUEL: u8 = 4;
+const NAPALM_SMOKE_CHANCE: f32 = 0.7;
+// coal is hard to light but burns for a long time and leaves ash
+const COAL_FUEL: u8 = 200;
+const COAL_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 160;
+const COAL_SMOKE_CHANCE: f32 = 0.25;
+// burning coal is far hotter than other fire, it heats the stone, metal and sand touching it this much a tick on average
+// until they melt, so a coal fire walled in with stone is a furnace that keeps turning out lava
+const COAL_FIRE_HEAT: u8 = 6;
+// chance of burnt out fire leaving smoke, tnt only burns in the double buffered and gpu updates
+const WOOD_SMOKE_CHANCE: f32 = 0.125;
+const TNT_SMOKE_CHANCE: f32 = 0.5;
+const FIRE_SMOKE_CHANCE: f32 = 0.125;
+// average heat fire gives each burnable neighbour a tick, the actual amount is random up to twice this
+const FIRE_HEAT: u8 = 4;
+// flames lick up at what is above them far harder than they creep sideways, and barely reach down at all
+const FIRE_RISING_HEAT: f32 = 3.0;
+const FIRE_FALLING_HEAT: f32 = 0.25;
+
+// tnt blows up instead of burning on the cpu, the fuel is only for the double buffered and gpu updates where it burns
+const TNT_FUEL: u8 = 8;
+const TNT_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 32;
+// the blast pushes cells away at up to this many cells a tick, falling off to nothing at the edge of the radius. tnt
+// goes off between ticks like a detonator so its blast can reach past the columns a strip may touch
+const TNT_POWER: f32 = MAX_SIDEWAYS_VELOCITY;
+const TNT_RADIUS: usize = 16;
+// gunpowder goes off in lots of little pops instead of one big bang, each one close enough to set off the grains next to
+// it so a trail of it runs through in a few ticks
+const GUNPOWDER_FUEL: u8 = 2;
+const GUNPOWDER_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 24;
+const GUNPOWDER_SMOKE_CHANCE: f32 = 0.5;
+const GUNPOWDER_POWER: f32 = TNT_POWER / 4.0;
+const GUNPOWDER_RADIUS: usize = 3;
+// a fuse lights like gunpowder but burns along at a steady pace instead, each cell taking this many ticks to burn through
+// before it lights the fuse and sets off the detonators touching it and nothing else. 15 cells a second
+const FUSE_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 24;
+const FUSE_BURN_TICKS: u8 = 4;
+// nitro goes off with a blast between gunpowder and tnt, from heat or from landing at least this fast, about a drop of
+// a few dozen cells. so it can be poured gently but not dropped
+const NITRO_FUEL: u8 = 4;
+const NITRO_IGNITION_TEMPERATURE: u8 = ROOM_TEMPERATURE + 48;
+const NITRO_RADIUS: usize = 10;
+const NITRO_SHOCK_VELOCITY: f32 = 3.0;
+// the blasts new detonators can be set to, they go off between ticks so can reach much further than tnt
+const DETONATOR_RADII: [u8; 4] = [8, 16, 32, 64];
+// the ticks new timers go off every and new delays wait, a tenth of a second up to a second
+const TIMER_PERIODS: [u8; 4] = [6, 15, 30, 60];
+// fans push everything up to this many cells in front of them a cell further each tick. facing sideways the cell pushed
+// furthest has to stay in MAX_REACH, up and down the strips dont limit it
+const FAN_REACH: usize = 8;
+const _: () = assert!(FAN_REACH < MAX_REACH);
+const FAN_VERTICAL_REACH: usize = 32;
+// blowing across gravity, fans throw what leaves the end of them this fast
+const FAN_VELOCITY: f32 = 2.0;
+
+// salt dissolves into water touching it until the water holds this much, when the water boils away whatever it held is
+// left behind as salt
+const MAX_SALT: u8 = 4;
+const SALT_DISSOLVE_CHANCE: f32 = 0.05;
+// ash dissolves into water the same way, only slower, and stays in it. water keeps its salt in the low bits of its
+// lifetime and its ash in the bits above ASH_SHIFT
+const MAX_ASH: u8 = 4;
+const ASH_DISSOLVE_CHANCE: f32 = 0.01;
+const ASH_SHIFT: u8 = 4;
+const _: () = assert!(MAX_SALT < 1 << ASH_SHIFT && MAX_ASH < 1 << (8 - ASH_SHIFT));
+// eats through about 5 cells before it is used up, a quarter of them go up in smoke
+// seeds sprout on wet sand or in water and grow up to 24 cells tall, a branch every 10 cells or so
+const PLANT: Growth = Growth {
+    substrates: &[CellType::Water],
+    drinks: true,
+    grows_through: &[CellType::Air],
+    body: CellType::Plant,
+    rate: 0.1,
+    branch_chance: 0.1,
+    max_size: 24,
+};
+// vines creep over wood, stone and glass a lot slower than plants grow, covering about 64 cells from each one drawn
+const VINE: Growth = Growth {
+    substrates: &[CellType::Wood, CellType::Stone, CellType::Glass],
+    drinks: false,
+    grows_through: &[CellType::Air],
+    body: CellType::Vine,
+    rate: 0.02,
+    branch_chance: 0.0,
+    max_size: 64,
+};
+
+// what the rock key goes through
+const ROCKS: [CellType; 3] = [CellType::Stone, CellType::Lava, CellType::Obsidian];
+
+// what the liquid key goes through
+const LIQUIDS: [CellType; 4] = [
+    CellType::Oil,
+    CellType::Nitro,
+    CellType::Honey,
+    CellType::Napalm,
+];
+
+// what the bath key goes through
+const BATH: [CellType; 3] = [CellType::Sponge, CellType::Soap, CellType::Foam];
+
+// what the gas key goes through
+const GASES: [CellType; 4] = [
+    CellType::Smoke,
+    CellType::Hydrogen,
+    CellType::ToxicGas,
+    CellType::Methane,
+];
+
+// what the growth key goes through
+const GROWTHS: [CellType; 4] = [
+    CellType::Seed,
+    CellType::Vine,
+    CellType::Moss,
+    CellType::Fungus,
+];
+
+// ants wander over and through sand and dirt, never into water, and drown in a couple of seconds under it. fish swim
+// about in water and last a few seconds out of it, both rot into dirt
+const ANT: Creature = Creature {
+    moves_through: &[CellType::Air, CellType::Sand, CellType::Dirt, CellType::Ash],
+    clings: true,
+    speed: 0.3,
+    turn_chance: 0.05,
+    suffocation_chance: 0.25,
+    remains: CellType::Dirt,
+};
+const FISH: Creature = Creature {
+    moves_through: &[CellType::Water],
+    clings: false,
+    speed: 0.2,
+    turn_chance: 0.1,
+    suffocation_chance: 0.1,
+    remains: CellType::Dirt,
+};
+
+// virus eats its way through a tree in a few seconds, leaving nothing behind a second after the last of it is gone
+const VIRUS: Contagion = Contagion {
+    infects: &[
+        CellType::Wood,
+        CellType::Plant,
+        CellType::Vine,
+        CellType::Seed,
+        CellType::Sponge,
+        CellType::Ant,
+        CellType::Fish,
+    ],
+    body: CellType::Virus,
+    spread_chance: 0.05,
+    burnout: 60,
+};
+
+// only a pile of uranium a few dozen cells across keeps a chain reaction going, and instead of blowing up it melts down
+// into slag
+const URANIUM: Radioactive = Radioactive {
+    decay_chance: 0.0005,
+    fission_chance: 0.4,
+    fission_neutrons: 2,
+    // uranium soaking up a neutron is slowly turned into plutonium
+    breeds: Some((0.02, CellType::Plutonium)),
+    warmth: ROOM_TEMPERATURE + 16,
+    melts: Some(CellType::Slag),
+};
+const PLUTONIUM: Radioactive = Radioactive {
+    decay_chance: 0.002,
+    fission_chance: 0.5,
+    fission_neutrons: 3,
+    breeds: None,
+    warmth: ROOM_TEMPERATURE,
+    melts: None,
+};
+// slag is spent, it cant split any more but decays faster than uranium and keeps itself hot
+const SLAG: Radioactive = Radioactive {
+    decay_chance: 0.002,
+    fission_chance: 0.0,
+    fission_neutrons: 0,
+    breeds: None,
+    warmth: SLAG_TEMPERATURE,
+    melts: None,
+};
+
+const ACID: Corrosive = Corrosive {
+    strength: 1.0,
+    byproduct_chance: 0.25,
+    byproduct: CellType::ToxicGas,
+    used_up_chance: 0.2,
+};
+
+// a lone drop of liquid looks this far either side for more of its own kind to join, and beads up where it is with
+// this chance when there is none
+const COHESION_REACH: usize = 3;
+const _: () = assert!(COHESION_REACH < MAX_REACH);
+const DROP_COHESION: f32 = 0.9;
+
+// dry sand or dirt touching water soaks up a cell of it and is this wet, it stays that way while it touches water and
+// otherwise dries out a step at a time with this chance a tick
+const MAX_WETNESS: u8 = u8::MAX;
+const SOAK_CHANCE: f32 = 0.05;
+const DRY_CHANCE: f32 = 0.25;
+// wet sand falls harder and mostly holds its shape instead of sliding down slopes
+const WET_SAND_WEIGHT: f32 = 1.5;
+const WET_SAND_STICKINESS: f32 = 0.9;
+// how dark soaked sand and dirt are drawn
+const WET_SAND_DARKNESS: f32 = 0.35;
+// dirt clumps together so it piles up steeper than sand, wet or dry
+const DIRT_STICKINESS: f32 = 0.75;
+// soaked sand or dirt still touching water takes in another cell of it and turns to mud with this chance a tick. mud
+// with no water touching it dries back into what it was made from with this chance a tick, still wet
+const MUD_CHANCE: f32 = 0.002;
+const MUD_DRY_CHANCE: f32 = 0.002;
+// water carrying a grain of sediment is drawn this far towards sand
+const SILTY_WATER_TINT: f32 = 0.3;
+
+// presets for --gen, rolling hills of dirt with trees, flat grassland with lakes under it and sandy dunes
+const HILLS: Terrain = Terrain {
+    surface: 200.0,
+    hill_height: 40.0,
+    hill_width: 120.0,
+    sand_depth: 6.0,
+    dirt_depth: 16.0,
+    water: 0.2,
+    methane: 0.06,
+    pocket_size: 24.0,
+    caves: 0.05,
+    cave_size: 48.0,
+    coal: 0.2,
+    gold: 0.012,
+    gold_depth: 40.0,
+    vein_size: 24.0,
+    tree_chance: 0.04,
+};
+const PLAINS: Terrain = Terrain {
+    surface: 220.0,
+    hill_height: 8.0,
+    hill_width: 200.0,
+    sand_depth: 3.0,
+    dirt_depth: 20.0,
+    water: 0.3,
+    methane: 0.04,
+    pocket_size: 32.0,
+    caves: 0.03,
+    cave_size: 64.0,
+    coal: 0.25,
+    gold: 0.008,
+    gold_depth: 50.0,
+    vein_size: 32.0,
+    tree_chance: 0.02,
+};
+const DUNES: Terrain = Terrain {
+    surface: 210.0,
+    hill_height: 30.0,
+    hill_width: 60.0,
+    sand_depth: 40.0,
+    dirt_depth: 6.0,
+    water: 0.1,
+    methane: 0.08,
+    pocket_size: 16.0,
+    caves: 0.06,
+    cave_size: 40.0,
+    coal: 0.15,
+    gold: 0.015,
+    gold_depth: 70.0,
+    vein_size: 20.0,
+    tree_chance: 0.005,
+};
+
+// a blob of goo hangs from a ceiling until about 40 cells hang off one link, sagging once more than 6 do
+const GOO: Goo = Goo {
+    falls_through: &[
+        CellType::Air,
+        CellType::Water,
+        CellType::Steam,
+        CellType::Smoke,
+    ],
+    strength: 40.0,
+    stretch: 6.0,
+    adhesion: 60.0,
+    sag_chance: 0.2,
+    heal_chance: 0.2,
+    cohesion: 0.3,
+    bounce: 0.0,
+    absorbs: false,
+};
+
+// slime clings to walls and ceilings harder than goo but starts stretching into strands once 3 cells hang off a link and
+// drips once 15 do. it bounces back up half as many cells as it was falling a tick and swallows whatever flies into it
+const SLIME: Goo = Goo {
+    falls_through: &[
+        CellType::Air,
+        CellType::Water,
+        CellType::Steam,
+        CellType::Smoke,
+    ],
+    strength: 15.0,
+    stretch: 3.0,
+    adhesion: 90.0,
+    sag_chance: 0.3,
+    heal_chance: 0.3,
+    cohesion: 0.2,
+    bounce: 0.5,
+    absorbs: true,
+};
+
+// wood only slowly soaks up what rains on it, a sponge drinks up a puddle and holds a lot more
+const WOOD: Porous = Porous {
+    soak_chance: 0.02,
+    capacity: 4,
+};
+const SPONGE: Porous = Porous {
+    soak_chance: 0.5,
+    capacity: 32,
+};
+// how dark wood and sponge holding all they can are drawn
+const SOAKED_DARKNESS: f32 = 0.4;
+
+// oil is a little thicker than water, strong acid a lot thicker and lava thicker still
+const OIL_VISCOSITY: f32 = 0.2;
+const ACID_VISCOSITY: f32 = 0.5;
+const LAVA_VISCOSITY: f32 = 0.8;
+const MOLTEN_METAL_VISCOSITY: f32 = 0.6;
+// mud is thick enough it barely flows at all, and honey thicker still
+const MUD_VISCOSITY: f32 = 0.95;
+const SLAG_VISCOSITY: f32 = 0.9;
+const HONEY_VISCOSITY: f32 = 0.97;
+// napalm is a gel that holds on to anything solid it touches, and off it only creeps
+const NAPALM_VISCOSITY: f32 = 0.9;
+// honey hanging under anything solid holds on, letting a drop go with this chance a tick. grains resting on it sink
+// into it a cell with this chance a tick
+const HONEY_DRIP_CHANCE: f32 = 0.01;
+const HONEY_SINK_CHANCE: f32 = 0.02;
+// how hard each liquid pushes up rigid bodies sunk in it, bodies float if they are lighter than this
+const WATER_DENSITY: f32 = 1.0;
+const OIL_DENSITY: f32 = 0.8;
+const NAPALM_DENSITY: f32 = 0.9;
+const ACID_DENSITY: f32 = 1.2;
+const HONEY_DENSITY: f32 = 1.4;
+const NITRO_DENSITY: f32 = 1.6;
+const LAVA_DENSITY: f32 = 2.5;
+const MOLTEN_METAL_DENSITY: f32 = 3.0;
+const MUD_DENSITY: f32 = 2.0;
+const SLAG_DENSITY: f32 = 3.5;
+
+// charge lost passing through a cell of each conductor, a wire carries it about 100 cells from a battery and water only
+// a few
+const WIRE_CHARGE_LOSS: u8 = 2;
+const GOLD_CHARGE_LOSS: u8 = 1;
+const WATER_CHARGE_LOSS: u8 = 32;
+
+// fire heats water up to this many cells away, the water boils once it gets this hot
+const WATER_HEAT_REACH: usize = 2;
+const _: () = assert!(WATER_HEAT_REACH < MAX_REACH);
+const WATER_BOILING_TEMPERATURE: u8 = ROOM_TEMPERATURE + 64;
+// puddles this deep or shallower slowly dry up, each cell on the surface has this chance a tick of evaporating unless
+// --evaporation changes it
+const PUDDLE_DEPTH: usize = 2;
+const EVAPORATION_CHANCE: f32 = 0.0005;
+
+// how often salty water tries to share its salt with a neighbour holding less
+const SALT_SPREAD_CHANCE: f32 = 0.5;
+
+// blasts at least this strong smash wood into debris
+const WOOD_STRENGTH: f32 = 2.0;
+// and this strong crack obsidian, only the very middle of a tnt blast or a bigger one does
+const OBSIDIAN_STRENGTH: f32 = 3.0;
+
+// velocity is stored as a u8 in 1/VELOCITY_SCALE cell steps, MAX_VELOCITY has to fit
+const VELOCITY_SCALE: f32 = 20.0;
+const _: () = assert!(MAX_VELOCITY * VELOCITY_SCALE <= u8::MAX as f32);
+
+// sideways velocity is signed so it only gets half the range, it also moves cells sideways so has to stay in MAX_REACH
+const MAX_SIDEWAYS_VELOCITY: f32 = 4.0;
+const _: () = assert!(MAX_SIDEWAYS_VELOCITY * VELOCITY_SCALE <= i8::MAX as f32);
+const _: () = assert!(MAX_SIDEWAYS_VELOCITY <= MAX_VELOCITY);
+// sideways velocity lost every tick a cell spends flying, and sliding along the top of something
+const AIR_DRAG: f32 = 0.1;
+const GROUND_DRAG: f32 = 0.5;
+const ICE_DRAG: f32 = 0.05;
+// a cell tipping off a pile onto ice goes sliding off sideways this fast
+const ICE_SLIDE_VELOCITY: f32 = 2.0;
+// water landing at least this fast splashes out sideways with half its speed
+const SPLASH_VELOCITY: f32 = 3.0;
+
+// cells are stamped with the generation they last moved in instead of a flag, so nothing has to walk the grid every tick
+// to reset them. 0 is never a live generation so it means not moved, and the stamps are cleared once every time the
+// generations wrap so an old stamp can never match
+const GENERATIONS: u64 = u8::MAX as u64;
+
+// furthest a single cell update can read or write from its own column: a fluid spreading at max velocity
+// scans velocity + 1 cells out and furthest_by_vector looks one further than that
+const MAX_REACH: usize = MAX_VELOCITY as usize + 2;
+// strips updated in the same phase are one strip apart, so as long as two reaches fit in a strip no two
+// threads can ever touch the same cell
+const STRIP_WIDTH: usize = 32;
+const _: () = assert!(STRIP_WIDTH >= 2 * MAX_REACH);
+
+// gravity starts out pointing down the screen and is turned a quarter at a time
+const DOWN: (isize, isize) = (0, 1);
+
+// the wind keys change it a step at a time. the strongest gusts on the slowest gases are capped at
+// MAX_SIDEWAYS_VELOCITY
+const WIND_STEP: f32 = 0.25;
+const MAX_WIND: f32 = 1.0;
+// gusts swing the wind this far either side of what it is set to, once every 6 seconds at 60 ticks a second
+const GUST_STRENGTH: f32 = 0.5;
+const GUST_RATE: f32 = std::f32::consts::TAU / 360.0;
+
+const NO_FIELD: u8 = 0;
+// the 4 straight directions, fields and fans store which way they point as an index into these
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (-1, 0), (0, -1), (1, 0)];
+
+// checked in this order by fire and reactions, left, right, top, bottom then the diagonals
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (-1, 1),
+    (1, -1),
+    (1, 1),
+];
+
+const STRESS_TICKS: u64 = 1000;
+// longest the update gets each frame before the rest of the tick is left for the next frame
+const TICK_FRAME_BUDGET: Duration = Duration::from_millis(10);
+
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+const FRAME_TIME_SMOOTHING: f32 = 0.1;
+const TITLE_UPDATE_INTERVAL: Duration = Duration::from_millis(250);
 
 const AIR_COLOR: [u8; 3] = [0x00, 0x00, 0x00];
+// the empty sky at midday with --day-length set, fading back to AIR_COLOR at night in this many steps
+const DAY_SKY_COLOR: [u8; 3] = [0x12, 0x1a, 0x2a];
+const SKY_STEPS: u8 = 16;
 const SAND_COLORS: [[u8; 3]; 4] = [
     [0xf6, 0xd7, 0xb0],
     [0xf2, 0xd2, 0xa9],
@@ -45,6 +723,7 @@ const WOOD_COLORS: [[u8; 3]; 4] = [
     [0x6b, 0x47, 0x36],
     [0x65, 0x43, 0x33],
 ];
+const CHARRED_COLOR: [u8; 3] = [0x16, 0x12, 0x10];
 const FIRE_COLORS: [[u8; 3]; 6] = [
     // weighted colors = more red less yellow
     // reds
@@ -60,8 +739,125 @@ const SMOKE_COLOR_LIGHT: [u8; 3] = [0x47, 0x47, 0x47];
 const SMOKE_COLOR_DARK: [u8; 3] = [0x00, 0x00, 0x00];
 const STEAM_COLOR_LIGHT: [u8; 3] = [0xf5, 0xf5, 0xf5];
 const STEAM_COLOR_DARK: [u8; 3] = [0x00, 0x00, 0x00];
+// empty space inside a painted force field
+const FIELD_COLOR: [u8; 3] = [0x14, 0x10, 0x26];
+const TNT_COLORS: [[u8; 3]; 2] = [[0xd0, 0x2f, 0x2f], [0xc4, 0x29, 0x29]];
+const FAN_COLOR: [u8; 3] = [0x8a, 0x9b, 0xa8];
+const GUNPOWDER_COLORS: [[u8; 3]; 3] = [[0x2e, 0x2e, 0x30], [0x26, 0x26, 0x28], [0x37, 0x36, 0x33]];
+const SALT_COLORS: [[u8; 3]; 3] = [[0xf4, 0xf1, 0xe8], [0xe8, 0xe4, 0xd9], [0xfa, 0xf8, 0xf2]];
+const ACID_COLORS: [[u8; 3]; 3] = [[0x7f, 0xff, 0x00], [0x76, 0xee, 0x00], [0x66, 0xcd, 0x00]];
+const WIRE_COLOR: [u8; 3] = [0xb8, 0x73, 0x33];
+const BATTERY_COLOR: [u8; 3] = [0x3a, 0x3f, 0x4a];
+// fully charged conductors are tinted this far towards CHARGE_COLOR
+const CHARGE_COLOR: [u8; 3] = [0xff, 0xf2, 0x6e];
+const CHARGE_GLOW: f32 = 0.6;
+// off then on
+const SWITCH_COLORS: [[u8; 3]; 2] = [[0x8a, 0x2e, 0x2e], [0x3c, 0xa0, 0x46]];
+const PLATE_COLOR: [u8; 3] = [0x9a, 0x8c, 0x70];
+// and, or, not
+const GATE_COLORS: [[u8; 3]; 3] = [[0x4a, 0x6f, 0xd0], [0x9a, 0x4a, 0xd0], [0xd0, 0x4a, 0x8a]];
+const LAMP_COLOR: [u8; 3] = [0x4e, 0x48, 0x30];
+const LAMP_LIT_COLOR: [u8; 3] = [0xff, 0xf8, 0xc8];
+const LASER_COLOR: [u8; 3] = [0x6e, 0x1e, 0x24];
+// rising then falling
+const MIRROR_COLORS: [[u8; 3]; 2] = [[0xd8, 0xe4, 0xea], [0xb8, 0xc8, 0xd2]];
+const GLASS_COLOR: [u8; 3] = [0xa4, 0xd4, 0xd0];
+// glass is see through, the sky behind it shows through this much
+const GLASS_CLARITY: f32 = 0.6;
+// hydrogen is no more than a faint tint over the sky
+const HYDROGEN_COLOR: [u8; 3] = [0xe4, 0xee, 0xff];
+const HYDROGEN_CLARITY: f32 = 0.85;
+// and methane barely shows at all
+const METHANE_COLORS: [[u8; 3]; 3] = [[0xf0, 0xf4, 0xe0], [0xd8, 0xe4, 0xf4], [0xec, 0xe0, 0xf0]];
+const METHANE_CLARITY: f32 = 0.95;
+const TOXIC_GAS_COLORS: [[u8; 3]; 3] = [[0xb8, 0xc8, 0x4a], [0xaa, 0xbc, 0x40], [0xc4, 0xd2, 0x56]];
+// laser beams are drawn over the cells they cross this opaque
+const BEAM_COLOR: [u8; 3] = [0xff, 0x2a, 0x2a];
+const BEAM_ALPHA: u8 = 0xb0;
+const BOLT_COLOR: [u8; 3] = [0xf0, 0xf0, 0xff];
+const URANIUM_COLORS: [[u8; 3]; 2] = [[0x56, 0x8c, 0x3a], [0x4c, 0x7e, 0x33]];
+const PLUTONIUM_COLORS: [[u8; 3]; 2] = [[0x74, 0x66, 0x8c], [0x68, 0x5b, 0x80]];
+const SLAG_COLORS: [[u8; 3]; 3] = [[0x6e, 0x78, 0x2a], [0x62, 0x6c, 0x24], [0x7a, 0x84, 0x32]];
+const NEUTRON_COLOR: [u8; 3] = [0xb4, 0xff, 0x8a];
+const ANTIMATTER_COLORS: [[u8; 3]; 3] =
+    [[0xe6, 0x3c, 0xd2], [0xd6, 0x32, 0xc2], [0xf0, 0x50, 0xe0]];
+// embers cool from hot to cool over the last EMBER_FADE ticks of their lifetime
+const EMBER_COLOR_HOT: [u8; 3] = [0xff, 0xc0, 0x40];
+const EMBER_COLOR_COOL: [u8; 3] = [0x8b, 0x1a, 0x00];
+const EMBER_FADE: u16 = 30;
+const ASH_COLORS: [[u8; 3]; 3] = [[0x9a, 0x96, 0x90], [0x85, 0x82, 0x7d], [0xae, 0xaa, 0xa4]];
+const SPONGE_COLORS: [[u8; 3]; 3] = [[0xe8, 0xd4, 0x4d], [0xd9, 0xc2, 0x3e], [0xf0, 0xdc, 0x5a]];
+const SOAP_COLORS: [[u8; 3]; 3] = [[0xf0, 0xc8, 0xdc], [0xe6, 0xba, 0xd0], [0xf6, 0xd4, 0xe6]];
+const FOAM_COLORS: [[u8; 3]; 3] = [[0xf4, 0xf8, 0xfc], [0xe8, 0xf0, 0xf8], [0xfc, 0xfc, 0xfc]];
+const STONE_COLORS: [[u8; 3]; 3] = [[0x6e, 0x6c, 0x6a], [0x62, 0x60, 0x5e], [0x78, 0x76, 0x73]];
+const DIRT_COLORS: [[u8; 3]; 3] = [[0x6b, 0x4a, 0x2f], [0x5e, 0x40, 0x28], [0x75, 0x52, 0x35]];
+const MUD_COLORS: [[u8; 3]; 3] = [[0x4a, 0x36, 0x24], [0x42, 0x30, 0x20], [0x52, 0x3c, 0x28]];
+const COAL_COLORS: [[u8; 3]; 3] = [[0x24, 0x23, 0x26], [0x1b, 0x1b, 0x1d], [0x2e, 0x2d, 0x31]];
+const METAL_COLORS: [[u8; 3]; 3] = [[0x9a, 0x9e, 0xa6], [0x8c, 0x90, 0x98], [0xa8, 0xac, 0xb4]];
+const MOLTEN_METAL_COLORS: [[u8; 3]; 3] =
+    [[0xff, 0xd2, 0x8c], [0xff, 0xc0, 0x6e], [0xff, 0xe0, 0xa8]];
+const RUST_COLORS: [[u8; 3]; 3] = [[0x8b, 0x45, 0x13], [0x7a, 0x3b, 0x10], [0x9c, 0x50, 0x1a]];
+const OBSIDIAN_COLORS: [[u8; 3]; 3] = [[0x1a, 0x16, 0x22], [0x24, 0x1e, 0x2e], [0x12, 0x10, 0x18]];
+const GOLD_COLORS: [[u8; 3]; 3] = [[0xe6, 0xbe, 0x3a], [0xd4, 0xa8, 0x2a], [0xf2, 0xd0, 0x5a]];
+const SPOUT_COLOR: [u8; 3] = [0x3c, 0x6e, 0x8c];
+const DRAIN_COLOR: [u8; 3] = [0x2a, 0x2a, 0x3a];
+const CLONE_COLOR: [u8; 3] = [0xb8, 0xa0, 0x3c];
+const VOID_COLOR: [u8; 3] = [0x50, 0x14, 0x28];
+// the two ends of a pair of portals
+const PORTAL_COLORS: [[u8; 3]; 2] = [[0x3c, 0x8c, 0xf0], [0xf0, 0x8c, 0x28]];
+const CONVEYOR_COLOR: [u8; 3] = [0x4a, 0x4e, 0x54];
+const HEATER_COLOR: [u8; 3] = [0xb4, 0x3c, 0x28];
+const COOLER_COLOR: [u8; 3] = [0x3c, 0x8c, 0xc8];
+const DETONATOR_COLOR: [u8; 3] = [0x8c, 0x1e, 0x1e];
+const FUSE_COLORS: [[u8; 3]; 2] = [[0x6e, 0x5a, 0x3c], [0x62, 0x50, 0x34]];
+const FUSE_BURNING_COLOR: [u8; 3] = [0xff, 0xe0, 0x60];
+const TIMER_COLOR: [u8; 3] = [0x5a, 0x6e, 0x50];
+const DELAY_COLOR: [u8; 3] = [0x6e, 0x5a, 0x82];
+const SENSOR_COLOR: [u8; 3] = [0x82, 0x78, 0x46];
+const ANT_COLORS: [[u8; 3]; 3] = [[0x3a, 0x1e, 0x14], [0x2e, 0x18, 0x10], [0x46, 0x24, 0x18]];
+const FISH_COLORS: [[u8; 3]; 3] = [[0xf0, 0x8c, 0x28], [0xe6, 0x78, 0x1e], [0xfa, 0xa0, 0x3c]];
+const VIRUS_COLORS: [[u8; 3]; 3] = [[0xa0, 0x28, 0xb4], [0x8c, 0x1e, 0xa0], [0xb4, 0x3c, 0xc8]];
+const SNOW_COLORS: [[u8; 3]; 3] = [[0xf4, 0xf8, 0xfc], [0xe8, 0xee, 0xf6], [0xfc, 0xfc, 0xff]];
+const CONFETTI_COLORS: [[u8; 3]; 6] = [
+    [0xf0, 0x3c, 0x50],
+    [0xfa, 0xc8, 0x28],
+    [0x3c, 0xc8, 0x64],
+    [0x32, 0x8c, 0xf0],
+    [0xb4, 0x50, 0xe6],
+    [0xff, 0x8c, 0xc8],
+];
+const NITRO_COLORS: [[u8; 3]; 3] = [[0xe8, 0xe0, 0xa8], [0xdf, 0xd6, 0x9a], [0xee, 0xe8, 0xb8]];
+const OIL_COLORS: [[u8; 3]; 3] = [[0x3a, 0x2a, 0x12], [0x33, 0x25, 0x0f], [0x42, 0x30, 0x16]];
+const HONEY_COLORS: [[u8; 3]; 3] = [[0xe0, 0x9a, 0x1c], [0xd4, 0x8c, 0x14], [0xea, 0xa8, 0x2a]];
+const NAPALM_COLORS: [[u8; 3]; 3] = [[0xa8, 0x5a, 0x28], [0x9a, 0x50, 0x22], [0xb4, 0x64, 0x30]];
+const NAPALM_BURNING_COLORS: [[u8; 3]; 3] =
+    [[0xff, 0x8a, 0x1e], [0xf4, 0x6c, 0x12], [0xff, 0xa8, 0x34]];
+const ICE_COLORS: [[u8; 3]; 3] = [[0xbe, 0xe6, 0xf5], [0xae, 0xdc, 0xf0], [0xcc, 0xee, 0xfa]];
+const LAVA_COLORS: [[u8; 3]; 3] = [[0xff, 0x5a, 0x0a], [0xf0, 0x3c, 0x05], [0xff, 0x8c, 0x1e]];
+// lava glows less and less as it cools, just about to set it is this dull
+const LAVA_COLOR_CRUST: [u8; 3] = [0x5a, 0x1e, 0x14];
+const GOO_COLORS: [[u8; 3]; 3] = [[0x4c, 0xc8, 0x64], [0x44, 0xb8, 0x5a], [0x54, 0xd4, 0x6e]];
+// goo about to tear is drawn this pale
+const GOO_COLOR_STRETCHED: [u8; 3] = [0xc8, 0xf4, 0xc0];
+const SLIME_COLORS: [[u8; 3]; 3] = [[0x9e, 0xd8, 0x2c], [0x92, 0xcc, 0x24], [0xaa, 0xe2, 0x38]];
+const SLIME_COLOR_STRETCHED: [u8; 3] = [0xe2, 0xf6, 0xa8];
+// one for each BodyShape
+const BODY_COLORS: [[u8; 3]; 2] = [[0xb0, 0x7a, 0x3c], [0xd0, 0x40, 0x60]];
+const SEED_COLORS: [[u8; 3]; 2] = [[0x8b, 0x6b, 0x3d], [0x7a, 0x5c, 0x32]];
+const VINE_COLORS: [[u8; 3]; 3] = [[0x2a, 0x6e, 0x2a], [0x24, 0x62, 0x26], [0x31, 0x7a, 0x2e]];
+const MOSS_COLORS: [[u8; 3]; 3] = [[0x4f, 0x7a, 0x2c], [0x45, 0x6e, 0x26], [0x5a, 0x86, 0x33]];
+// how far moss tints what it grows on towards its own colour
+const MOSS_TINT: f32 = 0.6;
+const FUNGUS_COLORS: [[u8; 3]; 3] = [[0xc8, 0xb8, 0xa0], [0xb8, 0xa6, 0x8c], [0xd6, 0xc8, 0xb4]];
+const SPORE_COLOR: [u8; 3] = [0xe6, 0xde, 0xc8];
+const PLANT_COLORS: [[u8; 3]; 3] = [[0x3c, 0x9a, 0x3c], [0x34, 0x8a, 0x34], [0x46, 0xa8, 0x40]];
+// salty water is tinted this far towards salt when it cant hold any more, and ashy water this far towards grey
+const SALT_WATER_TINT: f32 = 0.3;
+const ASHY_WATER_COLOR: [u8; 3] = [0x3a, 0x3c, 0x40];
+const ASHY_WATER_TINT: f32 = 0.6;
 
 #[derive(PartialEq, Default, Clone, Copy, Sequence)]
+#[repr(u8)]
 enum CellType {
     #[default]
     Air,
@@ -71,538 +867,3697 @@ enum CellType {
     Fire,
     Smoke,
     Steam,
+    Tnt,
+    Fan,
+    Salt,
+    Acid,
+    Wire,
+    Battery,
+    Switch,
+    Plate,
+    Gate,
+    Lamp,
+    Laser,
+    Mirror,
+    Glass,
+    Uranium,
+    Plutonium,
+    Seed,
+    Plant,
+    Ash,
+    Sponge,
+    Body,
+    Goo,
+    Stone,
+    Dirt,
+    Coal,
+    Gold,
+    Spout,
+    Drain,
+    Clone,
+    Void,
+    Portal,
+    Conveyor,
+    Heater,
+    Cooler,
+    Ice,
+    Lava,
+    Detonator,
+    Timer,
+    Delay,
+    Sensor,
+    Ant,
+    Fish,
+    Virus,
+    Snow,
+    Oil,
+    Gunpowder,
+    Nitro,
+    Vine,
+    Moss,
+    Fungus,
+    Metal,
+    MoltenMetal,
+    Rust,
+    Obsidian,
+    Mud,
+    CharredWood,
+    Honey,
+    Slime,
+    Soap,
+    Foam,
+    Hydrogen,
+    ToxicGas,
+    Methane,
+    Napalm,
+    Fuse,
+    Confetti,
+    Slag,
+    Antimatter,
 }
 
-#[derive(PartialEq, Clone)]
-struct Cell {
+// packed so the whole grid is as little memory traffic as possible, colors are looked up from the per element palettes
+// when drawing
+#[derive(PartialEq, Clone, Copy)]
+pub struct Cell {
     ty: CellType,
-    moved: bool,
-    velocity: f32,
-    lifetime: u32,
-    color: [u8; 3],
+    moved_generation: u8,
+    // down for everything but gases, which fall upwards. spouts, timers and delays never move so keep the way they face
+    // in it instead
+    velocity: u8,
+    // across gravity, negative is left when it points down. anything but 0 means the cell is flying and moves along a
+    // line instead of the usual rules
+    velocity_x: i8,
+    color_index: u8,
+    // smoke and steam fade out with it, fans and lasers keep the way they face in it, gates that and which gate they
+    // are, spouts, timers and delays what they put out, clones what they copy, sensors what they look for,
+    // portals which end of which pair they are, conveyors which way they run, detonators how big they blow up, mirrors
+    // the way they lean, switches whether they are on, plants and vines how far they are from where they started, moss
+    // what it is growing on, ants and fish which way they are heading and how much breath they have left, virus how
+    // long it lasts with nothing to infect, wood and sponge how much water they hold, rigid bodies which shape they
+    // are, water how much salt and ash is dissolved in it, sand and dirt how wet they are, metal how rusted it is, rust
+    // whether it has crumbled, soap how much it has lathered and fire the cell type it is burning
+    lifetime: u8,
+    // only used by burnables and fire, by plants for the water they hold, by moss for how long it has been dry, by
+    // timers and delays to count down, by goo for how strained it is and by water for whether it carries a grain of
+    // sediment
+    fuel: u8,
+    temperature: u8,
 }
 
+const _: () = assert!(std::mem::size_of::<Cell>() <= 8);
+
 impl Cell {
     fn from(cell_type: CellType, rng: &Rng) -> Self {
         let mut cell = Cell {
             ty: cell_type,
-            moved: false,
-            velocity: 1.0,
+            moved_generation: 0,
+            velocity: 0,
+            velocity_x: 0,
+            color_index: cell_type_color_index_random(cell_type, rng),
             lifetime: cell_type_lifetime(cell_type),
-            color: [0; 3],
+            fuel: cell_type_fuel(cell_type),
+            temperature: cell_type_temperature(cell_type),
         };
 
-        // HEEELPPPPPPPP!
-        cell.color = cell_type_color_random(&cell, rng);
+        cell.set_velocity(1.0);
 
         cell
     }
-}
 
-fn update_cells(cells: &mut [Vec<Cell>], rng: &Rng) {
-    // traverse the odd indices left to right and the even indices left to right, removes any sort of cell movement priority
-    for i in 0..=1 {
-        for y in (0..HEIGHT).rev() {
-            // forward pass, odds only
-            if i == 1 {
-                for x in 0..WIDTH {
-                    update_cell(i, cells, x, y, rng)
-                }
-            // reverse pass, evens only
-            } else {
-                for x in (0..WIDTH).rev() {
-                    update_cell(i, cells, x, y, rng)
-                }
-            }
-        }
+    fn moved(&self, generation: u8) -> bool {
+        self.moved_generation == generation
     }
 
-    for cell_col in cells.iter_mut() {
-        for cell in cell_col.iter_mut() {
-            cell.moved = false;
-        }
+    fn set_moved(&mut self, generation: u8) {
+        self.moved_generation = generation;
     }
-}
 
-fn update_cell(i: usize, cells: &mut [Vec<Cell>], x: usize, y: usize, rng: &Rng) {
-    if x % 2 == i {
-        return;
+    fn clear_moved(&mut self) {
+        self.moved_generation = 0;
     }
 
-    let cell = &cells[x][y];
+    fn velocity(&self) -> f32 {
+        self.velocity as f32 / VELOCITY_SCALE
+    }
 
-    if cell.moved {
-        return;
+    fn set_velocity(&mut self, velocity: f32) {
+        // float to int casts saturate so this clamps to 0..=255 for free
+        self.velocity = (velocity * VELOCITY_SCALE).round() as u8;
     }
 
-    match cell.ty {
-        CellType::Sand => update_sand(
-            cells,
-            x,
-            y,
-            &[
-                CellType::Air,
-                CellType::Water,
-                CellType::Steam,
-                CellType::Smoke,
-            ],
-            rng,
-        ),
-        CellType::Water => update_water(
-            cells,
-            x,
-            y,
-            &[CellType::Air, CellType::Steam, CellType::Smoke],
-            rng,
-        ),
-        CellType::Fire => update_fire(cells, x, y, &[CellType::Wood], rng),
-        CellType::Smoke => update_smoke(cells, x, y, &[CellType::Air], rng),
-        CellType::Steam => update_steam(cells, x, y, &[CellType::Air], rng),
-        _ => (),
+    fn accelerate(&mut self, acceleration: f32, max_velocity: f32) {
+        self.set_velocity((self.velocity() + acceleration).min(max_velocity));
+    }
+
+    fn velocity_x(&self) -> f32 {
+        self.velocity_x as f32 / VELOCITY_SCALE
+    }
+
+    fn set_velocity_x(&mut self, velocity_x: f32) {
+        let velocity_x = velocity_x.clamp(-MAX_SIDEWAYS_VELOCITY, MAX_SIDEWAYS_VELOCITY);
+        self.velocity_x = (velocity_x * VELOCITY_SCALE).round() as i8;
+    }
+
+    // slows a flying cell down, stopping it once the drag would take it past 0
+    fn drag(&mut self, drag: f32) {
+        let velocity_x = self.velocity_x();
+        self.set_velocity_x((velocity_x.abs() - drag).max(0.0).copysign(velocity_x));
     }
 }
 
-fn update_fire(cells: &mut [Vec<Cell>], x: usize, y: usize, burn_types: &[CellType], rng: &Rng) {
-    let should_spread = rng.f32() < 0.5_f32.powf(6.0);
+const CHUNK_SIZE: usize = STRIP_WIDTH;
+const CHUNKS_X: usize = WIDTH.div_ceil(CHUNK_SIZE);
+const CHUNKS_Y: usize = HEIGHT.div_ceil(CHUNK_SIZE);
 
-    if in_bounds_left(x as isize - 1) {
-        let left_cell_type = cells[x - 1][y].ty;
+// bookkeeping shared by all the strip threads, every write to a cell has to keep it in sync
+struct Tracking {
+    // non air cells in each row, lets the update skip the empty sky
+    row_counts: Vec<AtomicU32>,
+    // chunks whose cells changed since they were last drawn into the frame
+    dirty_chunks: Vec<AtomicBool>,
+    // stamp for cells that move during the current tick
+    generation: u8,
+    // the tick being updated, for elements that skip ticks
+    tick: u64,
+    // furthest liquids can spread sideways this tick
+    spread_limit: usize,
+    // the direction everything falls, one of the 4 straight directions. gases rise against it
+    gravity: (isize, isize),
+    // painted force fields, NO_FIELD or 1 + the index into DIRECTIONS of the gravity inside them
+    fields: Vec<u8>,
+    // in cells a tick across gravity, to the right when it points down. gusts around this
+    wind: f32,
+    // chance a tick of each cell on the surface of a puddle evaporating
+    evaporation: f32,
+    // water pressure at each position and the top of the column of water causing it, see pressure::update
+    pressure: Vec<u16>,
+    pressure_sources: Vec<u32>,
+    // charge at each position and the charge last tick it spreads from, see electricity::update
+    charge: Vec<u8>,
+    previous_charge: Vec<u8>,
+    // every cell a laser beam crossed this tick, see light::update
+    beams: Vec<(usize, usize)>,
+    particles: Vec<Particle>,
+    // None until the first rigid body is spawned, see rigid::update
+    bodies: Option<Box<Bodies>>,
+    // blasts set off during the tick for the rigid bodies to feel at the start of the next one
+    blasts: Mutex<Vec<Blast>>,
+    // detonators set off during the tick and how far their blasts reach, they go off at the start of the next one
+    detonations: Mutex<Vec<((usize, usize), usize)>>,
+    // hydrogen set alight during the tick, the pocket it is part of goes up at the start of the next one
+    ignitions: Mutex<Vec<(usize, usize)>>,
+    // scales how likely neutrons are to split what they strike, above 1 smaller lumps go critical
+    criticality: f32,
+    // ticks from one midday to the next, 0 for no days at all. see update_ambient
+    day_length: u64,
+    ambient: u8,
+    // how far into the day the empty sky is tinted, 0 at night
+    sky: u8,
+    // what falls in along the top edge and the chance a tick of each cell there dropping some, see weather::update
+    weather: Weather,
+    weather_intensity: f32,
+    // where lightning was called down during the frame, it strikes at the start of the next tick. see lightning::update
+    strike: Option<(usize, usize)>,
+    // every cell the last bolt crossed, drawn for as long as it flashes
+    bolt: Vec<(usize, usize)>,
+    bolt_flash: u8,
+}
 
-        if burn_types.contains(&left_cell_type) {
-            if should_spread {
-                cells[x][y].color = cell_type_color_random(&cells[x][y], rng);
-                spread_to_cell(cells, (x, y), (x - 1, y))
-            }
-        } else if left_cell_type == CellType::Water {
-            cells[x][y] = Cell::from(CellType::Steam, rng);
-            return;
+impl Tracking {
+    fn new() -> Self {
+        Tracking {
+            row_counts: (0..HEIGHT).map(|_| AtomicU32::new(0)).collect(),
+            // the frame starts out empty so everything needs drawing once
+            dirty_chunks: (0..CHUNKS_X * CHUNKS_Y)
+                .map(|_| AtomicBool::new(true))
+                .collect(),
+            generation: 1,
+            tick: 0,
+            spread_limit: usize::MAX,
+            gravity: DOWN,
+            fields: vec![NO_FIELD; WIDTH * HEIGHT],
+            wind: 0.0,
+            evaporation: EVAPORATION_CHANCE,
+            pressure: vec![0; WIDTH * HEIGHT],
+            pressure_sources: vec![0; WIDTH * HEIGHT],
+            charge: vec![0; WIDTH * HEIGHT],
+            previous_charge: vec![0; WIDTH * HEIGHT],
+            beams: Vec::new(),
+            particles: Vec::new(),
+            bodies: None,
+            blasts: Mutex::new(Vec::new()),
+            detonations: Mutex::new(Vec::new()),
+            ignitions: Mutex::new(Vec::new()),
+            criticality: 1.0,
+            day_length: 0,
+            ambient: ROOM_TEMPERATURE,
+            sky: 0,
+            weather: Weather::Clear,
+            weather_intensity: WEATHER_INTENSITIES[1],
+            strike: None,
+            bolt: Vec::new(),
+            bolt_flash: 0,
         }
     }
 
-    if in_bounds_right(x + 1) {
-        let right_cell_type = cells[x + 1][y].ty;
-
-        if burn_types.contains(&right_cell_type) {
-            if should_spread {
-                cells[x][y].color = cell_type_color_random(&cells[x][y], rng);
-                spread_to_cell(cells, (x, y), (x + 1, y))
-            }
-        } else if right_cell_type == CellType::Water {
-            cells[x][y] = Cell::from(CellType::Steam, rng);
-            return;
+    // view for cells starting at column x_offset of the grid
+    fn window(&self, x_offset: usize) -> TrackingWindow<'_> {
+        TrackingWindow {
+            tracking: self,
+            x_offset,
         }
     }
 
-    if in_bounds_top(y as isize - 1) {
-        let top_cell_type = cells[x][y - 1].ty;
+    fn mark_region_dirty(&self, top_left: (usize, usize), bottom_right: (usize, usize)) {
+        let bottom_right = (
+            bottom_right.0.min(WIDTH - 1),
+            bottom_right.1.min(HEIGHT - 1),
+        );
 
-        if burn_types.contains(&top_cell_type) {
-            if should_spread {
-                cells[x][y].color = cell_type_color_random(&cells[x][y], rng);
-                spread_to_cell(cells, (x, y), (x, y - 1))
+        for chunk_y in top_left.1 / CHUNK_SIZE..=bottom_right.1 / CHUNK_SIZE {
+            for chunk_x in top_left.0 / CHUNK_SIZE..=bottom_right.0 / CHUNK_SIZE {
+                self.dirty_chunks[chunk_y * CHUNKS_X + chunk_x].store(true, Ordering::Relaxed);
             }
-        } else if top_cell_type == CellType::Water {
-            cells[x][y] = Cell::from(CellType::Steam, rng);
-            return;
         }
     }
+}
 
-    if in_bounds_bottom(y + 1) {
-        let bottom_cell_type = cells[x][y + 1].ty;
+// the update functions only see a window of columns so positions they pass in are relative to it
+struct TrackingWindow<'a> {
+    tracking: &'a Tracking,
+    x_offset: usize,
+}
 
-        if burn_types.contains(&bottom_cell_type) {
-            if should_spread {
-                cells[x][y].color = cell_type_color_random(&cells[x][y], rng);
-                spread_to_cell(cells, (x, y), (x, y + 1))
-            }
-        } else if bottom_cell_type == CellType::Water {
-            cells[x][y] = Cell::from(CellType::Steam, rng);
-            return;
-        }
+impl TrackingWindow<'_> {
+    fn row_count(&self, y: usize) -> u32 {
+        self.tracking.row_counts[y].load(Ordering::Relaxed)
+    }
+
+    fn add_to_row(&self, y: usize) {
+        self.tracking.row_counts[y].fetch_add(1, Ordering::Relaxed);
     }
 
-    if in_bounds_left(x as isize - 1) && in_bounds_top(y as isize - 1) {
-        let top_left_cell_type = cells[x - 1][y - 1].ty;
+    fn remove_from_row(&self, y: usize) {
+        self.tracking.row_counts[y].fetch_sub(1, Ordering::Relaxed);
+    }
 
-        if burn_types.contains(&top_left_cell_type) {
-            if should_spread {
-                cells[x][y].color = cell_type_color_random(&cells[x][y], rng);
-                spread_to_cell(cells, (x, y), (x - 1, y - 1))
-            }
-        } else if top_left_cell_type == CellType::Water {
-            cells[x][y] = Cell::from(CellType::Steam, rng);
-            return;
-        }
+    fn generation(&self) -> u8 {
+        self.tracking.generation
     }
 
-    if in_bounds_left(x as isize - 1) && in_bounds_bottom(y + 1) {
-        let bottom_left_cell_type = cells[x - 1][y + 1].ty;
+    fn tick(&self) -> u64 {
+        self.tracking.tick
+    }
 
-        if burn_types.contains(&bottom_left_cell_type) {
-            if should_spread {
-                cells[x][y].color = cell_type_color_random(&cells[x][y], rng);
-                spread_to_cell(cells, (x, y), (x - 1, y + 1))
-            }
-        } else if bottom_left_cell_type == CellType::Water {
-            cells[x][y] = Cell::from(CellType::Steam, rng);
-            return;
+    // painted fields override the global gravity inside them
+    fn gravity_at(&self, (x, y): (usize, usize)) -> (isize, isize) {
+        match self.tracking.fields[y * WIDTH + self.x_offset + x] {
+            NO_FIELD => self.tracking.gravity,
+            field => DIRECTIONS[field as usize - 1],
         }
     }
 
-    if in_bounds_right(x + 1) && in_bounds_top(y as isize - 1) {
-        let top_right_cell_type = cells[x + 1][y - 1].ty;
+    fn wind(&self) -> f32 {
+        let gust = (self.tracking.tick as f32 * GUST_RATE).sin() * GUST_STRENGTH;
+        self.tracking.wind * (1.0 + gust)
+    }
 
-        if burn_types.contains(&top_right_cell_type) {
-            if should_spread {
-                cells[x][y].color = cell_type_color_random(&cells[x][y], rng);
-                spread_to_cell(cells, (x, y), (x + 1, y - 1))
-            }
-        } else if top_right_cell_type == CellType::Water {
-            cells[x][y] = Cell::from(CellType::Steam, rng);
-            return;
-        }
+    fn evaporation(&self) -> f32 {
+        self.tracking.evaporation
     }
 
-    if in_bounds_right(x + 1)
-        && in_bounds_bottom(y + 1)
-        && is_empty(cells, x + 1, y + 1, burn_types)
-    {
-        let bottom_right_cell_type = cells[x + 1][y + 1].ty;
+    fn criticality(&self) -> f32 {
+        self.tracking.criticality
+    }
 
-        if burn_types.contains(&bottom_right_cell_type) {
-            if should_spread {
-                cells[x][y].color = cell_type_color_random(&cells[x][y], rng);
-                spread_to_cell(cells, (x, y), (x + 1, y + 1))
-            }
-        } else if bottom_right_cell_type == CellType::Water {
-            cells[x][y] = Cell::from(CellType::Steam, rng);
-            return;
+    fn ambient(&self) -> u8 {
+        self.tracking.ambient
+    }
+
+    fn weather_intensity(&self) -> f32 {
+        self.tracking.weather_intensity
+    }
+
+    fn spread_limit(&self) -> usize {
+        self.tracking.spread_limit
+    }
+
+    // only kept while there are rigid bodies to throw
+    fn record_blast(&self, (x, y): (usize, usize), radius: usize, power: f32) {
+        if self.tracking.bodies.is_some() {
+            self.tracking.blasts.lock().unwrap().push(Blast {
+                center: (x + self.x_offset, y),
+                radius,
+                power,
+            });
         }
     }
 
-    if !should_spread {
-        return;
+    fn detonate(&self, (x, y): (usize, usize), radius: usize) {
+        self.tracking
+            .detonations
+            .lock()
+            .unwrap()
+            .push(((x + self.x_offset, y), radius));
     }
 
-    cells[x][y] = if rng.f32() < 0.125 {
-        Cell::from(CellType::Smoke, rng)
-    } else {
-        Cell::from(CellType::Air, rng)
-    };
-}
+    fn ignite(&self, (x, y): (usize, usize)) {
+        self.tracking
+            .ignitions
+            .lock()
+            .unwrap()
+            .push((x + self.x_offset, y));
+    }
 
-fn update_sand(cells: &mut [Vec<Cell>], x: usize, y: usize, empty_types: &[CellType], rng: &Rng) {
-    generic_fall(
-        cells,
-        (x, y),
-        empty_types,
-        MAX_VELOCITY,
-        ACCELERATION,
-        false,
-        rng,
-    );
-}
+    fn charge_at(&self, (x, y): (usize, usize)) -> u8 {
+        self.tracking.charge[y * WIDTH + self.x_offset + x]
+    }
 
-fn update_water(cells: &mut [Vec<Cell>], x: usize, y: usize, empty_types: &[CellType], rng: &Rng) {
-    if rng.f32() < 0.125 && cells[x][y].velocity < 0.1 {
-        cells[x][y].color = cell_type_color_random(&cells[x][y], rng);
+    fn mark_dirty(&self, x: usize, y: usize) {
+        let x = x + self.x_offset;
+        self.tracking.dirty_chunks[(y / CHUNK_SIZE) * CHUNKS_X + x / CHUNK_SIZE]
+            .store(true, Ordering::Relaxed);
     }
+}
 
-    generic_fluid(
-        cells,
-        (x, y),
-        empty_types,
-        MAX_VELOCITY,
-        ACCELERATION,
-        false,
-        rng,
-    );
+// one tick of the update as a queue of strip batches, so a tick that cant finish inside a frame can carry on in the next
+// one while the app keeps drawing and taking input at full speed
+struct TickProgress {
+    tick: u64,
+    // the strips still to update for each pass and phase, in the order they have to run
+    stages: VecDeque<(usize, usize, Vec<usize>)>,
+    // total time spent updating this tick across every frame it was spread over
+    elapsed: Duration,
 }
 
-fn update_smoke(cells: &mut [Vec<Cell>], x: usize, y: usize, empty_types: &[CellType], rng: &Rng) {
-    if cells[x][y].lifetime == 0 {
-        cells[x][y] = Cell::from(CellType::Air, rng);
+// the day starts at midday, the ambient temperature and the sky follow the sun down to midnight halfway through it and
+// back up again
+fn update_ambient(tracking: &mut Tracking) {
+    if tracking.day_length == 0 {
         return;
     }
 
-    cells[x][y].lifetime -= 1;
-
-    cells[x][y].color = interpolate_color(
-        &SMOKE_COLOR_LIGHT,
-        &SMOKE_COLOR_DARK,
-        cells[x][y].lifetime as f32 / SMOKE_LIFETIME as f32,
-    );
+    let time = (tracking.tick % tracking.day_length) as f32 / tracking.day_length as f32;
+    let daylight = (1.0 + (time * 2.0 * PI).cos()) / 2.0;
+    tracking.ambient =
+        NIGHT_TEMPERATURE + ((DAY_TEMPERATURE - NIGHT_TEMPERATURE) as f32 * daylight).round() as u8;
 
-    generic_fluid(
-        cells,
-        (x, y),
-        empty_types,
-        SMOKE_MAX_VELOCITY,
-        SMOKE_ACCELERATION,
-        true,
-        rng,
-    );
+    let sky = (daylight * SKY_STEPS as f32).round() as u8;
+    if sky != tracking.sky {
+        tracking.sky = sky;
+        tracking.mark_region_dirty((0, 0), (WIDTH - 1, HEIGHT - 1));
+    }
 }
 
-fn update_steam(cells: &mut [Vec<Cell>], x: usize, y: usize, empty_types: &[CellType], rng: &Rng) {
-    if cells[x][y].lifetime == 0 {
-        if rng.f32() < 0.5_f32.powf(6.0) {
-            cells[x][y] = Cell::from(CellType::Water, rng)
-        } else {
-            cells[x][y] = Cell::from(CellType::Air, rng)
-        }
-        return;
-    }
+fn start_tick(
+    cells: &mut Grid,
+    tracking: &mut Tracking,
+    profiler: &mut Profiler,
+    degradation: Degradation,
+    seed: u64,
+    tick: u64,
+) -> TickProgress {
+    let generation = tick % GENERATIONS;
+    let clear_start = Instant::now();
 
-    cells[x][y].lifetime -= 1;
+    // first generation of a new cycle, wipe the stamps left over from the last one
+    if generation == 0 {
+        cells.cells_mut().par_iter_mut().for_each(Cell::clear_moved);
 
-    cells[x][y].color = interpolate_color(
-        &STEAM_COLOR_LIGHT,
-        &STEAM_COLOR_DARK,
-        cells[x][y].lifetime as f32 / STEAM_LIFETIME as f32,
-    );
+        profiler.record(Scope::ClearMoved, clear_start.elapsed());
+    }
 
-    generic_fluid(
-        cells,
-        (x, y),
-        empty_types,
-        STEAM_MAX_VELOCITY,
-        STEAM_ACCELERATION,
-        true,
-        rng,
-    );
-}
+    tracking.generation = generation as u8 + 1;
+    tracking.tick = tick;
+    update_ambient(tracking);
+    tracking.spread_limit = if degradation >= Degradation::ShortSpread {
+        SHORT_SPREAD
+    } else {
+        usize::MAX
+    };
 
-fn generic_fluid(
-    cells: &mut [Vec<Cell>],
-    cell_pos: (usize, usize),
-    empty_types: &[CellType],
-    max_velocity: f32,
-    acceleration: f32,
-    inverted: bool,
-    rng: &Rng,
-) -> Option<(usize, usize)> {
-    // todo something like: if the cell has a low velocity falling down then randomly spread to the side, will stop some water cells standing on top of others without spreading i think
-    if let Some(fall_result) = generic_fall(
-        cells,
-        cell_pos,
-        empty_types,
-        max_velocity,
-        acceleration,
-        inverted,
-        rng,
-    ) {
-        return Some(fall_result);
+    // the pressure pass sums water down the columns so only works with gravity pointing down them
+    if tracking.gravity == DOWN {
+        let pressure_start = Instant::now();
+        pressure::update(cells, tracking);
+        profiler.record(Scope::Pressure, pressure_start.elapsed());
     }
 
-    let spread_factor = (cells[cell_pos.0][cell_pos.1].velocity + 1.0) as usize;
+    // the strips use the streams before this one
+    let electricity_start = Instant::now();
+    let strip_count = WIDTH.div_ceil(STRIP_WIDTH) as u64;
+    let rng = Rng::with_seed(stream_seed(seed, tick, 2 * strip_count));
+    electricity::update(cells, tracking, &rng);
+    profiler.record(Scope::Electricity, electricity_start.elapsed());
 
-    let furthest_left = furthest_by_vector(cells, cell_pos, spread_factor, empty_types, (-1, 0));
-    let furthest_right = furthest_by_vector(cells, cell_pos, spread_factor, empty_types, (1, 0));
+    // lasers need the charge from this tick
+    let light_start = Instant::now();
+    light::update(cells, tracking, &rng);
+    profiler.record(Scope::Light, light_start.elapsed());
 
-    if let (Some(furthest_left), Some(furthest_right)) = (furthest_left, furthest_right) {
-        if rng.bool() {
-            swap_cells(cells, cell_pos, (furthest_right.0, furthest_right.1));
-            return Some(furthest_right);
-        } else {
-            swap_cells(cells, cell_pos, (furthest_left.0, furthest_left.1));
-            return Some(furthest_left);
+    let particles_start = Instant::now();
+    radioactivity::update(cells, tracking, &rng);
+    embers::update(cells, tracking, &rng);
+    fungus::update(cells, tracking, &rng);
+    particles::update(cells, tracking, &rng);
+    profiler.record(Scope::Particles, particles_start.elapsed());
+
+    // before the bodies so they feel these blasts straight away
+    let blasts_start = Instant::now();
+    hydrogen::update(cells, tracking, &rng);
+    detonate(cells, tracking, &rng);
+    profiler.record(Scope::Blasts, blasts_start.elapsed());
+
+    let bodies_start = Instant::now();
+    rigid::update(cells, tracking, &rng);
+    profiler.record(Scope::Bodies, bodies_start.elapsed());
+
+    let goo_start = Instant::now();
+    goo::update(cells, tracking, &rng);
+    profiler.record(Scope::Goo, goo_start.elapsed());
+
+    let portals_start = Instant::now();
+    portal::update(cells, tracking, &rng);
+    profiler.record(Scope::Portals, portals_start.elapsed());
+
+    // whatever falls in along the top edge starts falling in the strips this same tick
+    weather::update(cells, tracking, &rng);
+    lightning::update(cells, tracking, &rng);
+
+    // traverse the odd indices left to right and the even indices left to right, removes any sort of cell movement priority
+    // each pass does the even strips then the odd strips, the strips of a phase never overlap so they run in parallel
+    let mut stages = VecDeque::new();
+    for i in 0..=1 {
+        for phase in 0..=1 {
+            let strips = (phase..WIDTH.div_ceil(STRIP_WIDTH))
+                .step_by(2)
+                // under load every other pair of strips sits out each tick, so each strip is updated every other tick
+                .filter(|strip| {
+                    degradation < Degradation::HalfStrips || (strip / 2) as u64 % 2 == tick % 2
+                })
+                .collect();
+
+            stages.push_back((i, phase, strips));
         }
-    } else if let Some(furthest_left) = furthest_left {
-        swap_cells(cells, cell_pos, (furthest_left.0, furthest_left.1));
-        return Some(furthest_left);
-    } else if let Some(furthest_right) = furthest_right {
-        swap_cells(cells, cell_pos, (furthest_right.0, furthest_right.1));
-        return Some(furthest_right);
     }
 
-    None
+    TickProgress {
+        tick,
+        stages,
+        elapsed: clear_start.elapsed(),
+    }
 }
 
-fn generic_fall(
-    cells: &mut [Vec<Cell>],
-    cell_pos: (usize, usize),
-    fall_through_types: &[CellType],
-    max_velocity: f32,
-    acceleration: f32,
-    inverted: bool,
-    rng: &Rng,
-) -> Option<(usize, usize)> {
-    let down = if inverted { -1 } else { 1 };
+// updates batches of strips until the tick is done or the frame budget is used up, returns whether the tick finished.
+// without a budget the whole tick is done in one go
+fn continue_tick(
+    cells: &mut Grid,
+    tracking: &Tracking,
+    profiler: &mut Profiler,
+    progress: &mut TickProgress,
+    seed: u64,
+    budget: Option<Duration>,
+) -> bool {
+    let frame_start = Instant::now();
+    let strip_count = WIDTH.div_ceil(STRIP_WIDTH);
+    let tick = progress.tick;
 
-    if let Some(furthest_down) = furthest_by_vector(
-        cells,
-        cell_pos,
-        cells[cell_pos.0][cell_pos.1].velocity as usize,
-        fall_through_types,
-        (0, down),
-    ) {
-        cells[cell_pos.0][cell_pos.1].velocity =
-            (cells[cell_pos.0][cell_pos.1].velocity + acceleration).min(max_velocity);
-        swap_cells(cells, cell_pos, (furthest_down.0, furthest_down.1));
-        // todo swap current with furthest, then current with furthest - 1 = put whatever was in furthest on top of current
+    while let Some((i, phase, strips)) = progress.stages.front_mut() {
+        let (i, phase) = (*i, *phase);
+        let batch_start = Instant::now();
 
-        return Some(furthest_down);
-    }
+        // one strip per thread at a time when there is a budget to check against between batches
+        let batch_size = match budget {
+            Some(_) => rayon::current_num_threads().min(strips.len()),
+            None => strips.len(),
+        };
+        let batch = strips.drain(..batch_size).collect::<Vec<_>>();
 
-    let furthest_down_left = furthest_by_vector(
-        cells,
-        cell_pos,
-        cells[cell_pos.0][cell_pos.1].velocity as usize,
-        fall_through_types,
-        (-1, down),
-    );
-    let furthest_down_right = furthest_by_vector(
-        cells,
-        cell_pos,
-        cells[cell_pos.0][cell_pos.1].velocity as usize,
-        fall_through_types,
-        (1, down),
+        if strips.is_empty() {
+            progress.stages.pop_front();
+        }
+
+        let profiler_ref = &*profiler;
+        strip_windows(cells, phase)
+            .into_par_iter()
+            .filter(|(_, strip)| batch.contains(strip))
+            .for_each(|(mut window, strip)| {
+                let stream = (i * strip_count + strip) as u64;
+                let rng = Rng::with_seed(stream_seed(seed, tick, stream));
+                let tracking = tracking.window(window.x_offset());
+
+                update_strip(i, &mut window, &tracking, strip, &rng, profiler_ref);
+            });
+
+        let pass_scope = if i == 0 {
+            Scope::UpdateEvens
+        } else {
+            Scope::UpdateOdds
+        };
+        profiler.record(pass_scope, batch_start.elapsed());
+
+        if budget.is_some_and(|budget| frame_start.elapsed() >= budget) {
+            break;
+        }
+    }
+
+    progress.elapsed += frame_start.elapsed();
+
+    progress.stages.is_empty()
+}
+
+fn update_cells(
+    cells: &mut Grid,
+    tracking: &mut Tracking,
+    profiler: &mut Profiler,
+    degradation: Degradation,
+    seed: u64,
+    tick: u64,
+) {
+    let mut progress = start_tick(cells, tracking, profiler, degradation, seed, tick);
+    continue_tick(cells, tracking, profiler, &mut progress, seed, None);
+}
+
+// splits the grid into disjoint mutable windows, one per strip in this phase, each padded by MAX_REACH columns on both
+// sides so every cell in the strip can see everything it is allowed to touch
+fn strip_windows(cells: &mut Grid, phase: usize) -> Vec<(GridWindow<'_>, usize)> {
+    let strips = (phase..WIDTH.div_ceil(STRIP_WIDTH)).step_by(2);
+
+    let windows = cells.column_windows(strips.clone().map(|strip| {
+        let window_start = (strip * STRIP_WIDTH).saturating_sub(MAX_REACH);
+        let window_end = ((strip + 1) * STRIP_WIDTH + MAX_REACH).min(WIDTH);
+
+        window_start..window_end
+    }));
+
+    windows.into_iter().zip(strips).collect()
+}
+
+fn update_strip(
+    i: usize,
+    window: &mut GridWindow,
+    tracking: &TrackingWindow,
+    strip: usize,
+    rng: &Rng,
+    profiler: &Profiler,
+) {
+    let strip_start = strip * STRIP_WIDTH;
+    let strip_end = ((strip + 1) * STRIP_WIDTH).min(WIDTH);
+
+    let mut element_nanos = [0; CellType::CARDINALITY];
+    let mut update = |x: usize, y: usize| {
+        if profiler.enabled {
+            let cell_type = window[(x - tracking.x_offset, y)].ty;
+            let start = Instant::now();
+
+            update_cell(i, window, tracking, x, x - tracking.x_offset, y, rng);
+
+            element_nanos[cell_type as usize] += start.elapsed().as_nanos() as u64;
+        } else {
+            update_cell(i, window, tracking, x, x - tracking.x_offset, y, rng)
+        }
+    };
+
+    for y in (0..HEIGHT).rev() {
+        // nothing but air, anything that moves into this row during the tick is already marked as moved
+        if tracking.row_count(y) == 0 {
+            continue;
+        }
+
+        // forward pass, odds only
+        if i == 1 {
+            for x in strip_start..strip_end {
+                update(x, y)
+            }
+        // reverse pass, evens only
+        } else {
+            for x in (strip_start..strip_end).rev() {
+                update(x, y)
+            }
+        }
+    }
+
+    if profiler.enabled {
+        profiler.add_element_nanos(&element_nanos);
+    }
+}
+
+// splitmix64 finaliser, gives every (tick, strip) its own uncorrelated rng stream so the sim stays deterministic for a
+// given seed no matter how rayon schedules the strips
+fn stream_seed(seed: u64, tick: u64, stream: u64) -> u64 {
+    let mut z = seed
+        ^ tick.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ stream.wrapping_mul(0xd1b5_4a32_d192_ed03);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// x is the column in the whole grid, used for the odd/even pass, local_x is the column within cells
+fn update_cell(
+    i: usize,
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    local_x: usize,
+    y: usize,
+    rng: &Rng,
+) {
+    if x % 2 == i {
+        return;
+    }
+
+    let x = local_x;
+    let cell = &cells[(x, y)];
+
+    if cell.moved(tracking.generation())
+        || !tracking
+            .tick()
+            .is_multiple_of(cell_type_tick_interval(cell.ty))
+    {
+        return;
+    }
+
+    if change_phase(cells, tracking, x, y, rng)
+        || react(cells, tracking, x, y, rng)
+        || cell_type_corrosive(cells[(x, y)].ty)
+            .is_some_and(|corrosive| corrosion::corrode(cells, tracking, (x, y), corrosive, rng))
+    {
+        return;
+    }
+
+    if let Some(porous) = cell_type_porous(cells[(x, y)].ty) {
+        absorption::absorb(cells, tracking, (x, y), porous, rng);
+    }
+
+    if moss::HOSTS.contains(&cells[(x, y)].ty) {
+        moss::colonize(cells, tracking, (x, y), rng);
+    }
+
+    match cells[(x, y)].ty {
+        CellType::Sand => update_sand(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Nitro,
+            ],
+            rng,
+        ),
+        CellType::Salt => update_salt(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Nitro,
+            ],
+            rng,
+        ),
+        CellType::Antimatter => {
+            update_antimatter(cells, tracking, x, y, &[CellType::Air, CellType::Fire], rng)
+        }
+        CellType::Gunpowder => update_gunpowder(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Nitro,
+            ],
+            rng,
+        ),
+        CellType::Water => update_water(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Napalm,
+            ],
+            rng,
+        ),
+        CellType::Napalm => update_napalm(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+            ],
+            rng,
+        ),
+        CellType::Oil => update_oil(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+            ],
+            rng,
+        ),
+        CellType::Honey => update_honey(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+            ],
+            rng,
+        ),
+        CellType::Soap => update_soap(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Nitro,
+            ],
+            rng,
+        ),
+        CellType::Foam => update_foam(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+            ],
+            rng,
+        ),
+        CellType::Nitro => update_nitro(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+            ],
+            rng,
+        ),
+        CellType::Acid => update_acid(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+            ],
+            rng,
+        ),
+        CellType::Fire => update_fire(cells, tracking, x, y, rng),
+        CellType::Tnt => update_tnt(cells, tracking, x, y, rng),
+        CellType::Detonator => update_detonator(cells, tracking, x, y, rng),
+        CellType::Fuse => update_fuse(cells, tracking, x, y, rng),
+        CellType::Timer => timer::tick_timer(cells, tracking, (x, y), rng),
+        CellType::Delay => timer::tick_delay(cells, tracking, (x, y), rng),
+        CellType::Fan => update_fan(cells, tracking, x, y),
+        CellType::Conveyor => update_conveyor(cells, tracking, x, y),
+        CellType::Heater => update_heater(cells, tracking, x, y, rng),
+        CellType::Cooler => update_cooler(cells, x, y),
+        CellType::Metal => update_metal(cells, tracking, x, y, rng),
+        CellType::CharredWood => update_charred_wood(cells, tracking, x, y, rng),
+        CellType::Rust => update_rust(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Nitro,
+            ],
+            rng,
+        ),
+        CellType::Lava | CellType::MoltenMetal => update_lava(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Nitro,
+            ],
+            rng,
+        ),
+        CellType::Slag => update_slag(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Nitro,
+                CellType::Lava,
+                CellType::MoltenMetal,
+            ],
+            rng,
+        ),
+        CellType::Spout => spout::pour(cells, tracking, (x, y), rng),
+        CellType::Drain => spout::drain(cells, tracking, (x, y), rng),
+        CellType::Clone => spout::duplicate(cells, tracking, (x, y), rng),
+        CellType::Void => spout::consume(cells, tracking, (x, y), rng),
+        CellType::Smoke => update_smoke(cells, tracking, x, y, &[CellType::Air], rng),
+        CellType::Steam => update_steam(cells, tracking, x, y, &[CellType::Air], rng),
+        CellType::Hydrogen => update_hydrogen(
+            cells,
+            tracking,
+            x,
+            y,
+            &[CellType::Air, CellType::Smoke, CellType::Steam],
+            rng,
+        ),
+        CellType::Methane => update_methane(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Smoke,
+                CellType::Steam,
+                CellType::ToxicGas,
+            ],
+            rng,
+        ),
+        CellType::ToxicGas => update_toxic_gas(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Smoke,
+                CellType::Steam,
+                CellType::Hydrogen,
+            ],
+            rng,
+        ),
+        CellType::Seed => update_seed(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Nitro,
+            ],
+            rng,
+        ),
+        CellType::Plant => growth::grow(cells, tracking, (x, y), &PLANT, rng),
+        CellType::Vine => growth::creep(cells, tracking, (x, y), &VINE, rng),
+        CellType::Moss => moss::grow(cells, tracking, (x, y), rng),
+        CellType::Fungus => fungus::grow(cells, tracking, (x, y), rng),
+        CellType::Goo => goo::ooze(cells, tracking, (x, y), &GOO, rng),
+        CellType::Slime => goo::ooze(cells, tracking, (x, y), &SLIME, rng),
+        CellType::Ant => creature::crawl(
+            cells,
+            tracking,
+            (x, y),
+            &ANT,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Nitro,
+            ],
+            rng,
+        ),
+        CellType::Fish => creature::crawl(
+            cells,
+            tracking,
+            (x, y),
+            &FISH,
+            &[
+                CellType::Air,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+            ],
+            rng,
+        ),
+        CellType::Virus => contagion::spread(cells, tracking, (x, y), &VIRUS, rng),
+        CellType::Dirt => update_dirt(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Nitro,
+            ],
+            rng,
+        ),
+        CellType::Mud => update_mud(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Nitro,
+            ],
+            rng,
+        ),
+        CellType::Ash => update_ash(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Nitro,
+            ],
+            rng,
+        ),
+        CellType::Stone | CellType::Uranium => update_stone(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Water,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+                CellType::Oil,
+                CellType::Nitro,
+            ],
+            rng,
+        ),
+        CellType::Snow => update_snow(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+            ],
+            rng,
+        ),
+        CellType::Confetti => update_confetti(
+            cells,
+            tracking,
+            x,
+            y,
+            &[
+                CellType::Air,
+                CellType::Steam,
+                CellType::Smoke,
+                CellType::ToxicGas,
+                CellType::Methane,
+            ],
+            rng,
+        ),
+        _ => (),
+    }
+}
+
+// turns the cell into its hotter or colder phase once it is past the temperature in the element table, true if it did.
+// otherwise it drifts a degree back towards room temperature
+fn change_phase(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    rng: &Rng,
+) -> bool {
+    let cell = cells[(x, y)];
+    let hotter = cell_type_hotter_phase(cell.ty);
+    let colder = cell_type_colder_phase(cell.ty);
+    if hotter.is_none() && colder.is_none() {
+        return false;
+    }
+
+    let phase = hotter
+        .filter(|&(temperature, _)| cell.temperature >= temperature)
+        .or(colder.filter(|&(temperature, _)| cell.temperature < temperature));
+    if let Some((_, cell_type)) = phase {
+        set_cell(cells, tracking, (x, y), transform(&cell, cell_type, rng));
+        cells[(x, y)].set_moved(tracking.generation());
+        return true;
+    }
+
+    // out in the open it drifts towards the time of day instead
+    let ambient = tracking.ambient();
+    let target = if ambient != ROOM_TEMPERATURE && touches_air(cells, (x, y)) {
+        ambient
+    } else {
+        ROOM_TEMPERATURE
+    };
+    let temperature = &mut cells[(x, y)].temperature;
+    match (*temperature).cmp(&target) {
+        std::cmp::Ordering::Greater => *temperature -= 1,
+        std::cmp::Ordering::Less => *temperature += 1,
+        std::cmp::Ordering::Equal => (),
+    }
+
+    false
+}
+
+// a cell turning into another type, salty water boiling away leaves its salt behind
+fn transform(cell: &Cell, cell_type: CellType, rng: &Rng) -> Cell {
+    if cell.ty == CellType::Water && cell_type == CellType::Steam && dissolved_salt(cell) > 0 {
+        Cell::from(CellType::Salt, rng)
+    } else {
+        Cell::from(cell_type, rng)
+    }
+}
+
+// the cell reacts with the first neighbour the reaction table has it reacting with, true if that replaced it
+fn react(cells: &mut GridWindow, tracking: &TrackingWindow, x: usize, y: usize, rng: &Rng) -> bool {
+    let cell_type = cells[(x, y)].ty;
+    if !reactions::starts_reaction(cell_type) {
+        return false;
+    }
+
+    for direction in NEIGHBOURS {
+        let Some((neighbour_pos, neighbour)) = cells.ray((x, y), direction, 1).next() else {
+            continue;
+        };
+        let Some(reaction) = reactions::reaction(cell_type, neighbour.ty) else {
+            continue;
+        };
+        if rng.f32() >= reaction.probability {
+            continue;
+        }
+
+        for (pos, product) in [
+            ((x, y), reaction.products.0),
+            (neighbour_pos, reaction.products.1),
+        ] {
+            if cells[pos].ty != product {
+                let new_cell = transform(&cells[pos], product, rng);
+                set_cell(cells, tracking, pos, new_cell);
+                cells[pos].set_moved(tracking.generation());
+            }
+        }
+
+        if reaction.heat > 0 {
+            for direction in NEIGHBOURS {
+                let Some((pos, cell)) = cells.ray((x, y), direction, 1).next() else {
+                    continue;
+                };
+                if cell_type_ignition_temperature(cell.ty).is_some() {
+                    cells[pos].temperature = cells[pos].temperature.saturating_add(reaction.heat);
+                }
+            }
+        }
+
+        return cells[(x, y)].ty != cell_type;
+    }
+
+    false
+}
+
+// fire heats the burnables around it until they catch, mostly the ones above it, and goes out once its fuel is gone. it
+// needs air next to it to keep burning, shut in it is smothered. burning coal melts what is around it as well
+fn update_fire(cells: &mut GridWindow, tracking: &TrackingWindow, x: usize, y: usize, rng: &Rng) {
+    let mut has_air = false;
+    // the wind fans the flames onto whatever is downwind
+    let gravity = tracking.gravity_at((x, y));
+    let across = across(gravity);
+    let wind = tracking.wind();
+
+    for direction in NEIGHBOURS {
+        let Some((neighbour_pos, neighbour)) = cells.ray((x, y), direction, 1).next() else {
+            continue;
+        };
+        let neighbour_type = neighbour.ty;
+
+        if neighbour_type == CellType::Air {
+            has_air = true;
+        } else if let Some(ignition_temperature) = cell_type_ignition_temperature(neighbour_type) {
+            let downwind = (direction.0 * across.0 + direction.1 * across.1) as f32 * wind;
+            let rising = match direction.0 * gravity.0 + direction.1 * gravity.1 {
+                -1 => FIRE_RISING_HEAT,
+                0 => 1.0,
+                _ => FIRE_FALLING_HEAT,
+            };
+            let heat = (rng.u8(0..=FIRE_HEAT * 2) as f32 * rising) as u8
+                + (downwind.max(0.0) * FIRE_HEAT as f32) as u8;
+
+            let neighbour = &mut cells[neighbour_pos];
+            neighbour.temperature = neighbour.temperature.saturating_add(heat);
+
+            // wood buried inside wood cant burn until whatever is around it has burnt away, and wet wood until it has
+            // dried out. explosives go off in their own update once they are hot enough
+            if !explosive(neighbour_type)
+                && neighbour.temperature >= ignition_temperature
+                && !absorption::soaked(neighbour)
+                && touches_air(cells, neighbour_pos)
+            {
+                let fire = ignite(&cells[neighbour_pos], rng);
+                set_cell(cells, tracking, neighbour_pos, fire);
+                cells[neighbour_pos].set_moved(tracking.generation());
+
+                cells[(x, y)].color_index = cell_type_color_index_random(CellType::Fire, rng);
+                tracking.mark_dirty(x, y);
+            }
+        }
+    }
+
+    if cells[(x, y)].lifetime == CellType::Coal as u8 {
+        for direction in NEIGHBOURS {
+            let Some((neighbour_pos, neighbour)) = cells.ray((x, y), direction, 1).next() else {
+                continue;
+            };
+            if cell_type_hotter_phase(neighbour.ty).is_some() {
+                heat_cell(
+                    cells,
+                    tracking,
+                    neighbour_pos,
+                    rng.u8(0..=COAL_FIRE_HEAT * 2),
+                    rng,
+                );
+            }
+        }
+    }
+
+    // water near the fire heats up until it boils, ice and snow until they melt
+    for direction in NEIGHBOURS {
+        for step in 1..=WATER_HEAT_REACH {
+            let Some(pos) = cells
+                .ray((x, y), direction, step)
+                .nth(step - 1)
+                .filter(|(_, cell)| {
+                    matches!(cell.ty, CellType::Water | CellType::Ice | CellType::Snow)
+                })
+                .map(|(pos, _)| pos)
+            else {
+                continue;
+            };
+
+            let heat = rng.u8(0..=FIRE_HEAT * 2);
+            cells[pos].temperature = cells[pos].temperature.saturating_add(heat);
+        }
+    }
+
+    if has_air && cells[(x, y)].fuel > 0 {
+        cells[(x, y)].fuel -= 1;
+        return;
+    }
+
+    let burnt_cell = if has_air {
+        burnt_out(&cells[(x, y)], rng)
+    } else {
+        smothered(&cells[(x, y)], rng)
+    };
+    set_cell(cells, tracking, (x, y), burnt_cell);
+}
+
+// fire shut off from the air goes out, leaving what it was burning with the fuel it had left. it stays hot enough to
+// catch again as soon as it is heated with air beside it
+fn smothered(fire: &Cell, rng: &Rng) -> Cell {
+    let burning = all::<CellType>()
+        .nth(fire.lifetime as usize)
+        .unwrap_or_default();
+    let Some(ignition_temperature) = cell_type_ignition_temperature(burning) else {
+        return burnt_out(fire, rng);
+    };
+    if fire.fuel == 0 || explosive(burning) {
+        return burnt_out(fire, rng);
+    }
+
+    let mut smouldering = Cell::from(burning, rng);
+    smouldering.fuel = fire.fuel;
+    smouldering.temperature = ignition_temperature;
+    smouldering
+}
+
+// fire burning the cell, it keeps the cells fuel and remembers what it was so it leaves the right things behind
+fn catch_fire(cell: &Cell, rng: &Rng) -> Cell {
+    let mut fire = Cell::from(CellType::Fire, rng);
+    fire.fuel = cell.fuel;
+    fire.lifetime = cell.ty as u8;
+    fire
+}
+
+// wood chars instead of catching fire, see CHARRED_BURN_CHANCE, and napalm and fuses burn where they are, see
+// update_napalm and update_fuse
+fn ignite(cell: &Cell, rng: &Rng) -> Cell {
+    match cell.ty {
+        CellType::Wood => {
+            let mut charred = Cell::from(CellType::CharredWood, rng);
+            charred.fuel = cell.fuel;
+            charred.color_index = cell.color_index;
+            charred.temperature = cell.temperature;
+            charred
+        }
+        CellType::Napalm => {
+            let mut burning = *cell;
+            burning.lifetime = NAPALM_BURNING;
+            burning
+        }
+        // a fuse already burning keeps its own pace
+        CellType::Fuse if cell.lifetime == 0 => {
+            let mut burning = *cell;
+            burning.lifetime = FUSE_BURN_TICKS;
+            burning
+        }
+        CellType::Fuse => *cell,
+        _ => catch_fire(cell, rng),
+    }
+}
+
+// burnables past their ignition temperature catch fire if they have air to burn in like they do next to fire, tnt,
+// gunpowder and nitro go off in their own update once they are hot enough
+fn heat_cell(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    heat: u8,
+    rng: &Rng,
+) {
+    let cell = &mut cells[pos];
+    cell.temperature = cell.temperature.saturating_add(heat);
+
+    if !explosive(cell.ty)
+        && cell_type_ignition_temperature(cell.ty)
+            .is_some_and(|ignition_temperature| cell.temperature >= ignition_temperature)
+        && !absorption::soaked(cell)
+        && touches_air(cells, pos)
+    {
+        let fire = ignite(&cells[pos], rng);
+        set_cell(cells, tracking, pos, fire);
+        cells[pos].set_moved(tracking.generation());
+    }
+}
+
+// blows up instead of catching fire on the cpu
+fn explosive(cell_type: CellType) -> bool {
+    matches!(
+        cell_type,
+        CellType::Tnt | CellType::Gunpowder | CellType::Nitro | CellType::Hydrogen
+    )
+}
+
+// what fire leaves once its fuel is gone, fire from the brush or a blast counts as burning air
+fn burnt_out(fire: &Cell, rng: &Rng) -> Cell {
+    let burning = all::<CellType>()
+        .nth(fire.lifetime as usize)
+        .unwrap_or_default();
+    let (smoke_chance, residue) = cell_type_combustion(burning);
+
+    if rng.f32() < smoke_chance {
+        Cell::from(CellType::Smoke, rng)
+    } else {
+        Cell::from(residue, rng)
+    }
+}
+
+// stays where it is drawn. lit by fire, lava, charge or heat it counts down its lifetime from FUSE_BURN_TICKS, then
+// lights the unlit fuse and sets off the detonators around it and burns away to ash. nothing else catches from it, so
+// the time it takes to reach the end only depends on how long it is
+fn update_fuse(cells: &mut GridWindow, tracking: &TrackingWindow, x: usize, y: usize, rng: &Rng) {
+    if cells[(x, y)].lifetime == 0 {
+        if lit(cells, tracking, (x, y), FUSE_IGNITION_TEMPERATURE) {
+            cells[(x, y)].lifetime = FUSE_BURN_TICKS;
+            tracking.mark_dirty(x, y);
+        }
+        return;
+    }
+
+    cells[(x, y)].lifetime -= 1;
+    if cells[(x, y)].lifetime > 0 {
+        return;
+    }
+
+    for direction in NEIGHBOURS {
+        let Some((pos, neighbour)) = cells.ray((x, y), direction, 1).next() else {
+            continue;
+        };
+
+        match neighbour.ty {
+            CellType::Fuse if neighbour.lifetime == 0 => {
+                cells[pos].lifetime = FUSE_BURN_TICKS;
+                // so it doesnt start counting down until next tick
+                cells[pos].set_moved(tracking.generation());
+                tracking.mark_dirty(pos.0, pos.1);
+            }
+            CellType::Detonator => cells[pos].temperature = u8::MAX,
+            _ => (),
+        }
+    }
+
+    set_cell(cells, tracking, (x, y), Cell::from(CellType::Ash, rng));
+}
+
+// set off by fire or charge touching it or being heated like tnt, the blast goes off at the start of the next tick. the
+// radius of the blast is kept in the lifetime
+fn update_detonator(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    rng: &Rng,
+) {
+    let triggered = cells[(x, y)].temperature >= TNT_IGNITION_TEMPERATURE
+        || NEIGHBOURS.into_iter().any(|direction| {
+            cells
+                .ray((x, y), direction, 1)
+                .next()
+                .is_some_and(|(pos, neighbour)| {
+                    neighbour.ty == CellType::Fire || tracking.charge_at(pos) > 0
+                })
+        });
+
+    if triggered {
+        tracking.detonate((x, y), cells[(x, y)].lifetime as usize);
+        set_cell(cells, tracking, (x, y), Cell::from(CellType::Fire, rng));
+    }
+}
+
+// the detonators and tnt set off last tick blow up, over the whole grid so their blasts can be bigger than a strip could
+// hold. loose cells around the edge of each blast are thrown out as debris
+fn detonate(cells: &mut Grid, tracking: &mut Tracking, rng: &Rng) {
+    // strips push these in whatever order they finish, sorted so the same seed blows up the same way every run
+    let mut detonations = std::mem::take(tracking.detonations.get_mut().unwrap());
+    detonations.sort_unstable();
+    let mut thrown = Vec::new();
+
+    {
+        let mut cells = cells.window();
+        let tracking = tracking.window(0);
+
+        for (center, radius) in detonations {
+            debris::throw(
+                &mut cells,
+                &tracking,
+                center,
+                radius,
+                TNT_POWER,
+                &mut thrown,
+                rng,
+            );
+            explode(&mut cells, &tracking, center, radius, TNT_POWER, rng);
+        }
+    }
+
+    for debris in thrown {
+        particles::emit(&mut tracking.particles, debris);
+    }
+}
+
+// doesnt need air, heated past its ignition temperature by fire, sparks or another blast it goes off at the start of the
+// next tick
+fn update_tnt(cells: &mut GridWindow, tracking: &TrackingWindow, x: usize, y: usize, rng: &Rng) {
+    if cells[(x, y)].temperature >= TNT_IGNITION_TEMPERATURE {
+        tracking.detonate((x, y), TNT_RADIUS);
+        set_cell(cells, tracking, (x, y), Cell::from(CellType::Fire, rng));
+    }
+}
+
+// falls like sand, heated past its ignition temperature it pops with a small blast that sets off the grains around it
+fn update_gunpowder(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if cells[(x, y)].temperature >= GUNPOWDER_IGNITION_TEMPERATURE {
+        explode(
+            cells,
+            tracking,
+            (x, y),
+            GUNPOWDER_RADIUS,
+            GUNPOWDER_POWER,
+            rng,
+        );
+        return;
+    }
+
+    generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        false,
+        rng,
+    );
+}
+
+// everything within radius of center is hit by a blast of power, falling off to nothing at the edge. the middle half
+// turns to fire, wood and obsidian that get hit hard enough are smashed into flying debris, glass shatters, water boils
+// and anything loose is thrown away from the centre, rust crumbling as it goes. explosives in the blast are set off next
+// tick so explosions chain
+fn explode(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    center: (usize, usize),
+    radius: usize,
+    power: f32,
+    rng: &Rng,
+) {
+    let x_range = center.0.saturating_sub(radius)..(center.0 + radius + 1).min(cells.width());
+    let y_range = center.1.saturating_sub(radius)..(center.1 + radius + 1).min(HEIGHT);
+    tracking.record_blast(center, radius, power);
+
+    for y in y_range {
+        for x in x_range.clone() {
+            let (dx, dy) = (x as f32 - center.0 as f32, y as f32 - center.1 as f32);
+            let distance = (dx * dx + dy * dy).sqrt();
+            if distance > radius as f32 {
+                continue;
+            }
+
+            let strength = power * (1.0 - distance / radius as f32);
+            let cell = cells[(x, y)];
+
+            let mut hit = match cell.ty {
+                CellType::Air
+                | CellType::Fire
+                | CellType::Tnt
+                | CellType::Gunpowder
+                | CellType::Nitro
+                    if strength > power / 2.0 =>
+                {
+                    Cell::from(CellType::Fire, rng)
+                }
+                CellType::Air if rng.f32() < 0.25 => Cell::from(CellType::Smoke, rng),
+                CellType::Water if strength > power / 2.0 => transform(&cell, CellType::Steam, rng),
+                CellType::Wood | CellType::CharredWood if strength >= WOOD_STRENGTH => {
+                    Cell::from(CellType::Sand, rng)
+                }
+                // glass is brittle, any blast at all shatters it back into sand
+                CellType::Glass => Cell::from(CellType::Sand, rng),
+                // and pops foam
+                CellType::Foam => Cell::from(CellType::Air, rng),
+                CellType::Obsidian if strength >= OBSIDIAN_STRENGTH => {
+                    Cell::from(CellType::Sand, rng)
+                }
+                CellType::Wood
+                | CellType::Tnt
+                | CellType::Plant
+                | CellType::Vine
+                | CellType::Fungus
+                | CellType::Ant
+                | CellType::Fish
+                | CellType::Virus
+                | CellType::Coal
+                | CellType::Oil
+                | CellType::Honey
+                | CellType::Napalm
+                | CellType::Gunpowder
+                | CellType::Fuse
+                | CellType::Nitro
+                | CellType::Hydrogen
+                | CellType::Detonator => {
+                    let mut heated = cell;
+                    heated.temperature = u8::MAX;
+                    heated
+                }
+                CellType::Rust => {
+                    let mut crumbled = cell;
+                    crumbled.lifetime = 1;
+                    crumbled
+                }
+                CellType::Sand
+                | CellType::Salt
+                | CellType::Soap
+                | CellType::Seed
+                | CellType::Ash
+                | CellType::Snow
+                | CellType::Confetti
+                | CellType::Dirt
+                | CellType::Mud
+                | CellType::Water
+                | CellType::Acid
+                | CellType::Lava
+                | CellType::MoltenMetal
+                | CellType::Slag => cell,
+                _ => continue,
+            };
+
+            // pushed straight out, though nothing can be thrown against gravity so above the centre it only goes
+            // sideways
+            if matches!(
+                hit.ty,
+                CellType::Sand
+                    | CellType::Salt
+                    | CellType::Soap
+                    | CellType::Seed
+                    | CellType::Ash
+                    | CellType::Snow
+                    | CellType::Confetti
+                    | CellType::Dirt
+                    | CellType::Mud
+                    | CellType::Rust
+                    | CellType::Water
+                    | CellType::Acid
+            ) && distance > 0.0
+            {
+                let strength = if hit.ty == CellType::Confetti {
+                    strength * CONFETTI_BLAST
+                } else {
+                    strength
+                };
+                let down = tracking.gravity_at((x, y));
+                let across = across(down);
+                let sideways = dx * across.0 as f32 + dy * across.1 as f32;
+                let fall = dx * down.0 as f32 + dy * down.1 as f32;
+
+                hit.set_velocity_x(sideways / distance * strength);
+                hit.set_velocity(hit.velocity().max(fall / distance * strength));
+            }
+
+            set_cell(cells, tracking, (x, y), hit);
+        }
+    }
+}
+
+// fans dont age so keep the index of the direction they face in their lifetime
+fn fan_facing(cell: &Cell) -> (isize, isize) {
+    DIRECTIONS[cell.lifetime as usize]
+}
+
+// everything loose in front of a fan is moved a cell further from it, furthest first so a whole column moves together.
+// anything solid stops the fan reaching past it
+fn update_fan(cells: &mut GridWindow, tracking: &TrackingWindow, x: usize, y: usize) {
+    let facing = fan_facing(&cells[(x, y)]);
+    let reach = if facing.0 == 0 {
+        FAN_VERTICAL_REACH
+    } else {
+        FAN_REACH
+    };
+
+    let beam = cells
+        .ray((x, y), facing, reach + 1)
+        .take_while(|(_, cell)| blowable(cell.ty) || cell.ty == CellType::Air)
+        .map(|(pos, _)| pos)
+        .collect::<Vec<_>>();
+
+    let across = across(tracking.gravity_at((x, y)));
+    let sideways = facing.0 * across.0 + facing.1 * across.1;
+
+    for i in (0..beam.len().saturating_sub(1)).rev() {
+        let (from, to) = (beam[i], beam[i + 1]);
+        let (from_type, to_type) = (cells[from].ty, cells[to].ty);
+
+        // gases only push through air, anything heavier pushes through gases too
+        let gas = matches!(from_type, CellType::Smoke | CellType::Steam);
+        if !blowable(from_type)
+            || !(to_type == CellType::Air
+                || !gas && matches!(to_type, CellType::Smoke | CellType::Steam))
+        {
+            continue;
+        }
+
+        swap_cells(cells, tracking, from, to);
+
+        // thrown off the end when blowing sideways, otherwise it stops falling for as long as it is in the beam
+        cells[to].set_velocity(0.0);
+        cells[to].set_velocity_x(sideways as f32 * FAN_VELOCITY);
+    }
+}
+
+// conveyors dont age so keep whether they run the other way in their lifetime, they run across gravity, to the right
+// when it points down
+fn conveyor_sideways(cell: &Cell, gravity: (isize, isize)) -> (isize, isize) {
+    let across = across(gravity);
+    if cell.lifetime == 0 {
+        across
+    } else {
+        (-across.0, -across.1)
+    }
+}
+
+// whatever rests on top of a conveyor is carried a cell along it, into air or gas. the conveyor runs before the cell on it
+// so that cell has moved by the time its own update comes around and doesnt fall back the same tick
+fn update_conveyor(cells: &mut GridWindow, tracking: &TrackingWindow, x: usize, y: usize) {
+    let gravity = tracking.gravity_at((x, y));
+    let Some((on_top, cell)) = cells.ray((x, y), (-gravity.0, -gravity.1), 1).next() else {
+        return;
+    };
+    if !blowable(cell.ty) || matches!(cell.ty, CellType::Smoke | CellType::Steam) {
+        return;
+    }
+
+    let sideways = conveyor_sideways(&cells[(x, y)], gravity);
+    let Some((to, target)) = cells.ray(on_top, sideways, 1).next() else {
+        return;
+    };
+    if matches!(target.ty, CellType::Air | CellType::Smoke | CellType::Steam) {
+        swap_cells(cells, tracking, on_top, to);
+    }
+}
+
+// everything touching a heater warms up, burnables catch fire once they are hot enough
+fn update_heater(cells: &mut GridWindow, tracking: &TrackingWindow, x: usize, y: usize, rng: &Rng) {
+    for direction in NEIGHBOURS {
+        let Some((neighbour_pos, neighbour)) = cells.ray((x, y), direction, 1).next() else {
+            continue;
+        };
+        if neighbour.ty != CellType::Air {
+            heat_cell(cells, tracking, neighbour_pos, HEATER_HEAT, rng);
+        }
+    }
+}
+
+fn update_cooler(cells: &mut GridWindow, x: usize, y: usize) {
+    for direction in NEIGHBOURS {
+        let Some((neighbour_pos, neighbour)) = cells.ray((x, y), direction, 1).next() else {
+            continue;
+        };
+        if neighbour.ty != CellType::Air {
+            let neighbour = &mut cells[neighbour_pos];
+            neighbour.temperature = neighbour.temperature.saturating_sub(COOLER_CHILL);
+        }
+    }
+}
+
+fn blowable(cell_type: CellType) -> bool {
+    matches!(
+        cell_type,
+        CellType::Sand
+            | CellType::Salt
+            | CellType::Soap
+            | CellType::Gunpowder
+            | CellType::Antimatter
+            | CellType::Seed
+            | CellType::Ash
+            | CellType::Foam
+            | CellType::Snow
+            | CellType::Confetti
+            | CellType::Dirt
+            | CellType::Mud
+            | CellType::Water
+            | CellType::Acid
+            | CellType::Oil
+            | CellType::Nitro
+            | CellType::Honey
+            | CellType::Napalm
+            | CellType::Lava
+            | CellType::MoltenMetal
+            | CellType::Slag
+            | CellType::Ant
+            | CellType::Fish
+            | CellType::Smoke
+            | CellType::Steam
+            | CellType::Hydrogen
+            | CellType::ToxicGas
+            | CellType::Methane
+    )
+}
+
+fn touches_air(cells: &GridWindow, pos: (usize, usize)) -> bool {
+    NEIGHBOURS.into_iter().any(|direction| {
+        cells
+            .ray(pos, direction, 1)
+            .next()
+            .is_some_and(|(_, neighbour)| neighbour.ty == CellType::Air)
+    })
+}
+
+// the edges of the grid count as cold surfaces, and once the night is cold enough anything that isnt air, gas or fire
+fn touches_cold_surface(cells: &GridWindow, pos: (usize, usize), ambient: u8) -> bool {
+    let dew = ambient < DEW_TEMPERATURE;
+    NEIGHBOURS.into_iter().any(|direction| {
+        cells
+            .ray(pos, direction, 1)
+            .next()
+            .is_none_or(|(_, neighbour)| {
+                matches!(
+                    neighbour.ty,
+                    CellType::Wood
+                        | CellType::Tnt
+                        | CellType::Fan
+                        | CellType::Ice
+                        | CellType::Snow
+                        | CellType::Cooler
+                ) && neighbour.temperature < CONDENSING_TEMPERATURE
+                    || dew
+                        && !matches!(
+                            neighbour.ty,
+                            CellType::Air | CellType::Smoke | CellType::Steam | CellType::Fire
+                        )
+            })
+    })
+}
+
+fn update_sand(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if soak(cells, tracking, (x, y), rng) {
+        return;
+    }
+
+    let acceleration = if cells[(x, y)].lifetime > 0 {
+        ACCELERATION * WET_SAND_WEIGHT
+    } else {
+        ACCELERATION
+    };
+
+    generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        acceleration,
+        false,
+        rng,
+    );
+}
+
+// sand or dirt next to water gets wet, dry grains take in the water they touched and soaked ones now and then turn to
+// mud. away from water they dry out. true if it turned to mud
+fn soak(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), rng: &Rng) -> bool {
+    let water = touching_water(cells, pos);
+    let wetness = cells[pos].lifetime;
+
+    match water {
+        Some(water) if wetness > 0 && rng.f32() < MUD_CHANCE => {
+            set_cell(cells, tracking, water, Cell::from(CellType::Air, rng));
+            let mud = muddied(&cells[pos], rng);
+            set_cell(cells, tracking, pos, mud);
+            return true;
+        }
+        Some(_) if wetness > 0 => cells[pos].lifetime = MAX_WETNESS,
+        Some(water) if rng.f32() < SOAK_CHANCE => {
+            set_cell(cells, tracking, water, Cell::from(CellType::Air, rng));
+            cells[pos].lifetime = MAX_WETNESS;
+        }
+        None if wetness > 0 && rng.f32() < DRY_CHANCE => cells[pos].lifetime -= 1,
+        _ => return false,
+    }
+
+    tracking.mark_dirty(pos.0, pos.1);
+    false
+}
+
+// mud made from the sand or dirt, which it dries back into
+fn muddied(soil: &Cell, rng: &Rng) -> Cell {
+    let mut mud = Cell::from(CellType::Mud, rng);
+    mud.lifetime = soil.ty as u8;
+    mud
+}
+
+fn touching_water(cells: &GridWindow, pos: (usize, usize)) -> Option<(usize, usize)> {
+    DIRECTIONS.into_iter().find_map(|direction| {
+        cells
+            .ray(pos, direction, 1)
+            .next()
+            .filter(|(_, neighbour)| neighbour.ty == CellType::Water)
+            .map(|(water, _)| water)
+    })
+}
+
+fn sticks(cell: &Cell, rng: &Rng) -> bool {
+    match cell.ty {
+        CellType::Sand => cell.lifetime > 0 && rng.f32() < WET_SAND_STICKINESS,
+        CellType::Dirt => rng.f32() < DIRT_STICKINESS,
+        CellType::Mud => rng.f32() < WET_SAND_STICKINESS,
+        _ => false,
+    }
+}
+
+// sinks like sand and dissolves into water touching it that can still hold more
+fn update_salt(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if rng.f32() < SALT_DISSOLVE_CHANCE {
+        let water = NEIGHBOURS.into_iter().find_map(|direction| {
+            cells
+                .ray((x, y), direction, 1)
+                .next()
+                .filter(|(_, neighbour)| {
+                    neighbour.ty == CellType::Water && dissolved_salt(neighbour) < MAX_SALT
+                })
+                .map(|(pos, _)| pos)
+        });
+
+        if let Some(water) = water {
+            cells[water].lifetime += 1;
+            tracking.mark_dirty(water.0, water.1);
+            set_cell(cells, tracking, (x, y), Cell::from(CellType::Air, rng));
+            return;
+        }
+    }
+
+    generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        false,
+        rng,
+    );
+}
+
+// sinks like salt, worked into water that is moving it lathers it up into foam until it is worn away
+fn update_soap(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if rng.f32() < SOAP_LATHER_CHANCE {
+        let generation = tracking.generation();
+        let water = NEIGHBOURS.into_iter().find_map(|direction| {
+            cells
+                .ray((x, y), direction, 1)
+                .next()
+                .filter(|(_, neighbour)| {
+                    neighbour.ty == CellType::Water && agitated(neighbour, generation)
+                })
+                .map(|(pos, _)| pos)
+        });
+
+        if let Some(water) = water {
+            set_cell(cells, tracking, water, Cell::from(CellType::Foam, rng));
+            cells[water].set_moved(generation);
+
+            cells[(x, y)].lifetime += 1;
+            if cells[(x, y)].lifetime >= SOAP_LATHER {
+                set_cell(cells, tracking, (x, y), Cell::from(CellType::Foam, rng));
+                return;
+            }
+        }
+    }
+
+    generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        false,
+        rng,
+    );
+}
+
+// moved this tick or the last one, still water doesnt lather. cells sinking through water leave it unstamped
+fn agitated(water: &Cell, generation: u8) -> bool {
+    water.moved_generation != 0 && generation.saturating_sub(water.moved_generation) <= 1
+}
+
+// bubbles up through water and comes to rest on top of it, drifting down through the air onto whatever is below and
+// heaping up. it pops on its own after a while, and straight away once fire or anything hot touches it
+fn update_foam(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    let scalded = cells[(x, y)].temperature >= WATER_BOILING_TEMPERATURE
+        || NEIGHBOURS.into_iter().any(|direction| {
+            cells
+                .ray((x, y), direction, 1)
+                .next()
+                .is_some_and(|(_, cell)| matches!(cell.ty, CellType::Fire | CellType::Lava))
+        });
+    if scalded || rng.f32() < FOAM_POP_CHANCE {
+        set_cell(cells, tracking, (x, y), Cell::from(CellType::Air, rng));
+        return;
+    }
+
+    if generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        &[CellType::Water, CellType::Oil],
+        FOAM_RISE_VELOCITY,
+        ACCELERATION,
+        true,
+        rng,
+    )
+    .is_some()
+        || rng.f32() >= FOAM_FALL_CHANCE
+    {
+        return;
+    }
+
+    generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        SNOW_MAX_VELOCITY,
+        ACCELERATION,
+        false,
+        rng,
+    );
+}
+
+// falls like sand through the air and its own flashes, anything else it touches is annihilated along with it in react
+fn update_antimatter(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        false,
+        rng,
+    );
+}
+
+// falls like sand until it sprouts
+fn update_seed(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if growth::sprout(cells, tracking, (x, y), &PLANT, rng) {
+        return;
+    }
+
+    generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        false,
+        rng,
+    );
+}
+
+// falls like sand but clumps, see sticks. it gets wet like sand too
+fn update_dirt(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if soak(cells, tracking, (x, y), rng) {
+        return;
+    }
+
+    generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        false,
+        rng,
+    );
+}
+
+// a thick liquid that barely flows, see MUD_VISCOSITY. left with no water touching it it dries back into the sand or
+// dirt it was made from, which it keeps in its lifetime
+fn update_mud(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if rng.f32() < MUD_DRY_CHANCE && touching_water(cells, (x, y)).is_none() {
+        let made_from = all::<CellType>()
+            .nth(cells[(x, y)].lifetime as usize)
+            .filter(|made_from| matches!(made_from, CellType::Sand | CellType::Dirt))
+            .unwrap_or(CellType::Dirt);
+        let mut dried = Cell::from(made_from, rng);
+        dried.lifetime = MAX_WETNESS;
+        set_cell(cells, tracking, (x, y), dried);
+        return;
+    }
+
+    generic_fluid(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        rng,
+    );
+}
+
+fn dissolved_salt(water: &Cell) -> u8 {
+    water.lifetime & ((1 << ASH_SHIFT) - 1)
+}
+
+fn dissolved_ash(water: &Cell) -> u8 {
+    water.lifetime >> ASH_SHIFT
+}
+
+// drifts down, fluttering from side to side as it falls. it dissolves into water touching it, see MAX_ASH, and a heap
+// of it smothers a small fire under it
+fn update_ash(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if rng.f32() < ASH_DISSOLVE_CHANCE {
+        let water = NEIGHBOURS.into_iter().find_map(|direction| {
+            cells
+                .ray((x, y), direction, 1)
+                .next()
+                .filter(|(_, neighbour)| {
+                    neighbour.ty == CellType::Water && dissolved_ash(neighbour) < MAX_ASH
+                })
+                .map(|(pos, _)| pos)
+        });
+
+        if let Some(water) = water {
+            cells[water].lifetime += 1 << ASH_SHIFT;
+            tracking.mark_dirty(water.0, water.1);
+            set_cell(cells, tracking, (x, y), Cell::from(CellType::Air, rng));
+            return;
+        }
+    }
+
+    let gravity = tracking.gravity_at((x, y));
+    match cells.ray((x, y), gravity, 1).next() {
+        Some((below, &fire))
+            if fire.ty == CellType::Fire
+                && fire.fuel <= ASH_SMOTHER_FUEL
+                && rng.f32() < ASH_SMOTHER_CHANCE =>
+        {
+            set_cell(cells, tracking, below, smothered(&fire, rng));
+            cells[below].set_moved(tracking.generation());
+        }
+        Some((_, below)) if empty_types.contains(&below.ty) && rng.f32() < ASH_FLUTTER_CHANCE => {
+            let flutter = (rng.f32() * 2.0 - 1.0) * ASH_FLUTTER + tracking.wind();
+            cells[(x, y)].set_velocity_x(flutter);
+        }
+        _ => (),
+    }
+
+    generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        ASH_MAX_VELOCITY,
+        ACCELERATION,
+        false,
+        rng,
+    );
+}
+
+// stays put as long as anything solid touches it, a lump broken off on its own falls like sand until it lands
+fn update_stone(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    // nearly all stone is resting on something, so only the cells with nothing under them look any further
+    let gravity = tracking.gravity_at((x, y));
+    let resting = cells
+        .ray((x, y), gravity, 1)
+        .next()
+        .is_none_or(|(_, below)| !empty_types.contains(&below.ty));
+    let supported = resting
+        || NEIGHBOURS.into_iter().any(|direction| {
+            cells
+                .ray((x, y), direction, 1)
+                .next()
+                .is_some_and(|(_, neighbour)| {
+                    !empty_types.contains(&neighbour.ty)
+                        && neighbour.ty != CellType::Fire
+                        && cell_type_liquid_density(neighbour.ty).is_none()
+                })
+        });
+    if supported {
+        return;
+    }
+
+    generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        false,
+        rng,
+    );
+}
+
+// falls slowly and drifts with the wind on the way down, it settles on water instead of sinking and melts once it is
+// warmed past freezing like ice
+fn update_snow(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    let falling = furthest_by_vector(cells, (x, y), 0, empty_types, tracking.gravity_at((x, y)))
+        .furthest
+        .is_some();
+    if falling {
+        blow(cells, tracking, (x, y), 1);
+    }
+
+    generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        SNOW_MAX_VELOCITY,
+        ACCELERATION,
+        false,
+        rng,
+    );
+}
+
+// flutters down even slower than snow, thrown from side to side and blown along hard by the wind. it settles on water
+// like snow and piles up loosely, the wind lifting the flakes on top of a heap off it again
+fn update_confetti(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    let gravity = tracking.gravity_at((x, y));
+    let falling = furthest_by_vector(cells, (x, y), 0, empty_types, gravity)
+        .furthest
+        .is_some();
+    let wind = tracking.wind() * CONFETTI_WIND;
+
+    if falling {
+        let flutter = (rng.f32() * 2.0 - 1.0) * CONFETTI_FLUTTER + wind;
+        cells[(x, y)].set_velocity_x(flutter);
+    } else if rng.f32() < CONFETTI_LIFT_CHANCE * wind.abs()
+        && cells
+            .ray((x, y), (-gravity.0, -gravity.1), 1)
+            .next()
+            .is_none_or(|(_, above)| empty_types.contains(&above.ty))
+    {
+        cells[(x, y)].set_velocity_x(wind);
+    }
+
+    generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        CONFETTI_MAX_VELOCITY,
+        ACCELERATION,
+        false,
+        rng,
+    );
+}
+
+// a liquid that sinks through water, it eats whatever it touches in update_cell
+// flows like a thick, heavy liquid and heats what it touches. the inside of a pool keeps itself hot, only lava touching
+// something else cools towards setting, and it is drawn dimmer as it does
+fn update_lava(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    let ty = cells[(x, y)].ty;
+    let mut exposed = false;
+    for direction in NEIGHBOURS {
+        let Some((neighbour_pos, neighbour)) = cells.ray((x, y), direction, 1).next() else {
+            continue;
+        };
+        exposed |= neighbour.ty != ty;
+        if cell_type_ignition_temperature(neighbour.ty).is_some()
+            || matches!(
+                neighbour.ty,
+                CellType::Water | CellType::Ice | CellType::Snow | CellType::Metal | CellType::Sand
+            )
+        {
+            heat_cell(
+                cells,
+                tracking,
+                neighbour_pos,
+                rng.u8(0..=LAVA_HEAT * 2),
+                rng,
+            );
+        }
+    }
+
+    // undoes the degree it drifted down in change_phase
+    if !exposed {
+        let cell = &mut cells[(x, y)];
+        cell.temperature = cell.temperature.saturating_add(1);
+    }
+    tracking.mark_dirty(x, y);
+
+    generic_fluid(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        rng,
+    );
+}
+
+// left behind by uranium melting down, it flows heavy and slow and sinks through lava and molten metal. it never sets,
+// radioactivity keeps it hot, and it heats the burnables, water, ice and snow touching it
+fn update_slag(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    for direction in NEIGHBOURS {
+        let Some((neighbour_pos, neighbour)) = cells.ray((x, y), direction, 1).next() else {
+            continue;
+        };
+        if cell_type_ignition_temperature(neighbour.ty).is_some()
+            || matches!(
+                neighbour.ty,
+                CellType::Water | CellType::Ice | CellType::Snow
+            )
+        {
+            heat_cell(
+                cells,
+                tracking,
+                neighbour_pos,
+                rng.u8(0..=SLAG_HEAT * 2),
+                rng,
+            );
+        }
+    }
+
+    generic_fluid(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        rng,
+    );
+}
+
+// heated by fire and anything molten touching it and shares its heat evenly with the metal around it, so heat runs
+// through a bar or sheet of it quickly. hot enough it lights the burnables touching it. wet it slowly rusts, counting
+// how far in its lifetime
+fn update_metal(cells: &mut GridWindow, tracking: &TrackingWindow, x: usize, y: usize, rng: &Rng) {
+    let mut wet = false;
+    let mut rusty = false;
+
+    for direction in NEIGHBOURS {
+        let Some((neighbour_pos, &neighbour)) = cells.ray((x, y), direction, 1).next() else {
+            continue;
+        };
+        let temperature = cells[(x, y)].temperature;
+        wet |= neighbour.ty == CellType::Water;
+        rusty |= neighbour.ty == CellType::Rust;
+
+        match neighbour.ty {
+            CellType::Fire | CellType::Lava | CellType::MoltenMetal => {
+                cells[(x, y)].temperature = temperature.saturating_add(rng.u8(0..=FIRE_HEAT * 2));
+            }
+            CellType::Metal => {
+                let total = temperature as u16 + neighbour.temperature as u16;
+                cells[(x, y)].temperature = (total / 2) as u8;
+                cells[neighbour_pos].temperature = (total - total / 2) as u8;
+            }
+            _ if cell_type_ignition_temperature(neighbour.ty)
+                .is_some_and(|ignition_temperature| temperature >= ignition_temperature) =>
+            {
+                heat_cell(
+                    cells,
+                    tracking,
+                    neighbour_pos,
+                    rng.u8(0..=FIRE_HEAT * 2),
+                    rng,
+                );
+            }
+            _ => (),
+        }
+    }
+
+    let rust_chance = if wet {
+        RUST_CHANCE
+    } else if rusty {
+        RUST_SPREAD_CHANCE
+    } else {
+        0.0
+    };
+    if rng.f32() < rust_chance {
+        cells[(x, y)].lifetime += 1;
+        if cells[(x, y)].lifetime >= RUST_STAGES {
+            set_cell(cells, tracking, (x, y), Cell::from(CellType::Rust, rng));
+            return;
+        }
+        tracking.mark_dirty(x, y);
+    }
+
+    // drawn glowing while it is hot, and once more as it cools back down
+    if cells[(x, y)].temperature > ROOM_TEMPERATURE {
+        tracking.mark_dirty(x, y);
+    }
+}
+
+// burns in place, heating the burnables around it like fire and now and then putting a flame out into the air beside
+// it. shut off from the air it goes out as charcoal, which is coal, as it does when water quenches it
+fn update_charred_wood(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    rng: &Rng,
+) {
+    let mut air = None;
+    for direction in NEIGHBOURS {
+        let Some((neighbour_pos, neighbour)) = cells.ray((x, y), direction, 1).next() else {
+            continue;
+        };
+
+        if neighbour.ty == CellType::Air {
+            air = Some(neighbour_pos);
+        } else if cell_type_ignition_temperature(neighbour.ty).is_some() {
+            heat_cell(
+                cells,
+                tracking,
+                neighbour_pos,
+                rng.u8(0..=FIRE_HEAT * 2),
+                rng,
+            );
+        }
+    }
+
+    let Some(air) = air else {
+        set_cell(cells, tracking, (x, y), Cell::from(CellType::Coal, rng));
+        return;
+    };
+
+    if rng.f32() < CHARRED_FLAME_CHANCE {
+        let mut flame = Cell::from(CellType::Fire, rng);
+        flame.fuel = CHARRED_FLAME_FUEL;
+        set_cell(cells, tracking, air, flame);
+        cells[air].set_moved(tracking.generation());
+    }
+
+    if rng.f32() >= CHARRED_BURN_CHANCE {
+        return;
+    }
+
+    if cells[(x, y)].fuel == 0 {
+        let mut fire = catch_fire(&cells[(x, y)], rng);
+        fire.fuel = CHARRED_FLAME_FUEL;
+        set_cell(cells, tracking, (x, y), fire);
+        return;
+    }
+
+    cells[(x, y)].fuel -= 1;
+    tracking.mark_dirty(x, y);
+}
+
+// a sticky gel that holds on to anything solid around it instead of flowing off, and only slowly spreads out over a floor
+// or across water, which it floats on. alight it stays where it is and keeps burning, on water or not, until it is shut
+// off from the air or its fuel runs out
+fn update_napalm(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if cells[(x, y)].lifetime == NAPALM_BURNING {
+        if burn_napalm(cells, tracking, (x, y), rng) {
+            return;
+        }
+    } else if cells[(x, y)].temperature >= NAPALM_IGNITION_TEMPERATURE && touches_air(cells, (x, y))
+    {
+        cells[(x, y)].lifetime = NAPALM_BURNING;
+        tracking.mark_dirty(x, y);
+    }
+
+    let clings = NEIGHBOURS.into_iter().any(|direction| {
+        cells
+            .ray((x, y), direction, 1)
+            .next()
+            .is_some_and(|(_, neighbour)| {
+                !empty_types.contains(&neighbour.ty)
+                    && !matches!(neighbour.ty, CellType::Fire | CellType::Napalm)
+                    && cell_type_liquid_density(neighbour.ty).is_none()
+            })
+    });
+    if clings {
+        return;
+    }
+
+    generic_fluid(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        rng,
+    );
+}
+
+// heats what burns around it and puts flames out into the air beside it, flickering as it goes. with no air it goes
+// out but stays hot enough to catch again. true once its fuel is gone and it has gone up in its last flame
+fn burn_napalm(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    rng: &Rng,
+) -> bool {
+    let mut air = None;
+    for direction in NEIGHBOURS {
+        let Some((neighbour_pos, neighbour)) = cells.ray(pos, direction, 1).next() else {
+            continue;
+        };
+
+        if neighbour.ty == CellType::Air {
+            air = Some(neighbour_pos);
+        } else if cell_type_ignition_temperature(neighbour.ty).is_some() {
+            heat_cell(
+                cells,
+                tracking,
+                neighbour_pos,
+                rng.u8(0..=FIRE_HEAT * 2),
+                rng,
+            );
+        }
+    }
+
+    let Some(air) = air else {
+        cells[pos].lifetime = 0;
+        cells[pos].temperature = NAPALM_IGNITION_TEMPERATURE;
+        tracking.mark_dirty(pos.0, pos.1);
+        return false;
+    };
+
+    cells[pos].color_index = cell_type_color_index_random(CellType::Napalm, rng);
+    tracking.mark_dirty(pos.0, pos.1);
+
+    if rng.f32() < NAPALM_FLAME_CHANCE {
+        let mut flame = Cell::from(CellType::Fire, rng);
+        flame.fuel = NAPALM_FLAME_FUEL;
+        set_cell(cells, tracking, air, flame);
+        cells[air].set_moved(tracking.generation());
+    }
+
+    if rng.f32() >= NAPALM_BURN_CHANCE {
+        return false;
+    }
+
+    if cells[pos].fuel == 0 {
+        let mut fire = catch_fire(&cells[pos], rng);
+        fire.fuel = NAPALM_FLAME_FUEL;
+        set_cell(cells, tracking, pos, fire);
+        return true;
+    }
+
+    cells[pos].fuel -= 1;
+    false
+}
+
+// how far through blackening it is, from 1 when it has only just caught up to CHARRED_STAGES
+fn charred_stage(cell: &Cell) -> u8 {
+    let burnt = WOOD_FUEL.saturating_sub(cell.fuel) as u16;
+    (burnt * CHARRED_STAGES as u16 / WOOD_FUEL as u16 + 1).min(CHARRED_STAGES as u16) as u8
+}
+
+// stays put until it crumbles, see RUST_CRUMBLE_CHANCE, crumbled rust keeps 1 in its lifetime and falls like sand
+fn update_rust(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if cells[(x, y)].lifetime == 0 {
+        let gravity = tracking.gravity_at((x, y));
+        let unsupported = cells
+            .ray((x, y), gravity, 1)
+            .next()
+            .is_some_and(|(_, below)| empty_types.contains(&below.ty));
+        let shaken = rng.f32() < RUST_CRUMBLE_CHANCE
+            && NEIGHBOURS.into_iter().any(|direction| {
+                cells
+                    .ray((x, y), direction, 1)
+                    .next()
+                    .is_some_and(|(_, neighbour)| {
+                        neighbour.ty == CellType::Rust && neighbour.lifetime > 0
+                    })
+            });
+        if !unsupported && !shaken {
+            return;
+        }
+
+        cells[(x, y)].lifetime = 1;
+    }
+
+    generic_fall(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        false,
+        rng,
+    );
+}
+
+fn update_oil(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    generic_fluid(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        rng,
+    );
+}
+
+// a very thick liquid, see HONEY_VISCOSITY. it clings to the underside of anything solid and drips off a drop at a
+// time, and grains landing on it sink slowly through it
+fn update_honey(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    let gravity = tracking.gravity_at((x, y));
+    let above = cells
+        .ray((x, y), (-gravity.0, -gravity.1), 1)
+        .next()
+        .map(|(pos, &cell)| (pos, cell));
+
+    if let Some((above_pos, grain)) = above {
+        if sinks_in_honey(grain.ty) && rng.f32() < HONEY_SINK_CHANCE {
+            swap_cells(cells, tracking, (x, y), above_pos);
+            cells[(x, y)].set_velocity(0.0);
+            return;
+        }
+    }
+
+    let hanging = above.is_some_and(|(_, above)| {
+        !empty_types.contains(&above.ty)
+            && above.ty != CellType::Fire
+            && cell_type_liquid_density(above.ty).is_none()
+    }) && cells
+        .ray((x, y), gravity, 1)
+        .next()
+        .is_some_and(|(_, below)| empty_types.contains(&below.ty));
+    if hanging && rng.f32() >= HONEY_DRIP_CHANCE {
+        return;
+    }
+
+    generic_fluid(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        rng,
+    );
+}
+
+fn sinks_in_honey(cell_type: CellType) -> bool {
+    matches!(
+        cell_type,
+        CellType::Sand
+            | CellType::Salt
+            | CellType::Gunpowder
+            | CellType::Seed
+            | CellType::Ash
+            | CellType::Dirt
+    )
+}
+
+// flows like water but goes off when it is heated or lands hard, see NITRO_SHOCK_VELOCITY
+fn update_nitro(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    let cell = cells[(x, y)];
+    let landed = cell.velocity() >= NITRO_SHOCK_VELOCITY
+        && furthest_by_vector(cells, (x, y), 0, empty_types, tracking.gravity_at((x, y)))
+            .furthest
+            .is_none();
+
+    if landed || cell.temperature >= NITRO_IGNITION_TEMPERATURE {
+        tracking.detonate((x, y), NITRO_RADIUS);
+        set_cell(cells, tracking, (x, y), Cell::from(CellType::Fire, rng));
+        return;
+    }
+
+    generic_fluid(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        rng,
+    );
+}
+
+fn update_acid(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    generic_fluid(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        rng,
+    );
+}
+
+// water open to the air above it with no more than PUDDLE_DEPTH cells of water in the column it is the top of
+fn puddle_surface(cells: &GridWindow, pos: (usize, usize), down: (isize, isize)) -> bool {
+    cells
+        .ray(pos, (-down.0, -down.1), 1)
+        .next()
+        .is_some_and(|(_, above)| above.ty == CellType::Air)
+        && cells
+            .ray(pos, down, PUDDLE_DEPTH)
+            .take_while(|(_, below)| below.ty == CellType::Water)
+            .count()
+            < PUDDLE_DEPTH
+}
+
+fn update_water(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if rng.f32() < tracking.evaporation()
+        && puddle_surface(cells, (x, y), tracking.gravity_at((x, y)))
+    {
+        let steam = transform(&cells[(x, y)], CellType::Steam, rng);
+        set_cell(cells, tracking, (x, y), steam);
+        return;
+    }
+
+    if rng.f32() < 0.125 && cells[(x, y)].velocity() < 0.1 {
+        cells[(x, y)].color_index = cell_type_color_index_random(cells[(x, y)].ty, rng);
+        tracking.mark_dirty(x, y);
+    }
+
+    // the salt is carried along in the water cell, and wanders through still water a cell at a time towards fresher
+    // water
+    let salt = dissolved_salt(&cells[(x, y)]);
+    if salt > 0 && rng.f32() < SALT_SPREAD_CHANCE {
+        let direction = DIRECTIONS[rng.usize(0..DIRECTIONS.len())];
+        let fresher = cells
+            .ray((x, y), direction, 1)
+            .next()
+            .filter(|(_, neighbour)| {
+                neighbour.ty == CellType::Water && dissolved_salt(neighbour) < salt
+            })
+            .map(|(pos, _)| pos);
+        if let Some(pos) = fresher {
+            cells[pos].lifetime += 1;
+            cells[(x, y)].lifetime -= 1;
+            tracking.mark_dirty(pos.0, pos.1);
+            tracking.mark_dirty(x, y);
+        }
+    }
+
+    // landing hard throws the water out sideways, it slides off over whatever it landed on
+    let cell = cells[(x, y)];
+    if cell.velocity() >= SPLASH_VELOCITY
+        && cell.velocity_x == 0
+        && furthest_by_vector(cells, (x, y), 0, empty_types, tracking.gravity_at((x, y)))
+            .furthest
+            .is_none()
+    {
+        let direction = if rng.bool() { 1.0 } else { -1.0 };
+        cells[(x, y)].set_velocity_x(direction * cell.velocity() / 2.0);
+        cells[(x, y)].set_velocity(0.0);
+    }
+
+    match generic_fluid(
+        cells,
+        tracking,
+        (x, y),
+        empty_types,
+        MAX_VELOCITY,
+        ACCELERATION,
+        rng,
+    ) {
+        Some(to) => erosion::scour(cells, tracking, (x, y), to, rng),
+        None => erosion::settle(cells, tracking, (x, y), rng),
+    }
+}
+
+fn update_smoke(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if cells[(x, y)].lifetime == 0 {
+        set_cell(cells, tracking, (x, y), Cell::from(CellType::Air, rng));
+        return;
+    }
+
+    let lifetime = &mut cells[(x, y)].lifetime;
+    *lifetime = lifetime.saturating_sub(SMOKE_TICK_INTERVAL as u8);
+    // smoke and steam fade with their lifetime
+    tracking.mark_dirty(x, y);
+
+    blow(cells, tracking, (x, y), SMOKE_TICK_INTERVAL);
+
+    diffusion::diffuse(
+        cells,
+        tracking,
+        (x, y),
+        &SMOKE,
+        empty_types,
+        SMOKE_MAX_VELOCITY * SMOKE_TICK_INTERVAL as f32,
+        SMOKE_ACCELERATION * SMOKE_TICK_INTERVAL as f32,
+        rng,
+    );
+}
+
+// rises fast and gathers under whatever is above it. fire, lava or charge touching it or being heated sets it off,
+// along with the rest of the pocket it is part of at the start of the next tick
+fn update_hydrogen(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if lit(cells, tracking, (x, y), HYDROGEN_IGNITION_TEMPERATURE) {
+        tracking.ignite((x, y));
+        return;
+    }
+
+    blow(cells, tracking, (x, y), 1);
+
+    diffusion::diffuse(
+        cells,
+        tracking,
+        (x, y),
+        &HYDROGEN,
+        empty_types,
+        HYDROGEN_MAX_VELOCITY,
+        HYDROGEN_ACCELERATION,
+        rng,
+    );
+}
+
+// rises slowly, shimmering. lit it catches fire straight away and burns out in a flash, see METHANE_IGNITION_TEMPERATURE
+fn update_methane(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if lit(cells, tracking, (x, y), METHANE_IGNITION_TEMPERATURE) {
+        let fire = catch_fire(&cells[(x, y)], rng);
+        set_cell(cells, tracking, (x, y), fire);
+        cells[(x, y)].set_moved(tracking.generation());
+        return;
+    }
+
+    if rng.f32() < METHANE_SHIMMER_CHANCE {
+        cells[(x, y)].color_index = cell_type_color_index_random(CellType::Methane, rng);
+        tracking.mark_dirty(x, y);
+    }
+
+    blow(cells, tracking, (x, y), 1);
+
+    diffusion::diffuse(
+        cells,
+        tracking,
+        (x, y),
+        &METHANE,
+        empty_types,
+        METHANE_MAX_VELOCITY,
+        METHANE_ACCELERATION,
+        rng,
+    );
+}
+
+// a gas that goes up at a touch, heated past ignition_temperature or with fire, lava or charge at or next to pos
+fn lit(
+    cells: &GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    ignition_temperature: u8,
+) -> bool {
+    cells[pos].temperature >= ignition_temperature
+        || tracking.charge_at(pos) > 0
+        || NEIGHBOURS.into_iter().any(|direction| {
+            cells
+                .ray(pos, direction, 1)
+                .next()
+                .is_some_and(|(neighbour_pos, neighbour)| {
+                    matches!(neighbour.ty, CellType::Fire | CellType::Lava)
+                        || tracking.charge_at(neighbour_pos) > 0
+                })
+        })
+}
+
+// sinks and pools in low places, breaking down now and then and dissolving into water it touches. plants around it
+// wither, creatures choke on it in their own update
+fn update_toxic_gas(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if rng.f32() < TOXIC_GAS_DECAY_CHANCE
+        || (rng.f32() < TOXIC_GAS_DISSOLVE_CHANCE && touching_water(cells, (x, y)).is_some())
+    {
+        set_cell(cells, tracking, (x, y), Cell::from(CellType::Air, rng));
+        return;
+    }
+
+    if rng.f32() < TOXIC_GAS_WITHER_CHANCE {
+        let direction = NEIGHBOURS[rng.usize(0..NEIGHBOURS.len())];
+        let plant = cells
+            .ray((x, y), direction, 1)
+            .next()
+            .and_then(|(pos, cell)| withered(cell, rng).map(|withered| (pos, withered)));
+        if let Some((pos, withered)) = plant {
+            set_cell(cells, tracking, pos, withered);
+            cells[pos].set_moved(tracking.generation());
+        }
+    }
+
+    blow(cells, tracking, (x, y), 1);
+
+    diffusion::diffuse(
+        cells,
+        tracking,
+        (x, y),
+        &TOXIC_GAS,
+        empty_types,
+        TOXIC_GAS_MAX_VELOCITY,
+        TOXIC_GAS_ACCELERATION,
+        rng,
+    );
+}
+
+// what a plant dies back into, ash or the stone or wood moss was growing on. None for anything that isnt a plant
+fn withered(plant: &Cell, rng: &Rng) -> Option<Cell> {
+    match plant.ty {
+        CellType::Plant | CellType::Vine | CellType::Seed | CellType::Fungus => {
+            Some(Cell::from(CellType::Ash, rng))
+        }
+        CellType::Moss => Some(Cell::from(moss::host(plant), rng)),
+        _ => None,
+    }
+}
+
+// gases are light enough to fly with the wind, as far as it blows them in the ticks since they last updated
+fn blow(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    tick_interval: u64,
+) {
+    let wind = tracking.wind();
+    if wind != 0.0 {
+        cells[pos].set_velocity_x(wind * tick_interval as f32);
+    }
+}
+
+fn update_steam(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    x: usize,
+    y: usize,
+    empty_types: &[CellType],
+    rng: &Rng,
+) {
+    if cells[(x, y)].lifetime == 0 {
+        if rng.f32() < 0.5_f32.powf(6.0) {
+            set_cell(cells, tracking, (x, y), Cell::from(CellType::Water, rng))
+        } else {
+            set_cell(cells, tracking, (x, y), Cell::from(CellType::Air, rng))
+        }
+        return;
+    }
+
+    // the water runs down whatever it condensed on
+    if rng.f32() < STEAM_CONDENSE_CHANCE && touches_cold_surface(cells, (x, y), tracking.ambient())
+    {
+        set_cell(cells, tracking, (x, y), Cell::from(CellType::Water, rng));
+        return;
+    }
+
+    let lifetime = &mut cells[(x, y)].lifetime;
+    *lifetime = lifetime.saturating_sub(STEAM_TICK_INTERVAL as u8);
+    // smoke and steam fade with their lifetime
+    tracking.mark_dirty(x, y);
+
+    blow(cells, tracking, (x, y), STEAM_TICK_INTERVAL);
+
+    diffusion::diffuse(
+        cells,
+        tracking,
+        (x, y),
+        &STEAM,
+        empty_types,
+        STEAM_MAX_VELOCITY * STEAM_TICK_INTERVAL as f32,
+        STEAM_ACCELERATION * STEAM_TICK_INTERVAL as f32,
+        rng,
     );
+}
+
+fn generic_fluid(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    cell_pos: (usize, usize),
+    empty_types: &[CellType],
+    max_velocity: f32,
+    acceleration: f32,
+    rng: &Rng,
+) -> Option<(usize, usize)> {
+    // thicker liquids speed up slower, and spread less far and less often
+    let viscosity = cell_type_viscosity(cells[cell_pos].ty);
+
+    // todo something like: if the cell has a low velocity falling down then randomly spread to the side, will stop some water cells standing on top of others without spreading i think
+    if let Some(fall_result) = generic_fall(
+        cells,
+        tracking,
+        cell_pos,
+        empty_types,
+        max_velocity,
+        acceleration * (1.0 - viscosity),
+        false,
+        rng,
+    ) {
+        return Some(fall_result);
+    }
+
+    // left and right of whichever way gravity points
+    let across = across(tracking.gravity_at(cell_pos));
+
+    // a lone drop of liquid is pulled towards more of its own kind instead of skittering off, so spray gathers into
+    // blobs
+    if lone_drop(cells, cell_pos) {
+        if let Some(step) = towards_own_kind(cells, cell_pos, across, empty_types) {
+            swap_cells(cells, tracking, cell_pos, step);
+            return Some(step);
+        }
+
+        if rng.f32() < DROP_COHESION {
+            return None;
+        }
+    }
+
+    if viscosity > 0.0 && rng.f32() < viscosity {
+        return None;
+    }
+
+    let spread_factor = (((cells[cell_pos].velocity() + 1.0) * (1.0 - viscosity)).max(1.0)
+        as usize)
+        .min(tracking.spread_limit());
+    let furthest_left = furthest_by_vector(
+        cells,
+        cell_pos,
+        spread_factor,
+        empty_types,
+        (-across.0, -across.1),
+    )
+    .furthest;
+    let furthest_right =
+        furthest_by_vector(cells, cell_pos, spread_factor, empty_types, across).furthest;
+
+    // and mostly wont break away from the rest of its kind, which would pull a thin film apart into single cells
+    let holds_together = |to| !joins_own_kind(cells, cell_pos, to) && rng.f32() < DROP_COHESION;
+    let furthest_left = furthest_left.filter(|&to| !holds_together(to));
+    let furthest_right = furthest_right.filter(|&to| !holds_together(to));
+
+    if let (Some(furthest_left), Some(furthest_right)) = (furthest_left, furthest_right) {
+        if rng.bool() {
+            swap_cells(
+                cells,
+                tracking,
+                cell_pos,
+                (furthest_right.0, furthest_right.1),
+            );
+            return Some(furthest_right);
+        } else {
+            swap_cells(
+                cells,
+                tracking,
+                cell_pos,
+                (furthest_left.0, furthest_left.1),
+            );
+            return Some(furthest_left);
+        }
+    } else if let Some(furthest_left) = furthest_left {
+        swap_cells(
+            cells,
+            tracking,
+            cell_pos,
+            (furthest_left.0, furthest_left.1),
+        );
+        return Some(furthest_left);
+    } else if let Some(furthest_right) = furthest_right {
+        swap_cells(
+            cells,
+            tracking,
+            cell_pos,
+            (furthest_right.0, furthest_right.1),
+        );
+        return Some(furthest_right);
+    }
+
+    None
+}
+
+// whether a cell moved from from to to would have any of its own kind next to it there
+fn joins_own_kind(cells: &GridWindow, from: (usize, usize), to: (usize, usize)) -> bool {
+    let cell_type = cells[from].ty;
+
+    NEIGHBOURS.into_iter().any(|direction| {
+        cells
+            .ray(to, direction, 1)
+            .next()
+            .is_some_and(|(pos, neighbour)| pos != from && neighbour.ty == cell_type)
+    })
+}
+
+fn lone_drop(cells: &GridWindow, pos: (usize, usize)) -> bool {
+    let cell_type = cells[pos].ty;
+
+    !NEIGHBOURS.into_iter().any(|direction| {
+        cells
+            .ray(pos, direction, 1)
+            .next()
+            .is_some_and(|(_, neighbour)| neighbour.ty == cell_type)
+    })
+}
+
+// the empty cell next to pos on the side of the nearest cell of the same type across gravity, looking through empty
+// cells up to COHESION_REACH away
+fn towards_own_kind(
+    cells: &GridWindow,
+    pos: (usize, usize),
+    across: (isize, isize),
+    empty_types: &[CellType],
+) -> Option<(usize, usize)> {
+    let cell_type = cells[pos].ty;
+
+    let distance_to_own_kind = |direction| {
+        cells
+            .ray(pos, direction, COHESION_REACH)
+            .take_while(|(_, cell)| cell.ty == cell_type || empty_types.contains(&cell.ty))
+            .position(|(_, cell)| cell.ty == cell_type)
+    };
+
+    let left = (-across.0, -across.1);
+    let direction = match (distance_to_own_kind(left), distance_to_own_kind(across)) {
+        (Some(left_distance), Some(right_distance)) if left_distance <= right_distance => left,
+        (Some(_), None) => left,
+        (_, Some(_)) => across,
+        (None, None) => return None,
+    };
+
+    cells.ray(pos, direction, 1).next().map(|(step, _)| step)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn generic_fall(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    cell_pos: (usize, usize),
+    fall_through_types: &[CellType],
+    max_velocity: f32,
+    acceleration: f32,
+    inverted: bool,
+    rng: &Rng,
+) -> Option<(usize, usize)> {
+    let gravity = tracking.gravity_at(cell_pos);
+    let down = if inverted {
+        (-gravity.0, -gravity.1)
+    } else {
+        gravity
+    };
+
+    if cells[cell_pos].velocity_x != 0 {
+        if let Some(landed) = fly(
+            cells,
+            tracking,
+            cell_pos,
+            fall_through_types,
+            max_velocity,
+            acceleration,
+            down,
+        ) {
+            return Some(landed);
+        }
+    }
+
+    let velocity = cells[cell_pos].velocity() as usize;
+
+    let down_scan = furthest_by_vector(cells, cell_pos, velocity, fall_through_types, down);
+    if let Some(furthest_down) = down_scan.furthest {
+        cells[cell_pos].accelerate(acceleration, max_velocity);
+        swap_cells(
+            cells,
+            tracking,
+            cell_pos,
+            (furthest_down.0, furthest_down.1),
+        );
+        // todo swap current with furthest, then current with furthest - 1 = put whatever was in furthest on top of current
+
+        return Some(furthest_down);
+    }
+
+    // lying on the bottom edge (or the top for gases), the diagonals would only step off it too. wet sand mostly stays
+    // put instead of sliding off
+    let across = across(down);
+    let (furthest_down_left, furthest_down_right) =
+        if down_scan.at_edge() || sticks(&cells[cell_pos], rng) {
+            (None, None)
+        } else {
+            (
+                furthest_by_vector(
+                    cells,
+                    cell_pos,
+                    velocity,
+                    fall_through_types,
+                    (down.0 - across.0, down.1 - across.1),
+                )
+                .furthest,
+                furthest_by_vector(
+                    cells,
+                    cell_pos,
+                    velocity,
+                    fall_through_types,
+                    (down.0 + across.0, down.1 + across.1),
+                )
+                .furthest,
+            )
+        };
 
     if let (Some(furthest_down_left), Some(furthest_down_right)) =
         (furthest_down_left, furthest_down_right)
     {
         if rng.bool() {
-            cells[cell_pos.0][cell_pos.1].velocity =
-                (cells[cell_pos.0][cell_pos.1].velocity + acceleration).min(max_velocity);
+            cells[cell_pos].accelerate(acceleration, max_velocity);
             swap_cells(
                 cells,
+                tracking,
                 cell_pos,
                 (furthest_down_left.0, furthest_down_left.1),
             );
+            slide(cells, furthest_down_left, down, -1.0);
             return Some(furthest_down_left);
         } else {
-            cells[cell_pos.0][cell_pos.1].velocity =
-                (cells[cell_pos.0][cell_pos.1].velocity + acceleration).min(max_velocity);
+            cells[cell_pos].accelerate(acceleration, max_velocity);
             swap_cells(
                 cells,
+                tracking,
                 cell_pos,
                 (furthest_down_right.0, furthest_down_right.1),
             );
+            slide(cells, furthest_down_right, down, 1.0);
             return Some(furthest_down_right);
         }
     } else if let Some(furthest_down_left) = furthest_down_left {
-        cells[cell_pos.0][cell_pos.1].velocity =
-            (cells[cell_pos.0][cell_pos.1].velocity + acceleration).min(max_velocity);
+        cells[cell_pos].accelerate(acceleration, max_velocity);
         swap_cells(
             cells,
+            tracking,
             cell_pos,
             (furthest_down_left.0, furthest_down_left.1),
         );
+        slide(cells, furthest_down_left, down, -1.0);
         return Some(furthest_down_left);
     } else if let Some(furthest_down_right) = furthest_down_right {
-        cells[cell_pos.0][cell_pos.1].velocity =
-            (cells[cell_pos.0][cell_pos.1].velocity + acceleration).min(max_velocity);
+        cells[cell_pos].accelerate(acceleration, max_velocity);
         swap_cells(
             cells,
+            tracking,
             cell_pos,
             (furthest_down_right.0, furthest_down_right.1),
         );
+        slide(cells, furthest_down_right, down, 1.0);
         return Some(furthest_down_right);
     }
 
     // if we didnt move then turn down velocity
-    cells[cell_pos.0][cell_pos.1].velocity /= 2.0;
+    let velocity = cells[cell_pos].velocity();
+    cells[cell_pos].set_velocity(velocity / 2.0);
 
     None
 }
 
-fn swap_cells(cells: &mut [Vec<Cell>], cell_1_pos: (usize, usize), cell_2_pos: (usize, usize)) {
-    let temp_cell = cells[cell_1_pos.0][cell_1_pos.1].clone();
-    cells[cell_1_pos.0][cell_1_pos.1] = cells[cell_2_pos.0][cell_2_pos.1].clone();
-    cells[cell_2_pos.0][cell_2_pos.1] = temp_cell;
-    cells[cell_1_pos.0][cell_1_pos.1].moved = false;
-    cells[cell_2_pos.0][cell_2_pos.1].moved = true;
+// a cell with sideways velocity moves along the line of its velocity and fall instead, so thrown cells arc through the
+// air. one resting on something slides along the top of it instead. hitting anything on the way stops it flying and it
+// goes back to the usual rules, it also gets there by slowing down
+fn fly(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    cell_pos: (usize, usize),
+    fall_through_types: &[CellType],
+    max_velocity: f32,
+    acceleration: f32,
+    down: (isize, isize),
+) -> Option<(usize, usize)> {
+    let supported = furthest_by_vector(cells, cell_pos, 0, fall_through_types, down)
+        .furthest
+        .is_none();
+
+    let across = across(down);
+    let sideways = cells[cell_pos].velocity_x().round() as isize;
+    // falls the same velocity + 1 cells a tick as generic_fall does
+    let fall = if supported {
+        0
+    } else {
+        cells[cell_pos].velocity() as isize + 1
+    };
+    let movement = (
+        across.0 * sideways + down.0 * fall,
+        across.1 * sideways + down.1 * fall,
+    );
+    let end = (
+        cell_pos.0 as isize + movement.0,
+        cell_pos.1 as isize + movement.1,
+    );
+
+    let Some(furthest) =
+        furthest_along_line(cells, cell_pos, movement, fall_through_types).furthest
+    else {
+        cells[cell_pos].set_velocity_x(0.0);
+        return None;
+    };
+
+    if (furthest.0 as isize, furthest.1 as isize) == end {
+        if supported && on_ice(cells, cell_pos, down) {
+            cells[cell_pos].drag(ICE_DRAG);
+        } else if supported {
+            cells[cell_pos].drag(GROUND_DRAG);
+        } else {
+            cells[cell_pos].drag(AIR_DRAG);
+            cells[cell_pos].accelerate(acceleration, max_velocity);
+        }
+    } else {
+        cells[cell_pos].set_velocity_x(0.0);
+    }
+
+    swap_cells(cells, tracking, cell_pos, furthest);
+
+    Some(furthest)
+}
+
+fn on_ice(cells: &GridWindow, pos: (usize, usize), down: (isize, isize)) -> bool {
+    cells
+        .ray(pos, down, 1)
+        .next()
+        .is_some_and(|(_, below)| below.ty == CellType::Ice)
+}
+
+// a cell that tipped off sideways onto ice keeps going that way, fly carries it along the top until it slows down
+fn slide(cells: &mut GridWindow, pos: (usize, usize), down: (isize, isize), side: f32) {
+    if cells[pos].velocity_x == 0 && on_ice(cells, pos, down) {
+        cells[pos].set_velocity_x(side * ICE_SLIDE_VELOCITY);
+    }
+}
+
+// sideways to down, pointing right when gravity points down
+fn across(down: (isize, isize)) -> (isize, isize) {
+    (down.1.abs(), down.0.abs())
+}
+
+fn direction_index(direction: (isize, isize)) -> u8 {
+    DIRECTIONS
+        .iter()
+        .position(|&straight_direction| straight_direction == direction)
+        .unwrap() as u8
+}
+
+// a quarter turn clockwise on screen, down turns to left
+fn rotate_clockwise((x, y): (isize, isize)) -> (isize, isize) {
+    (-y, x)
 }
 
-fn spread_to_cell(cells: &mut [Vec<Cell>], cell_1_pos: (usize, usize), cell_2_pos: (usize, usize)) {
-    cells[cell_2_pos.0][cell_2_pos.1] = cells[cell_1_pos.0][cell_1_pos.1].clone();
-    cells[cell_2_pos.0][cell_2_pos.1].moved = true;
-    cells[cell_1_pos.0][cell_1_pos.1].moved = true;
+fn swap_cells(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    cell_1_pos: (usize, usize),
+    cell_2_pos: (usize, usize),
+) {
+    // only moving between air and not air across rows changes the counts
+    if cell_1_pos.1 != cell_2_pos.1
+        && (cells[cell_1_pos].ty == CellType::Air) != (cells[cell_2_pos].ty == CellType::Air)
+    {
+        let (from_y, to_y) = if cells[cell_1_pos].ty == CellType::Air {
+            (cell_2_pos.1, cell_1_pos.1)
+        } else {
+            (cell_1_pos.1, cell_2_pos.1)
+        };
+
+        tracking.remove_from_row(from_y);
+        tracking.add_to_row(to_y);
+    }
+
+    tracking.mark_dirty(cell_1_pos.0, cell_1_pos.1);
+    tracking.mark_dirty(cell_2_pos.0, cell_2_pos.1);
+
+    cells.swap(cell_1_pos, cell_2_pos);
+
+    cells[cell_1_pos].clear_moved();
+    cells[cell_2_pos].set_moved(tracking.generation());
+}
+
+// replaces a cell in place keeping the tracking up to date
+fn set_cell(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), cell: Cell) {
+    let was_air = cells[pos].ty == CellType::Air;
+    let is_air = cell.ty == CellType::Air;
+
+    if was_air && !is_air {
+        tracking.add_to_row(pos.1);
+    } else if !was_air && is_air {
+        tracking.remove_from_row(pos.1);
+    }
+
+    tracking.mark_dirty(pos.0, pos.1);
+
+    cells[pos] = cell;
+}
+
+fn is_empty(cells: &GridWindow, x: usize, y: usize, empty_types: &[CellType]) -> bool {
+    empty_types.contains(&cells[(x, y)].ty)
+}
+
+// what a scan out along a direction found, the furthest empty cell before anything got in the way and the cell that got
+// in the way. both are None when the first step is already off the edge of the window
+struct VectorScan {
+    furthest: Option<(usize, usize)>,
+    blocker: Option<(usize, usize)>,
 }
 
-fn is_empty(cells: &[Vec<Cell>], x: usize, y: usize, empty_types: &[CellType]) -> bool {
-    empty_types.contains(&cells[x][y].ty)
+impl VectorScan {
+    fn at_edge(&self) -> bool {
+        self.furthest.is_none() && self.blocker.is_none()
+    }
 }
 
 fn furthest_by_vector(
-    cells: &[Vec<Cell>],
+    cells: &GridWindow,
     cell_pos: (usize, usize),
     movement_magnitude: usize,
     empty_types: &[CellType],
     direction: (isize, isize),
-) -> Option<(usize, usize)> {
-    assert!(direction.0.abs() <= 1 && direction.1.abs() <= 1);
+) -> VectorScan {
+    let mut scan = VectorScan {
+        furthest: None,
+        blocker: None,
+    };
 
-    let mut closest = None;
-    for i in 1..=(movement_magnitude + 1) as isize {
-        let current_cell = (
-            cell_pos.0 as isize + direction.0 * i,
-            cell_pos.1 as isize + direction.1 * i,
-        );
-        if in_bounds(current_cell.0, current_cell.1)
-            && is_empty(
-                cells,
-                current_cell.0 as usize,
-                current_cell.1 as usize,
-                empty_types,
-            )
-        {
-            closest = Some((current_cell.0 as usize, current_cell.1 as usize))
+    let diagonal = direction.0 != 0 && direction.1 != 0;
+    let mut previous = cell_pos;
+
+    // stops at the first cell that isnt empty so nothing clips through walls
+    for (pos, cell) in cells.ray(cell_pos, direction, movement_magnitude + 1) {
+        if !empty_types.contains(&cell.ty) {
+            scan.blocker = Some(pos);
+            break;
+        }
+
+        if diagonal && squeezes_between_walls(cells, previous, pos) {
+            scan.blocker = Some((previous.0, pos.1));
+            break;
         }
-        // not breaking causes clipping but breaking makes everything funny. idc im doing it
-        // else {
-        //     break
-        // }
+
+        scan.furthest = Some(pos);
+        previous = pos;
     }
 
-    closest
+    scan
 }
 
-#[inline(always)]
-fn in_bounds_bottom(y: usize) -> bool {
-    y < HEIGHT
-}
+// furthest_by_vector along any line rather than one of the 8 directions, stepping through whichever cell the line passes
+// closest to on each step so nothing gets skipped. stops at the edge of the window the same way
+fn furthest_along_line(
+    cells: &GridWindow,
+    cell_pos: (usize, usize),
+    (dx, dy): (isize, isize),
+    empty_types: &[CellType],
+) -> VectorScan {
+    let mut scan = VectorScan {
+        furthest: None,
+        blocker: None,
+    };
 
-#[inline(always)]
-fn in_bounds_right(x: usize) -> bool {
-    x < WIDTH
-}
+    let mut previous = cell_pos;
+    let steps = dx.abs().max(dy.abs());
+    for step in 1..=steps {
+        // rounds to the nearest cell, the line moves at most 1 cell along each axis a step
+        let x = cell_pos.0 as isize + (2 * dx * step + steps).div_euclid(2 * steps);
+        let y = cell_pos.1 as isize + (2 * dy * step + steps).div_euclid(2 * steps);
+        if x < 0 || y < 0 || x >= cells.width() as isize || y >= HEIGHT as isize {
+            break;
+        }
 
-#[inline(always)]
-fn in_bounds_top(y: isize) -> bool {
-    y >= 0
-}
+        let pos = (x as usize, y as usize);
+        if !empty_types.contains(&cells[pos].ty) {
+            scan.blocker = Some(pos);
+            break;
+        }
 
-#[inline(always)]
-fn in_bounds_left(x: isize) -> bool {
-    x >= 0
+        if squeezes_between_walls(cells, previous, pos) {
+            scan.blocker = Some((previous.0, pos.1));
+            break;
+        }
+
+        scan.furthest = Some(pos);
+        previous = pos;
+    }
+
+    scan
 }
 
-#[inline(always)]
-fn in_bounds(x: isize, y: isize) -> bool {
-    // check left and top = check greater than -1 before casting to usize, sketchy
-    in_bounds_left(x)
-        && in_bounds_top(y)
-        && in_bounds_bottom(y as usize)
-        && in_bounds_right(x as usize)
+// a diagonal step between two walls that only touch at their corners, the walls still make a solid line so the step
+// would tunnel through it
+fn squeezes_between_walls(cells: &GridWindow, from: (usize, usize), to: (usize, usize)) -> bool {
+    from.0 != to.0
+        && from.1 != to.1
+        && cells[(from.0, to.1)].ty == CellType::Wood
+        && cells[(to.0, from.1)].ty == CellType::Wood
 }
 
-fn draw_menu(frame: &mut [u8], selected_cell_type: CellType) {
+// nothing is highlighted while a field brush is picked
+fn draw_menu(frame: &mut [u8], selected_cell_type: Option<CellType>) {
     let starting = (3, 3);
     let spacing = 3;
     let square_size = 15;
 
+    // wraps into another column once it reaches the bottom
+    let per_column = (HEIGHT - starting.1) / (spacing + square_size);
+
     // skip 1 = skip drawing the square for the air cell type
     for (cell_type_index, cell_type) in all::<CellType>().skip(1).enumerate() {
-        if selected_cell_type == cell_type {
+        let top_left = (
+            starting.0 + spacing + (spacing + square_size) * (cell_type_index / per_column),
+            starting.1 + (spacing + square_size) * (cell_type_index % per_column),
+        );
+
+        if selected_cell_type == Some(cell_type) {
             draw_square(
                 frame,
-                (
-                    starting.0 + spacing - 1,
-                    starting.0 + (spacing + square_size) * cell_type_index - 1,
-                ),
+                (top_left.0 - 1, top_left.1 - 1),
                 square_size + 2,
                 &[0xff, 0xea, 0x00],
                 Some(&cell_type_color_fixed(cell_type)),
@@ -610,10 +4565,7 @@ fn draw_menu(frame: &mut [u8], selected_cell_type: CellType) {
         } else {
             draw_square(
                 frame,
-                (
-                    starting.0 + spacing,
-                    starting.0 + (spacing + square_size) * cell_type_index,
-                ),
+                top_left,
                 square_size,
                 &[0xff, 0xff, 0xff],
                 Some(&cell_type_color_fixed(cell_type)),
@@ -652,7 +4604,7 @@ fn draw_cursor(frame: &mut [u8], cursor_position: (usize, usize), cursor_radius:
             (cursor_position.1 as f32 + cursor_radius * theta.sin()) as usize,
         );
 
-        if !in_bounds(current_pixel.0 as isize, current_pixel.1 as isize) {
+        if current_pixel.0 >= WIDTH || current_pixel.1 >= HEIGHT {
             continue;
         }
 
@@ -669,6 +4621,55 @@ fn draw_cursor(frame: &mut [u8], cursor_position: (usize, usize), cursor_radius:
     }
 }
 
+fn draw_paused_indicator(frame: &mut [u8]) {
+    let bar_width = 4;
+    let bar_height = 13;
+    let spacing = 3;
+    let top_right = (WIDTH - 6, 6);
+
+    // two bars, pause symbol
+    for bar in 0..2 {
+        let bar_left = top_right.0 - bar_width - bar * (bar_width + spacing);
+
+        for y in top_right.1..top_right.1 + bar_height {
+            for x in bar_left..bar_left + bar_width {
+                write_to_pixel_buffer(frame, to_1d_index_pixel_buffer(x, y), &[0xff, 0xff, 0xff])
+            }
+        }
+    }
+}
+
+// a bar along the top out from the middle, as far as the wind blows that way
+fn draw_wind_indicator(frame: &mut [u8], wind: f32) {
+    if wind == 0.0 {
+        return;
+    }
+
+    let max_length = 30.0;
+    let middle = (WIDTH / 2, 6);
+    let length = (wind.abs() / MAX_WIND * max_length) as usize;
+    let x_range = if wind > 0.0 {
+        middle.0..middle.0 + length
+    } else {
+        middle.0 - length..middle.0
+    };
+
+    for y in middle.1 - 1..=middle.1 + 1 {
+        for x in x_range.clone() {
+            write_to_pixel_buffer(frame, to_1d_index_pixel_buffer(x, y), &[0x9c, 0xd8, 0xf0]);
+        }
+    }
+
+    // where no wind would be
+    for y in middle.1 - 3..=middle.1 + 3 {
+        write_to_pixel_buffer(
+            frame,
+            to_1d_index_pixel_buffer(middle.0, y),
+            &[0xff, 0xff, 0xff],
+        );
+    }
+}
+
 fn write_to_pixel_buffer(frame: &mut [u8], index: usize, color: &[u8; 3]) {
     frame[index] = color[0];
     frame[index + 1] = color[1];
@@ -703,51 +4704,727 @@ fn cell_type_color_fixed(cell_type: CellType) -> [u8; 3] {
         CellType::Fire => FIRE_COLORS[0],
         CellType::Smoke => SMOKE_COLOR_LIGHT,
         CellType::Steam => STEAM_COLOR_LIGHT,
+        CellType::Tnt => TNT_COLORS[0],
+        CellType::Fan => FAN_COLOR,
+        CellType::Salt => SALT_COLORS[0],
+        CellType::Acid => ACID_COLORS[0],
+        CellType::Wire => WIRE_COLOR,
+        CellType::Battery => BATTERY_COLOR,
+        CellType::Switch => SWITCH_COLORS[1],
+        CellType::Plate => PLATE_COLOR,
+        CellType::Gate => GATE_COLORS[0],
+        CellType::Lamp => LAMP_COLOR,
+        CellType::Laser => LASER_COLOR,
+        CellType::Mirror => MIRROR_COLORS[0],
+        CellType::Glass => GLASS_COLOR,
+        CellType::Hydrogen => HYDROGEN_COLOR,
+        CellType::Uranium => URANIUM_COLORS[0],
+        CellType::Plutonium => PLUTONIUM_COLORS[0],
+        CellType::Slag => SLAG_COLORS[0],
+        CellType::Antimatter => ANTIMATTER_COLORS[0],
+        CellType::Seed => SEED_COLORS[0],
+        CellType::Plant => PLANT_COLORS[0],
+        CellType::Ant => ANT_COLORS[0],
+        CellType::Fish => FISH_COLORS[0],
+        CellType::Virus => VIRUS_COLORS[0],
+        CellType::Ash => ASH_COLORS[0],
+        CellType::Sponge => SPONGE_COLORS[0],
+        CellType::ToxicGas => TOXIC_GAS_COLORS[0],
+        CellType::Methane => METHANE_COLORS[0],
+        CellType::Soap => SOAP_COLORS[0],
+        CellType::Foam => FOAM_COLORS[0],
+        CellType::Body => BODY_COLORS[0],
+        CellType::Goo => GOO_COLORS[0],
+        CellType::Slime => SLIME_COLORS[0],
+        CellType::Stone => STONE_COLORS[0],
+        CellType::Dirt => DIRT_COLORS[0],
+        CellType::Coal => COAL_COLORS[0],
+        CellType::Gold => GOLD_COLORS[0],
+        CellType::Spout => SPOUT_COLOR,
+        CellType::Drain => DRAIN_COLOR,
+        CellType::Clone => CLONE_COLOR,
+        CellType::Void => VOID_COLOR,
+        CellType::Portal => PORTAL_COLORS[0],
+        CellType::Conveyor => CONVEYOR_COLOR,
+        CellType::Heater => HEATER_COLOR,
+        CellType::Cooler => COOLER_COLOR,
+        CellType::Fuse => FUSE_COLORS[0],
+        CellType::Detonator => DETONATOR_COLOR,
+        CellType::Timer => TIMER_COLOR,
+        CellType::Delay => DELAY_COLOR,
+        CellType::Sensor => SENSOR_COLOR,
+        CellType::Ice => ICE_COLORS[0],
+        CellType::Lava => LAVA_COLORS[0],
+        CellType::Snow => SNOW_COLORS[0],
+        CellType::Confetti => CONFETTI_COLORS[0],
+        CellType::Oil => OIL_COLORS[0],
+        CellType::Gunpowder => GUNPOWDER_COLORS[0],
+        CellType::Nitro => NITRO_COLORS[0],
+        CellType::Vine => VINE_COLORS[0],
+        CellType::Moss => MOSS_COLORS[0],
+        CellType::Fungus => FUNGUS_COLORS[0],
+        CellType::Metal => METAL_COLORS[0],
+        CellType::MoltenMetal => MOLTEN_METAL_COLORS[0],
+        CellType::Rust => RUST_COLORS[0],
+        CellType::Obsidian => OBSIDIAN_COLORS[0],
+        CellType::Mud => MUD_COLORS[0],
+        CellType::CharredWood => CHARRED_COLOR,
+        CellType::Honey => HONEY_COLORS[0],
+        CellType::Napalm => NAPALM_COLORS[0],
     }
 }
 
-fn cell_type_color_random(cell: &Cell, rng: &Rng) -> [u8; 3] {
+fn cell_type_color_index_random(cell_type: CellType, rng: &Rng) -> u8 {
+    let color_count = match cell_type {
+        CellType::Sand => SAND_COLORS.len(),
+        CellType::Water => WATER_COLORS.len(),
+        CellType::Wood => WOOD_COLORS.len(),
+        CellType::Fire => FIRE_COLORS.len(),
+        CellType::Tnt => TNT_COLORS.len(),
+        CellType::Salt => SALT_COLORS.len(),
+        CellType::Acid => ACID_COLORS.len(),
+        CellType::Uranium => URANIUM_COLORS.len(),
+        CellType::Plutonium => PLUTONIUM_COLORS.len(),
+        CellType::Slag => SLAG_COLORS.len(),
+        CellType::Antimatter => ANTIMATTER_COLORS.len(),
+        CellType::Seed => SEED_COLORS.len(),
+        CellType::Plant => PLANT_COLORS.len(),
+        CellType::Ant => ANT_COLORS.len(),
+        CellType::Fish => FISH_COLORS.len(),
+        CellType::Virus => VIRUS_COLORS.len(),
+        CellType::Ash => ASH_COLORS.len(),
+        CellType::Sponge => SPONGE_COLORS.len(),
+        CellType::ToxicGas => TOXIC_GAS_COLORS.len(),
+        CellType::Methane => METHANE_COLORS.len(),
+        CellType::Soap => SOAP_COLORS.len(),
+        CellType::Foam => FOAM_COLORS.len(),
+        CellType::Goo => GOO_COLORS.len(),
+        CellType::Slime => SLIME_COLORS.len(),
+        CellType::Stone => STONE_COLORS.len(),
+        CellType::Dirt => DIRT_COLORS.len(),
+        CellType::Coal => COAL_COLORS.len(),
+        CellType::Gold => GOLD_COLORS.len(),
+        CellType::Ice => ICE_COLORS.len(),
+        CellType::Snow => SNOW_COLORS.len(),
+        CellType::Confetti => CONFETTI_COLORS.len(),
+        CellType::Oil => OIL_COLORS.len(),
+        CellType::Gunpowder => GUNPOWDER_COLORS.len(),
+        CellType::Fuse => FUSE_COLORS.len(),
+        CellType::Nitro => NITRO_COLORS.len(),
+        CellType::Vine => VINE_COLORS.len(),
+        CellType::Moss => MOSS_COLORS.len(),
+        CellType::Fungus => FUNGUS_COLORS.len(),
+        CellType::Metal => METAL_COLORS.len(),
+        CellType::MoltenMetal => MOLTEN_METAL_COLORS.len(),
+        CellType::Rust => RUST_COLORS.len(),
+        CellType::Obsidian => OBSIDIAN_COLORS.len(),
+        CellType::Mud => MUD_COLORS.len(),
+        CellType::CharredWood => WOOD_COLORS.len(),
+        CellType::Honey => HONEY_COLORS.len(),
+        CellType::Napalm => NAPALM_COLORS.len(),
+        CellType::Lava => LAVA_COLORS.len(),
+        CellType::Fan
+        | CellType::Wire
+        | CellType::Battery
+        | CellType::Switch
+        | CellType::Plate
+        | CellType::Gate
+        | CellType::Lamp
+        | CellType::Laser
+        | CellType::Mirror
+        | CellType::Glass
+        | CellType::Spout
+        | CellType::Drain
+        | CellType::Clone
+        | CellType::Void
+        | CellType::Portal
+        | CellType::Conveyor
+        | CellType::Heater
+        | CellType::Cooler
+        | CellType::Detonator
+        | CellType::Timer
+        | CellType::Delay
+        | CellType::Sensor
+        | CellType::Hydrogen => 1,
+        // single color or picked from lifetime
+        CellType::Air | CellType::Smoke | CellType::Steam | CellType::Body => 1,
+    };
+
+    rng.usize(0..color_count) as u8
+}
+
+fn cell_color(cell: &Cell) -> [u8; 3] {
+    let color_index = cell.color_index as usize;
+
     match cell.ty {
-        CellType::Sand => SAND_COLORS[rng.usize(0..SAND_COLORS.len())],
-        CellType::Water => WATER_COLORS[rng.usize(0..WATER_COLORS.len())],
+        CellType::Sand => interpolate_color(
+            &AIR_COLOR,
+            &SAND_COLORS[color_index],
+            cell.lifetime as f32 / MAX_WETNESS as f32 * WET_SAND_DARKNESS,
+        ),
+        CellType::Dirt => interpolate_color(
+            &AIR_COLOR,
+            &DIRT_COLORS[color_index],
+            cell.lifetime as f32 / MAX_WETNESS as f32 * WET_SAND_DARKNESS,
+        ),
+        CellType::Water => interpolate_color(
+            &SAND_COLORS[color_index],
+            &interpolate_color(
+                &ASHY_WATER_COLOR,
+                &interpolate_color(
+                    &SALT_COLORS[0],
+                    &WATER_COLORS[color_index],
+                    dissolved_salt(cell) as f32 / MAX_SALT as f32 * SALT_WATER_TINT,
+                ),
+                dissolved_ash(cell) as f32 / MAX_ASH as f32 * ASHY_WATER_TINT,
+            ),
+            cell.fuel as f32 * SILTY_WATER_TINT,
+        ),
         CellType::Air => AIR_COLOR,
-        CellType::Wood => WOOD_COLORS[rng.usize(0..WOOD_COLORS.len())],
-        CellType::Fire => FIRE_COLORS[rng.usize(0..FIRE_COLORS.len())],
-        CellType::Smoke => SMOKE_COLOR_LIGHT,
-        CellType::Steam => STEAM_COLOR_LIGHT,
+        CellType::Wood => soaked_color(cell, &WOOD_COLORS[color_index], &WOOD),
+        CellType::Fire => FIRE_COLORS[color_index],
+        CellType::Tnt => TNT_COLORS[color_index],
+        CellType::Fan => FAN_COLOR,
+        CellType::Salt => SALT_COLORS[color_index],
+        CellType::Acid => ACID_COLORS[color_index],
+        CellType::Wire => WIRE_COLOR,
+        CellType::Battery => BATTERY_COLOR,
+        CellType::Switch => SWITCH_COLORS[electricity::switch_on(cell) as usize],
+        CellType::Plate => PLATE_COLOR,
+        CellType::Gate => GATE_COLORS[electricity::gate(cell) as usize],
+        CellType::Lamp => LAMP_COLOR,
+        CellType::Laser => LASER_COLOR,
+        CellType::Mirror => MIRROR_COLORS[light::mirror(cell) as usize],
+        CellType::Glass => GLASS_COLOR,
+        CellType::Hydrogen => HYDROGEN_COLOR,
+        CellType::Uranium => URANIUM_COLORS[color_index],
+        CellType::Plutonium => PLUTONIUM_COLORS[color_index],
+        CellType::Slag => SLAG_COLORS[color_index],
+        CellType::Antimatter => ANTIMATTER_COLORS[color_index],
+        CellType::Seed => SEED_COLORS[color_index],
+        CellType::Plant => PLANT_COLORS[color_index],
+        CellType::Ant => ANT_COLORS[color_index],
+        CellType::Fish => FISH_COLORS[color_index],
+        CellType::Virus => VIRUS_COLORS[color_index],
+        CellType::Ash => ASH_COLORS[color_index],
+        CellType::Sponge => soaked_color(cell, &SPONGE_COLORS[color_index], &SPONGE),
+        CellType::ToxicGas => TOXIC_GAS_COLORS[color_index],
+        CellType::Methane => METHANE_COLORS[color_index],
+        CellType::Soap => SOAP_COLORS[color_index],
+        CellType::Foam => FOAM_COLORS[color_index],
+        CellType::Body => BODY_COLORS[cell.lifetime as usize],
+        CellType::Goo => interpolate_color(
+            &GOO_COLOR_STRETCHED,
+            &GOO_COLORS[color_index],
+            (cell.fuel as f32 / GOO.strength).min(1.0),
+        ),
+        CellType::Slime => interpolate_color(
+            &SLIME_COLOR_STRETCHED,
+            &SLIME_COLORS[color_index],
+            (cell.fuel as f32 / SLIME.strength).min(1.0),
+        ),
+        CellType::Stone => STONE_COLORS[color_index],
+        CellType::Coal => COAL_COLORS[color_index],
+        CellType::Gold => GOLD_COLORS[color_index],
+        CellType::Spout => SPOUT_COLOR,
+        CellType::Drain => DRAIN_COLOR,
+        CellType::Clone => CLONE_COLOR,
+        CellType::Void => VOID_COLOR,
+        CellType::Portal => PORTAL_COLORS[portal::portal_end(cell)],
+        CellType::Conveyor => CONVEYOR_COLOR,
+        CellType::Heater => HEATER_COLOR,
+        CellType::Cooler => COOLER_COLOR,
+        CellType::Fuse if cell.lifetime > 0 => FUSE_BURNING_COLOR,
+        CellType::Fuse => FUSE_COLORS[color_index],
+        CellType::Detonator => DETONATOR_COLOR,
+        CellType::Timer => TIMER_COLOR,
+        CellType::Delay => DELAY_COLOR,
+        CellType::Sensor => SENSOR_COLOR,
+        CellType::Ice => ICE_COLORS[color_index],
+        CellType::Snow => SNOW_COLORS[color_index],
+        CellType::Confetti => CONFETTI_COLORS[color_index],
+        CellType::Oil => OIL_COLORS[color_index],
+        CellType::Honey => HONEY_COLORS[color_index],
+        CellType::Napalm if cell.lifetime == NAPALM_BURNING => NAPALM_BURNING_COLORS[color_index],
+        CellType::Napalm => NAPALM_COLORS[color_index],
+        CellType::Gunpowder => GUNPOWDER_COLORS[color_index],
+        CellType::Nitro => NITRO_COLORS[color_index],
+        CellType::Vine => VINE_COLORS[color_index],
+        CellType::Fungus => FUNGUS_COLORS[color_index],
+        CellType::Rust => RUST_COLORS[color_index],
+        CellType::Obsidian => OBSIDIAN_COLORS[color_index],
+        CellType::Mud => MUD_COLORS[color_index],
+        CellType::CharredWood => interpolate_color(
+            &CHARRED_COLOR,
+            &WOOD_COLORS[color_index],
+            charred_stage(cell) as f32 / CHARRED_STAGES as f32,
+        ),
+        CellType::Metal => interpolate_color(
+            &LAVA_COLORS[color_index],
+            &interpolate_color(
+                &RUST_COLORS[color_index],
+                &METAL_COLORS[color_index],
+                cell.lifetime as f32 / RUST_STAGES as f32,
+            ),
+            cell.temperature.saturating_sub(METAL_GLOW_TEMPERATURE) as f32
+                / (METAL_MELTING_TEMPERATURE - METAL_GLOW_TEMPERATURE) as f32,
+        ),
+        CellType::MoltenMetal => interpolate_color(
+            &MOLTEN_METAL_COLORS[color_index],
+            &LAVA_COLORS[color_index],
+            cell.temperature.saturating_sub(METAL_SETTING_TEMPERATURE) as f32
+                / (LAVA_TEMPERATURE - METAL_SETTING_TEMPERATURE) as f32,
+        ),
+        CellType::Moss => interpolate_color(
+            &MOSS_COLORS[color_index],
+            &cell_type_color_fixed(moss::host(cell)),
+            MOSS_TINT,
+        ),
+        CellType::Lava => interpolate_color(
+            &LAVA_COLORS[color_index],
+            &LAVA_COLOR_CRUST,
+            cell.temperature.saturating_sub(LAVA_SETTING_TEMPERATURE) as f32
+                / (LAVA_TEMPERATURE - LAVA_SETTING_TEMPERATURE) as f32,
+        ),
+        CellType::Smoke => interpolate_color(
+            &SMOKE_COLOR_LIGHT,
+            &SMOKE_COLOR_DARK,
+            cell.lifetime as f32 / SMOKE_LIFETIME as f32,
+        ),
+        CellType::Steam => interpolate_color(
+            &STEAM_COLOR_LIGHT,
+            &STEAM_COLOR_DARK,
+            cell.lifetime as f32 / STEAM_LIFETIME as f32,
+        ),
+    }
+}
+
+fn soaked_color(cell: &Cell, color: &[u8; 3], porous: &Porous) -> [u8; 3] {
+    interpolate_color(
+        &AIR_COLOR,
+        color,
+        cell.lifetime as f32 / porous.capacity as f32 * SOAKED_DARKNESS,
+    )
+}
+
+fn cell_type_tick_interval(cell_type: CellType) -> u64 {
+    match cell_type {
+        CellType::Smoke => SMOKE_TICK_INTERVAL,
+        CellType::Steam => STEAM_TICK_INTERVAL,
+        _ => 1,
+    }
+}
+
+fn cell_type_fuel(cell_type: CellType) -> u8 {
+    match cell_type {
+        CellType::Wood => WOOD_FUEL,
+        CellType::Plant | CellType::Vine | CellType::Fungus => PLANT_FUEL,
+        CellType::Ant | CellType::Fish => CREATURE_FUEL,
+        CellType::Virus => VIRUS_FUEL,
+        CellType::Fire => FIRE_FUEL,
+        CellType::Tnt => TNT_FUEL,
+        CellType::Coal => COAL_FUEL,
+        CellType::Oil => OIL_FUEL,
+        CellType::Honey => HONEY_FUEL,
+        CellType::Napalm => NAPALM_FUEL,
+        CellType::Gunpowder => GUNPOWDER_FUEL,
+        CellType::Nitro => NITRO_FUEL,
+        CellType::Confetti => CONFETTI_FUEL,
+        _ => 0,
+    }
+}
+
+// None for anything that doesnt burn
+fn cell_type_ignition_temperature(cell_type: CellType) -> Option<u8> {
+    match cell_type {
+        CellType::Wood => Some(WOOD_IGNITION_TEMPERATURE),
+        CellType::Plant | CellType::Vine | CellType::Fungus => Some(PLANT_IGNITION_TEMPERATURE),
+        CellType::Ant | CellType::Fish => Some(CREATURE_IGNITION_TEMPERATURE),
+        CellType::Virus => Some(VIRUS_IGNITION_TEMPERATURE),
+        CellType::Tnt => Some(TNT_IGNITION_TEMPERATURE),
+        CellType::Coal => Some(COAL_IGNITION_TEMPERATURE),
+        CellType::Oil => Some(OIL_IGNITION_TEMPERATURE),
+        CellType::Honey => Some(HONEY_IGNITION_TEMPERATURE),
+        CellType::Napalm => Some(NAPALM_IGNITION_TEMPERATURE),
+        CellType::Gunpowder => Some(GUNPOWDER_IGNITION_TEMPERATURE),
+        CellType::Nitro => Some(NITRO_IGNITION_TEMPERATURE),
+        CellType::Fuse => Some(FUSE_IGNITION_TEMPERATURE),
+        CellType::Confetti => Some(CONFETTI_IGNITION_TEMPERATURE),
+        CellType::Hydrogen => Some(HYDROGEN_IGNITION_TEMPERATURE),
+        CellType::Methane => Some(METHANE_IGNITION_TEMPERATURE),
+        _ => None,
+    }
+}
+
+// None for anything that isnt a liquid
+fn cell_type_liquid_density(cell_type: CellType) -> Option<f32> {
+    match cell_type {
+        CellType::Water => Some(WATER_DENSITY),
+        CellType::Acid => Some(ACID_DENSITY),
+        CellType::Oil => Some(OIL_DENSITY),
+        CellType::Honey => Some(HONEY_DENSITY),
+        CellType::Napalm => Some(NAPALM_DENSITY),
+        CellType::Nitro => Some(NITRO_DENSITY),
+        CellType::Lava => Some(LAVA_DENSITY),
+        CellType::MoltenMetal => Some(MOLTEN_METAL_DENSITY),
+        CellType::Mud => Some(MUD_DENSITY),
+        CellType::Slag => Some(SLAG_DENSITY),
+        _ => None,
+    }
+}
+
+// 0 for liquids as runny as water up to 1 for ones that never flow
+fn cell_type_viscosity(cell_type: CellType) -> f32 {
+    match cell_type {
+        CellType::Acid => ACID_VISCOSITY,
+        CellType::Oil => OIL_VISCOSITY,
+        CellType::Honey => HONEY_VISCOSITY,
+        CellType::Napalm => NAPALM_VISCOSITY,
+        CellType::Lava => LAVA_VISCOSITY,
+        CellType::MoltenMetal => MOLTEN_METAL_VISCOSITY,
+        CellType::Mud => MUD_VISCOSITY,
+        CellType::Slag => SLAG_VISCOSITY,
+        _ => 0.0,
+    }
+}
+
+// None for anything corrosives cant touch, otherwise the share of their attacks it shrugs off
+fn cell_type_corrosion_resistance(cell_type: CellType) -> Option<f32> {
+    match cell_type {
+        CellType::Sand
+        | CellType::Wire
+        | CellType::Battery
+        | CellType::Switch
+        | CellType::Plate
+        | CellType::Sensor
+        | CellType::Gate
+        | CellType::Lamp
+        | CellType::Laser
+        | CellType::Mirror
+        | CellType::Uranium
+        | CellType::Plutonium => Some(0.9),
+        CellType::Wood | CellType::CharredWood | CellType::Tnt => Some(0.8),
+        CellType::Salt
+        | CellType::Gunpowder
+        | CellType::Seed
+        | CellType::Plant
+        | CellType::Vine
+        | CellType::Moss
+        | CellType::Fungus
+        | CellType::Ash
+        | CellType::Sponge
+        | CellType::Ant
+        | CellType::Fish => Some(0.5),
+        CellType::Virus => Some(0.2),
+        CellType::Fan | CellType::Stone | CellType::Coal | CellType::Metal => Some(0.95),
+        CellType::Rust => Some(0.5),
+        CellType::Dirt | CellType::Mud | CellType::Honey => Some(0.5),
+        _ => None,
+    }
+}
+
+// None for anything water cant wash away, otherwise the share of the time flowing water over it leaves it be
+fn cell_type_erosion_resistance(cell_type: CellType) -> Option<f32> {
+    match cell_type {
+        CellType::Sand | CellType::Ash | CellType::Snow => Some(0.0),
+        CellType::Dirt => Some(0.5),
+        _ => None,
+    }
+}
+
+fn cell_type_porous(cell_type: CellType) -> Option<&'static Porous> {
+    match cell_type {
+        CellType::Wood => Some(&WOOD),
+        CellType::Sponge => Some(&SPONGE),
+        _ => None,
+    }
+}
+
+fn cell_type_goo(cell_type: CellType) -> Option<&'static Goo> {
+    match cell_type {
+        CellType::Goo => Some(&GOO),
+        CellType::Slime => Some(&SLIME),
+        _ => None,
+    }
+}
+
+fn cell_type_radioactivity(cell_type: CellType) -> Option<&'static Radioactive> {
+    match cell_type {
+        CellType::Uranium => Some(&URANIUM),
+        CellType::Plutonium => Some(&PLUTONIUM),
+        CellType::Slag => Some(&SLAG),
+        _ => None,
+    }
+}
+
+fn cell_type_corrosive(cell_type: CellType) -> Option<&'static Corrosive> {
+    match cell_type {
+        CellType::Acid => Some(&ACID),
+        _ => None,
+    }
+}
+
+// None for insulators, otherwise how much charge is lost passing through a cell of it
+fn cell_type_charge_loss(cell_type: CellType) -> Option<u8> {
+    match cell_type {
+        CellType::Wire
+        | CellType::Switch
+        | CellType::Plate
+        | CellType::Lamp
+        | CellType::Laser
+        | CellType::Metal => Some(WIRE_CHARGE_LOSS),
+        CellType::Gold => Some(GOLD_CHARGE_LOSS),
+        CellType::Water => Some(WATER_CHARGE_LOSS),
+        _ => None,
+    }
+}
+
+// the chance fire burning the cell type leaves smoke when it goes out, and what it leaves otherwise
+fn cell_type_combustion(cell_type: CellType) -> (f32, CellType) {
+    match cell_type {
+        CellType::Wood | CellType::CharredWood => (WOOD_SMOKE_CHANCE, CellType::Ash),
+        CellType::Tnt => (TNT_SMOKE_CHANCE, CellType::Air),
+        CellType::Coal => (COAL_SMOKE_CHANCE, CellType::Ash),
+        CellType::Oil => (OIL_SMOKE_CHANCE, CellType::Air),
+        CellType::Honey => (HONEY_SMOKE_CHANCE, CellType::Air),
+        CellType::Napalm => (NAPALM_SMOKE_CHANCE, CellType::Air),
+        CellType::Gunpowder | CellType::Nitro => (GUNPOWDER_SMOKE_CHANCE, CellType::Air),
+        CellType::Seed | CellType::Plant | CellType::Vine | CellType::Fungus => {
+            (FIRE_SMOKE_CHANCE, CellType::Ash)
+        }
+        _ => (FIRE_SMOKE_CHANCE, CellType::Air),
+    }
+}
+
+fn cell_type_temperature(cell_type: CellType) -> u8 {
+    match cell_type {
+        CellType::Steam => STEAM_TEMPERATURE,
+        CellType::Ice | CellType::Snow => WATER_FREEZING_TEMPERATURE,
+        CellType::Lava | CellType::MoltenMetal => LAVA_TEMPERATURE,
+        CellType::Slag => SLAG_TEMPERATURE,
+        _ => ROOM_TEMPERATURE,
+    }
+}
+
+// what the cell type turns into once it is at least this hot
+fn cell_type_hotter_phase(cell_type: CellType) -> Option<(u8, CellType)> {
+    match cell_type {
+        CellType::Water => Some((WATER_BOILING_TEMPERATURE, CellType::Steam)),
+        CellType::Ice | CellType::Snow => Some((ICE_MELTING_TEMPERATURE, CellType::Water)),
+        CellType::Stone => Some((STONE_MELTING_TEMPERATURE, CellType::Lava)),
+        CellType::Metal => Some((METAL_MELTING_TEMPERATURE, CellType::MoltenMetal)),
+        CellType::Sand => Some((SAND_FUSING_TEMPERATURE, CellType::Glass)),
+        _ => None,
+    }
+}
+
+// what the cell type turns into once it is colder than this
+fn cell_type_colder_phase(cell_type: CellType) -> Option<(u8, CellType)> {
+    match cell_type {
+        CellType::Steam => Some((WATER_BOILING_TEMPERATURE, CellType::Water)),
+        CellType::Water => Some((WATER_FREEZING_TEMPERATURE, CellType::Ice)),
+        CellType::Lava => Some((LAVA_SETTING_TEMPERATURE, CellType::Stone)),
+        CellType::MoltenMetal => Some((METAL_SETTING_TEMPERATURE, CellType::Metal)),
+        _ => None,
     }
 }
 
-fn cell_type_lifetime(cell_type: CellType) -> u32 {
+fn cell_type_lifetime(cell_type: CellType) -> u8 {
     match cell_type {
         CellType::Smoke => SMOKE_LIFETIME,
         CellType::Steam => STEAM_LIFETIME,
+        // new goo is linked to everything around it, so a blob drawn in one go holds together
+        CellType::Goo | CellType::Slime => goo::ALL_LINKS,
+        CellType::Ant | CellType::Fish => creature::RESTED,
+        CellType::Virus => VIRUS.burnout,
         _ => 0,
     }
 }
 
-fn draw_frame(
-    pixels: &mut Pixels,
-    cells: &[Vec<Cell>],
-    selected_cell_type: CellType,
+// the frame only holds cells, the ui is drawn into the overlay
+fn draw_frame(frame: &mut [u8], cells: &Grid, tracking: &Tracking, camera: &Camera) {
+    if camera.zoom > 1 {
+        draw_cells_zoomed(frame, cells, tracking, camera);
+        // every pixel moves when zoomed so the chunks cant be reused, keep them all dirty for when it zooms back out
+        tracking.mark_region_dirty((0, 0), (WIDTH - 1, HEIGHT - 1));
+    } else {
+        draw_dirty_chunks(frame, cells, tracking);
+    }
+}
+
+fn draw_dirty_chunks(frame: &mut [u8], cells: &Grid, tracking: &Tracking) {
+    // each band of frame rows one chunk tall is converted on its own thread, walking its rows in order so the writes
+    // into the frame are sequential. only chunks where something changed get redrawn
+    frame
+        .par_chunks_mut(WIDTH * 4 * CHUNK_SIZE)
+        .enumerate()
+        .for_each(|(chunk_y, band)| {
+            for chunk_x in 0..CHUNKS_X {
+                if !tracking.dirty_chunks[chunk_y * CHUNKS_X + chunk_x]
+                    .swap(false, Ordering::Relaxed)
+                {
+                    continue;
+                }
+
+                let x_range = chunk_x * CHUNK_SIZE..((chunk_x + 1) * CHUNK_SIZE).min(WIDTH);
+
+                for (band_y, row) in band.chunks_exact_mut(WIDTH * 4).enumerate() {
+                    let y = chunk_y * CHUNK_SIZE + band_y;
+                    let cell_row = &cells.row(y)[x_range.clone()];
+                    let field_row = &tracking.fields[y * WIDTH..][x_range.clone()];
+                    let charge_row = &tracking.charge[y * WIDTH..][x_range.clone()];
+                    let pixel_row = &mut row[x_range.start * 4..x_range.end * 4];
+
+                    for (((pixel, cell), &field), &charge) in pixel_row
+                        .chunks_exact_mut(4)
+                        .zip(cell_row)
+                        .zip(field_row)
+                        .zip(charge_row)
+                    {
+                        write_to_pixel_buffer(
+                            pixel,
+                            0,
+                            &cell_color_at(cell, field, charge, tracking.sky),
+                        );
+                    }
+                }
+            }
+        });
+}
+
+fn draw_cells_zoomed(frame: &mut [u8], cells: &Grid, tracking: &Tracking, camera: &Camera) {
+    frame
+        .par_chunks_mut(WIDTH * 4)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let (cell_x, cell_y) = camera.cell_at((x, y));
+                let color = cell_color_at(
+                    &cells.row(cell_y)[cell_x],
+                    tracking.fields[cell_y * WIDTH + cell_x],
+                    tracking.charge[cell_y * WIDTH + cell_x],
+                    tracking.sky,
+                );
+                write_to_pixel_buffer(pixel, 0, &color);
+            }
+        });
+}
+
+// empty space inside a painted field is tinted so the fields can be seen, the rest of it lightens with the sky during
+// the day. charged conductors glow and lamps light up
+fn cell_color_at(cell: &Cell, field: u8, charge: u8, sky: u8) -> [u8; 3] {
+    if cell.ty == CellType::Air && field != NO_FIELD {
+        FIELD_COLOR
+    } else if cell.ty == CellType::Air {
+        sky_color(sky)
+    } else if cell.ty == CellType::Glass {
+        interpolate_color(&sky_color(sky), &GLASS_COLOR, GLASS_CLARITY)
+    } else if cell.ty == CellType::Hydrogen {
+        interpolate_color(&sky_color(sky), &HYDROGEN_COLOR, HYDROGEN_CLARITY)
+    } else if cell.ty == CellType::Methane {
+        interpolate_color(
+            &sky_color(sky),
+            &METHANE_COLORS[cell.color_index as usize],
+            METHANE_CLARITY,
+        )
+    } else if cell.ty == CellType::Lamp && charge > 0 {
+        LAMP_LIT_COLOR
+    } else if charge > 0 {
+        interpolate_color(
+            &CHARGE_COLOR,
+            &cell_color(cell),
+            charge as f32 / electricity::MAX_CHARGE as f32 * CHARGE_GLOW,
+        )
+    } else {
+        cell_color(cell)
+    }
+}
+
+fn sky_color(sky: u8) -> [u8; 3] {
+    interpolate_color(&DAY_SKY_COLOR, &AIR_COLOR, sky as f32 / SKY_STEPS as f32)
+}
+
+// everything but the cells, redrawn from scratch every frame. laser beams and particles are drawn under the ui on the cpu
+#[allow(clippy::too_many_arguments)]
+fn draw_overlay(
+    frame: &mut [u8],
+    tracking: Option<&Tracking>,
+    camera: &Camera,
+    selected_cell_type: Option<CellType>,
     cursor_position: (usize, usize),
     cursor_radius: f32,
+    paused: bool,
 ) {
-    let frame = pixels.frame_mut();
-    let mut pixels = frame.chunks_exact_mut(4);
-
-    for y in 0..HEIGHT {
-        for x in 0..WIDTH {
-            let cell_color = &cells[x][y].color;
-
-            let color = [cell_color[0], cell_color[1], cell_color[2], 0xff];
+    frame.fill(0);
 
-            pixels.next().unwrap().copy_from_slice(&color);
+    if let Some(tracking) = tracking {
+        draw_beams(frame, &tracking.beams, camera);
+        if tracking.bolt_flash > 0 {
+            for &pos in &tracking.bolt {
+                draw_over_cell(frame, pos, camera, &BOLT_COLOR, 0xff);
+            }
         }
+        draw_particles(frame, &tracking.particles, camera);
     }
-
     draw_menu(frame, selected_cell_type);
     draw_cursor(frame, cursor_position, cursor_radius);
+
+    if paused {
+        draw_paused_indicator(frame);
+    }
+}
+
+// every cell a beam crossed is covered in see through beam color
+fn draw_beams(frame: &mut [u8], beams: &[(usize, usize)], camera: &Camera) {
+    for &pos in beams {
+        draw_over_cell(frame, pos, camera, &BEAM_COLOR, BEAM_ALPHA);
+    }
+}
+
+fn draw_particles(frame: &mut [u8], particles: &[Particle], camera: &Camera) {
+    for particle in particles {
+        let Some(pos) = particle.cell() else {
+            continue;
+        };
+        let color = match particle.kind {
+            ParticleKind::Neutron => NEUTRON_COLOR,
+            ParticleKind::Ember => interpolate_color(
+                &EMBER_COLOR_HOT,
+                &EMBER_COLOR_COOL,
+                particle.lifetime.min(EMBER_FADE) as f32 / EMBER_FADE as f32,
+            ),
+            ParticleKind::Debris(cell_type) => cell_type_color_fixed(cell_type),
+            ParticleKind::Spore => SPORE_COLOR,
+        };
+        draw_over_cell(frame, pos, camera, &color, 0xff);
+    }
+}
+
+// zoomed in a cell covers zoom by zoom pixels, nothing is drawn for cells out of view
+fn draw_over_cell(
+    frame: &mut [u8],
+    (x, y): (usize, usize),
+    camera: &Camera,
+    color: &[u8; 3],
+    alpha: u8,
+) {
+    let (Some(x), Some(y)) = (
+        x.checked_sub(camera.position.0),
+        y.checked_sub(camera.position.1),
+    ) else {
+        return;
+    };
+    if x * camera.zoom >= WIDTH || y * camera.zoom >= HEIGHT {
+        return;
+    }
+
+    for pixel_y in y * camera.zoom..(y + 1) * camera.zoom {
+        for pixel_x in x * camera.zoom..(x + 1) * camera.zoom {
+            let index = to_1d_index_pixel_buffer(pixel_x, pixel_y);
+            write_to_pixel_buffer(frame, index, color);
+            frame[index + 3] = alpha;
+        }
+    }
 }
 
 fn cursor_region_cell_coordinates(
@@ -762,27 +5439,71 @@ fn cursor_region_cell_coordinates(
     let y_start = (cursor_position.1 - cursor_radius).max(0);
     let y_end = (cursor_position.1 + cursor_radius).min(HEIGHT as i32);
 
-    (x_start..x_end)
-        .flat_map(move |x| (y_start..y_end).map(move |y| (x, y)))
+    // row by row to match the grid layout
+    (y_start..y_end)
+        .flat_map(move |y| (x_start..x_end).map(move |x| (x, y)))
         .filter(move |&(x, y)| {
             (cursor_position.0 - x).pow(2) + (cursor_position.1 - y).pow(2) <= cursor_radius.pow(2)
         })
         .map(move |(x, y)| (x as usize, y as usize))
 }
 
+// velocity_x throws the new cells sideways across gravity, in cells a tick. fans, gates, lasers, spouts, timers and
+// delays are put down facing facing, spouts, timers and delays put out spout_element, portals are put down as the end
+// of a pair portal says and conveyors run the other way when conveyor_reversed. detonators blow up detonator_radius
+// cells around them, timers and delays count timer_period ticks and sensors look for spout_element, or anything loose
+// when sensor_any
+#[allow(clippy::too_many_arguments)]
 fn put_cell(
-    cells: &mut [Vec<Cell>],
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
     selected_cell_type: CellType,
     cursor_position: (usize, usize),
     cursor_radius: f32,
+    velocity_x: f32,
+    facing: (isize, isize),
+    gate: Gate,
+    mirror: Mirror,
+    spout_element: CellType,
+    portal: u8,
+    conveyor_reversed: bool,
+    detonator_radius: u8,
+    timer_period: u8,
+    sensor_any: bool,
     rng: &Rng,
 ) {
     for (x, y) in cursor_region_cell_coordinates(cursor_position, cursor_radius) {
         match selected_cell_type {
-            CellType::Sand | CellType::Water | CellType::Fire | CellType::Smoke => {
-                if rng.f32() > 0.125 {
-                    continue;
-                }
+            CellType::Sand
+            | CellType::Salt
+            | CellType::Soap
+            | CellType::Gunpowder
+            | CellType::Antimatter
+            | CellType::Seed
+            | CellType::Ash
+            | CellType::Foam
+            | CellType::Confetti
+            | CellType::Dirt
+            | CellType::Mud
+            | CellType::Water
+            | CellType::Acid
+            | CellType::Oil
+            | CellType::Nitro
+            | CellType::Honey
+            | CellType::Napalm
+            | CellType::Lava
+            | CellType::MoltenMetal
+            | CellType::Slag
+            | CellType::Fire
+            | CellType::Smoke
+            | CellType::Hydrogen
+            | CellType::ToxicGas
+            | CellType::Methane
+            | CellType::Ant
+            | CellType::Fish
+                if rng.f32() > 0.125 =>
+            {
+                continue;
             }
             _ => (),
         }
@@ -794,28 +5515,426 @@ fn put_cell(
             y,
             &[CellType::Air, CellType::Smoke, CellType::Water],
         ) {
-            cells[x][y] = Cell::from(selected_cell_type, rng)
+            let mut cell = Cell::from(selected_cell_type, rng);
+            // only what falls can fly, wood, tnt and fire stay where they are put
+            if matches!(
+                selected_cell_type,
+                CellType::Sand
+                    | CellType::Salt
+                    | CellType::Soap
+                    | CellType::Gunpowder
+                    | CellType::Antimatter
+                    | CellType::Seed
+                    | CellType::Ash
+                    | CellType::Foam
+                    | CellType::Confetti
+                    | CellType::Dirt
+                    | CellType::Mud
+                    | CellType::Water
+                    | CellType::Acid
+                    | CellType::Oil
+                    | CellType::Nitro
+                    | CellType::Honey
+                    | CellType::Napalm
+                    | CellType::Lava
+                    | CellType::MoltenMetal
+                    | CellType::Slag
+                    | CellType::Smoke
+                    | CellType::Steam
+                    | CellType::Hydrogen
+                    | CellType::ToxicGas
+                    | CellType::Methane
+            ) {
+                cell.set_velocity_x(velocity_x);
+            }
+            if matches!(selected_cell_type, CellType::Fan | CellType::Laser) {
+                cell.lifetime = direction_index(facing);
+            }
+            if selected_cell_type == CellType::Mirror {
+                cell.lifetime = mirror as u8;
+            }
+            if selected_cell_type == CellType::Gate {
+                cell.lifetime = electricity::gate_lifetime(gate, facing);
+            }
+            if selected_cell_type == CellType::Spout {
+                spout::set_spout(&mut cell, spout_element, facing);
+            }
+            if selected_cell_type == CellType::Portal {
+                cell.lifetime = portal;
+            }
+            if selected_cell_type == CellType::Conveyor {
+                cell.lifetime = conveyor_reversed as u8;
+            }
+            if selected_cell_type == CellType::Detonator {
+                cell.lifetime = detonator_radius;
+            }
+            if matches!(selected_cell_type, CellType::Timer | CellType::Delay) {
+                spout::set_spout(&mut cell, spout_element, facing);
+                cell.fuel = timer::timer_fuel(selected_cell_type, timer_period);
+            }
+            if selected_cell_type == CellType::Sensor {
+                let element = if sensor_any {
+                    CellType::Air
+                } else {
+                    spout_element
+                };
+                cell.lifetime = element as u8;
+            }
+
+            set_cell(cells, tracking, (x, y), cell)
+        }
+    }
+}
+
+// picked straight after these, spouts, timers, delays and sensors keep the element the last one had instead of taking it
+fn keeps_element(cell_type: CellType) -> bool {
+    matches!(
+        cell_type,
+        CellType::Spout | CellType::Drain | CellType::Timer | CellType::Delay | CellType::Sensor
+    )
+}
+
+// the period after period in TIMER_PERIODS, back to the shortest after the longest
+fn next_period(period: u8) -> u8 {
+    let index = TIMER_PERIODS.iter().position(|&p| p == period).unwrap();
+    TIMER_PERIODS[(index + 1) % TIMER_PERIODS.len()]
+}
+
+// flips every switch under the cursor
+fn toggle_switches(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    cursor_position: (usize, usize),
+    cursor_radius: f32,
+) {
+    for (x, y) in cursor_region_cell_coordinates(cursor_position, cursor_radius) {
+        if cells[(x, y)].ty == CellType::Switch {
+            cells[(x, y)].lifetime ^= 1;
+            tracking.mark_dirty(x, y);
         }
     }
 }
 
+#[derive(Clone, Copy)]
+enum FieldBrush {
+    // against whatever gravity was when it was painted
+    AntiGravity,
+    // round the centre of the brush clockwise
+    Vortex,
+    Erase,
+}
+
+fn paint_field(
+    tracking: &mut Tracking,
+    field_brush: FieldBrush,
+    cursor_position: (usize, usize),
+    cursor_radius: f32,
+) {
+    let anti_gravity = (-tracking.gravity.0, -tracking.gravity.1);
+
+    for (x, y) in cursor_region_cell_coordinates(cursor_position, cursor_radius) {
+        let direction = match field_brush {
+            FieldBrush::AntiGravity => Some(anti_gravity),
+            FieldBrush::Vortex => {
+                // a quarter turn from the way out from the centre, snapped to the nearest straight direction
+                let (dx, dy) = rotate_clockwise((
+                    x as isize - cursor_position.0 as isize,
+                    y as isize - cursor_position.1 as isize,
+                ));
+
+                match (dx, dy) {
+                    (0, 0) => None,
+                    _ if dx.abs() >= dy.abs() => Some((dx.signum(), 0)),
+                    _ => Some((0, dy.signum())),
+                }
+            }
+            FieldBrush::Erase => None,
+        };
+
+        tracking.fields[y * WIDTH + x] =
+            direction.map_or(NO_FIELD, |direction| direction_index(direction) + 1);
+    }
+
+    // fields are drawn behind the cells
+    let radius = cursor_radius as usize;
+    tracking.mark_region_dirty(
+        (
+            cursor_position.0.saturating_sub(radius),
+            cursor_position.1.saturating_sub(radius),
+        ),
+        (cursor_position.0 + radius, cursor_position.1 + radius),
+    );
+}
+
 fn remove_cells(
-    cells: &mut [Vec<Cell>],
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
     cursor_position: (usize, usize),
     cursor_radius: f32,
     rng: &Rng,
 ) {
     for (x, y) in cursor_region_cell_coordinates(cursor_position, cursor_radius) {
-        cells[x][y] = Cell::from(CellType::Air, rng);
+        set_cell(cells, tracking, (x, y), Cell::from(CellType::Air, rng));
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum FrameMode {
+    // presenting waits for the display
+    Vsync,
+    // no vsync, frames are paced by sleep_until
+    Limited(u32),
+    // no vsync and no limit, as fast as the sim can go
+    Unlimited,
+}
+
+#[derive(Clone, Copy)]
+enum StressScene {
+    // the whole grid full of water, every cell tries to spread every tick
+    Water,
+    // alternating sand and water columns falling from the top, lots of swapping between unlike cells
+    Columns,
+    // rows of wood trees on fire from the bottom up, lots of smoke
+    Forest,
+}
+
+struct Options {
+    frame_mode: FrameMode,
+    seed: u64,
+    pause_on_unfocus: bool,
+    use_gpu: bool,
+    double_buffer: bool,
+    evaporation: f32,
+    criticality: f32,
+    // in ticks, 0 for always the same time of day
+    day_length: u64,
+    weather: Weather,
+    weather_intensity: f32,
+    stress_scene: Option<StressScene>,
+    terrain: Option<&'static Terrain>,
+    // the seed the terrain is generated from, the sim seed if not given
+    generation_seed: Option<u64>,
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        frame_mode: FrameMode::Vsync,
+        // everything random in the sim is derived from this so a run can be replayed by passing the same seed
+        seed: fastrand::u64(..),
+        pause_on_unfocus: false,
+        use_gpu: false,
+        double_buffer: false,
+        evaporation: EVAPORATION_CHANCE,
+        criticality: 1.0,
+        day_length: 0,
+        weather: Weather::Clear,
+        weather_intensity: WEATHER_INTENSITIES[1],
+        stress_scene: None,
+        terrain: None,
+        generation_seed: None,
+    };
+
+    let mut args = std::env::args().skip(1);
+    let mut positional_index = 0;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pause-on-unfocus" => options.pause_on_unfocus = true,
+            "--gpu" => options.use_gpu = true,
+            "--double-buffer" => options.double_buffer = true,
+            "--vsync" => options.frame_mode = FrameMode::Vsync,
+            "--unlimited" => options.frame_mode = FrameMode::Unlimited,
+            "--fps" => {
+                options.frame_mode = fps_frame_mode(&args.next().expect("--fps needs a value"))
+            }
+            "--seed" => options.seed = args.next().expect("--seed needs a value").parse().unwrap(),
+            "--evaporation" => {
+                options.evaporation = args
+                    .next()
+                    .expect("--evaporation needs a value")
+                    .parse()
+                    .unwrap()
+            }
+            "--criticality" => {
+                options.criticality = args
+                    .next()
+                    .expect("--criticality needs a value")
+                    .parse()
+                    .unwrap()
+            }
+            // in seconds at 60 ticks a second
+            "--day-length" => {
+                let seconds: f32 = args
+                    .next()
+                    .expect("--day-length needs a value")
+                    .parse()
+                    .unwrap();
+                options.day_length = (seconds * 60.0) as u64;
+            }
+            "--weather" => {
+                options.weather = match args.next().expect("--weather needs a value").as_str() {
+                    "clear" => Weather::Clear,
+                    "rain" => Weather::Rain,
+                    "snow" => Weather::Snow,
+                    weather => panic!("unknown weather {weather}, expected clear, rain or snow"),
+                }
+            }
+            "--weather-intensity" => {
+                options.weather_intensity = args
+                    .next()
+                    .expect("--weather-intensity needs a value")
+                    .parse()
+                    .unwrap()
+            }
+            "--stress" => {
+                options.stress_scene = Some(
+                    match args.next().expect("--stress needs a scene").as_str() {
+                        "water" => StressScene::Water,
+                        "columns" => StressScene::Columns,
+                        "forest" => StressScene::Forest,
+                        scene => panic!(
+                            "unknown stress scene {scene}, expected water, columns or forest"
+                        ),
+                    },
+                )
+            }
+            "--gen" => {
+                options.terrain = Some(match args.next().expect("--gen needs a preset").as_str() {
+                    "hills" => &HILLS,
+                    "plains" => &PLAINS,
+                    "dunes" => &DUNES,
+                    preset => panic!("unknown preset {preset}, expected hills, plains or dunes"),
+                })
+            }
+            "--gen-seed" => {
+                options.generation_seed = Some(
+                    args.next()
+                        .expect("--gen-seed needs a value")
+                        .parse()
+                        .unwrap(),
+                )
+            }
+            _ if arg.starts_with("--") => panic!("unknown option {arg}"),
+            // [fps] [seed] from before there were named options
+            _ => {
+                match positional_index {
+                    0 => options.frame_mode = fps_frame_mode(&arg),
+                    1 => options.seed = arg.parse().unwrap(),
+                    _ => panic!("unexpected argument {arg}"),
+                }
+
+                positional_index += 1;
+            }
+        }
+    }
+
+    options
+}
+
+fn fps_frame_mode(fps: &str) -> FrameMode {
+    match fps.parse::<u32>().unwrap() {
+        0 => FrameMode::Vsync,
+        fps => FrameMode::Limited(fps),
+    }
+}
+
+// os sleeps can overshoot by a millisecond or two, so sleep most of the way then spin for the rest
+fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+
+    if deadline > now + SPIN_MARGIN {
+        std::thread::sleep(deadline - now - SPIN_MARGIN);
+    }
+
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
+
+fn fill_stress_scene(cells: &mut Grid, tracking: &Tracking, scene: StressScene, rng: &Rng) {
+    let mut cells = cells.window();
+    let tracking = tracking.window(0);
+
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let cell_type = match scene {
+                StressScene::Water => CellType::Water,
+                StressScene::Columns if y < HEIGHT / 2 => {
+                    if x % 2 == 0 {
+                        CellType::Sand
+                    } else {
+                        CellType::Water
+                    }
+                }
+                StressScene::Columns => CellType::Air,
+                // trunks every 4 columns with a branch every 6 rows, lit along the bottom
+                StressScene::Forest if y == HEIGHT - 1 => CellType::Fire,
+                StressScene::Forest if y > HEIGHT / 3 && (x % 4 == 0 || y % 6 == 0) => {
+                    CellType::Wood
+                }
+                StressScene::Forest => CellType::Air,
+            };
+
+            set_cell(&mut cells, &tracking, (x, y), Cell::from(cell_type, rng));
+        }
+    }
+}
+
+// headless, fills the grid with a worst case scene and times the update so optimisations can be compared
+fn run_stress(scene: StressScene, seed: u64, double_buffer: bool) {
+    let rng = Rng::with_seed(seed);
+    let mut cells = Grid::new(Cell::from(CellType::Air, &rng));
+    let mut back_cells = Grid::new(Cell::from(CellType::Air, &rng));
+    let mut tracking = Tracking::new();
+    let mut profiler = Profiler::new();
+
+    fill_stress_scene(&mut cells, &tracking, scene, &rng);
+
+    let mut total = Duration::ZERO;
+    let mut slowest = Duration::ZERO;
+
+    for tick in 0..STRESS_TICKS {
+        let tick_start = Instant::now();
+        if double_buffer {
+            double_buffer::step(&cells, &mut back_cells, &tracking, seed, tick);
+            std::mem::swap(&mut cells, &mut back_cells);
+        } else {
+            update_cells(
+                &mut cells,
+                &mut tracking,
+                &mut profiler,
+                Degradation::None,
+                seed,
+                tick,
+            );
+        }
+        let tick_time = tick_start.elapsed();
+
+        total += tick_time;
+        slowest = slowest.max(tick_time);
     }
+
+    println!(
+        "{STRESS_TICKS} ticks, {:.3} ms average, {:.3} ms slowest (seed {seed})",
+        total.as_secs_f64() * 1000.0 / STRESS_TICKS as f64,
+        slowest.as_secs_f64() * 1000.0
+    );
 }
 
 fn main() {
+    let options = parse_options();
+
+    if let Some(scene) = options.stress_scene {
+        run_stress(scene, options.seed, options.double_buffer);
+        return;
+    }
+
     let event_loop = EventLoop::new();
 
     let window = {
         let size = LogicalSize::new(WIDTH as f64, HEIGHT as f64);
-        let scaled_size = LogicalSize::new(WIDTH as f64 * 2.0, HEIGHT as f64 * 2.0);
+        let scaled_size =
+            LogicalSize::new(WIDTH as f64 * WINDOW_SCALE, HEIGHT as f64 * WINDOW_SCALE);
         WindowBuilder::new()
             .with_title("Sand Sim")
             .with_inner_size(scaled_size)
@@ -825,130 +5944,763 @@ fn main() {
             .unwrap()
     };
 
+    let Options {
+        frame_mode,
+        seed,
+        pause_on_unfocus,
+        use_gpu,
+        double_buffer,
+        ..
+    } = options;
+
     let mut pixels = {
         let window_size = window.inner_size();
         let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-        Pixels::new(WIDTH as u32, HEIGHT as u32, surface_texture).unwrap()
+        PixelsBuilder::new(WIDTH as u32, HEIGHT as u32, surface_texture)
+            .enable_vsync(frame_mode == FrameMode::Vsync)
+            .build()
+            .unwrap()
     };
 
-    let rng = Rng::new();
+    let rng = Rng::with_seed(seed);
 
-    let mut cells = vec![vec![Cell::from(CellType::Air, &rng); HEIGHT]; WIDTH];
+    // the cpu sim stays the reference implementation, the gpu one is opt in
+    let gpu_sim = use_gpu.then(|| GpuSim::new(&pixels, WIDTH as u32, HEIGHT as u32, seed));
+    let mut overlay = Overlay::new(&pixels);
+
+    let mut cells = Grid::new(Cell::from(CellType::Air, &rng));
+    // only used with --double-buffer, the next tick is written here then the two are swapped
+    let mut back_cells = Grid::new(Cell::from(CellType::Air, &rng));
+    let mut tracking = Tracking::new();
+    tracking.evaporation = options.evaporation;
+    tracking.criticality = options.criticality;
+    tracking.day_length = options.day_length;
+    tracking.weather = options.weather;
+    tracking.weather_intensity = options.weather_intensity;
+    let mut profiler = Profiler::new();
+    let mut governor = Governor::new();
     let mut cursor_radius = 3_f32;
     let mut cursor_position = (WIDTH / 2, HEIGHT / 2);
+    // cpu only, the gpu always draws the whole grid
+    let mut camera = Camera::new();
+    // the rest of the world beyond the grid, cpu only
+    let mut world = World::new(options.terrain, options.generation_seed.unwrap_or(seed));
+    if options.terrain.is_some() {
+        world.fill(&mut cells, &mut tracking);
+    }
+    // how far the camera was panned past the edges of the grid, the grid follows it once no tick is half done
+    let mut pan_past_grid = (0, 0);
+    // where the brush was last frame, for throwing
+    let mut last_cursor_cell = None;
     let mut lmb_down = false;
     let mut rmb_down = false;
+    // cpu only, the gpu has no explosions
+    let mut detonate = false;
+    // calls lightning down over the cursor at the next frame, cpu only
+    let mut lightning = false;
+    // flips the switches under the cursor at the next frame
+    let mut toggle = false;
+    // drops a rigid body under the cursor at the next frame, cpu only
+    let mut spawn = None;
     let mut current_cell_type = CellType::Sand;
-
-    let max_fps = if let Some(fps) = std::env::args().nth(1) {
-        fps.parse::<u32>().unwrap()
-    } else {
-        // unlimited
-        0
-    };
-
-    let time_per_frame_micros = (1_000_000.0 / max_fps as f32) as u64;
+    // lmb paints this instead of cells while it is picked, cpu only
+    let mut field_brush = None;
+    // the way new fans, gates and lasers face, which gate is put down and the way new mirrors lean
+    let mut facing = (0, -1);
+    let mut gate = Gate::And;
+    let mut mirror = Mirror::Rising;
+    // what new spouts pour, new timers and delays put out and new sensors look for, the element picked before them
+    let mut spout_element = CellType::Water;
+    // the end of a pair of portals drawn next, see portal::portal_end
+    let mut portal = 0u8;
+    // whether new conveyors run against across gravity, to the left when it points down
+    let mut conveyor_reversed = false;
+    let mut detonator_radius = DETONATOR_RADII[0];
+    let mut timer_period = TIMER_PERIODS[2];
+    // whether new sensors are set off by any powder, liquid or rigid body instead of spout_element
+    let mut sensor_any = false;
+    let mut tick = 0_u64;
+    let mut tick_progress = None;
+    let mut frame = 0_u64;
+    let mut paused = false;
+    let mut focused = true;
 
     let mut last_redraw = Instant::now();
+    let mut next_frame_deadline = Instant::now();
+    let mut smoothed_frame_time = 0_f32;
+    let mut last_title_update = Instant::now();
 
     event_loop.run(move |event, _, control_flow| match event {
-        Event::WindowEvent { ref event, .. } => match event {
-            WindowEvent::CloseRequested => control_flow.set_exit(),
-            WindowEvent::MouseInput { button, state, .. } => match button {
-                MouseButton::Left => lmb_down = *state == ElementState::Pressed,
-                MouseButton::Right => rmb_down = *state == ElementState::Pressed,
-                _ => (),
-            },
-            WindowEvent::MouseWheel {
-                delta: MouseScrollDelta::LineDelta(_, dy),
-                ..
-            } => {
-                let cursor_radius_step = 3.0;
-
-                if *dy != 0.0 {
-                    cursor_radius += dy * cursor_radius_step;
-                    cursor_radius = cursor_radius_step.max(cursor_radius);
+        Event::WindowEvent { ref event, .. } => {
+            match event {
+                WindowEvent::CloseRequested => control_flow.set_exit(),
+                WindowEvent::Focused(is_focused) => focused = *is_focused,
+                WindowEvent::MouseInput { button, state, .. } => match button {
+                    MouseButton::Left => {
+                        lmb_down = *state == ElementState::Pressed;
+                        // each stroke of portals is one end of a pair, the next stroke is the other end
+                        if !lmb_down
+                            && current_cell_type == CellType::Portal
+                            && field_brush.is_none()
+                        {
+                            portal = portal.wrapping_add(1);
+                        }
+                    }
+                    MouseButton::Right => rmb_down = *state == ElementState::Pressed,
+                    // once per click, the blast goes off next frame
+                    MouseButton::Middle if *state == ElementState::Pressed => detonate = true,
+                    _ => (),
+                },
+                WindowEvent::MouseWheel {
+                    delta: MouseScrollDelta::LineDelta(_, dy),
+                    ..
+                } => {
+                    let cursor_radius_step = 3.0;
+
+                    if *dy != 0.0 {
+                        cursor_radius += dy * cursor_radius_step;
+                        cursor_radius = cursor_radius_step.max(cursor_radius);
+                    }
                 }
-            }
-            WindowEvent::CursorMoved { position, .. } => {
-                cursor_position = pixels
-                    .window_pos_to_pixel((position.x as f32, position.y as f32))
-                    .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
-            }
-            WindowEvent::KeyboardInput {
-                input:
-                    KeyboardInput {
-                        virtual_keycode: Some(virtual_keycode),
-                        state: ElementState::Pressed,
-                        ..
-                    },
-                ..
-            } => match virtual_keycode {
-                VirtualKeyCode::Escape => control_flow.set_exit(),
-                VirtualKeyCode::Key1 => current_cell_type = CellType::Sand,
-                VirtualKeyCode::Key2 => current_cell_type = CellType::Water,
-                VirtualKeyCode::Key3 => current_cell_type = CellType::Wood,
-                VirtualKeyCode::Key4 => current_cell_type = CellType::Fire,
-                VirtualKeyCode::Key5 => current_cell_type = CellType::Smoke,
-                VirtualKeyCode::Key6 => current_cell_type = CellType::Steam,
+                WindowEvent::CursorMoved { position, .. } => {
+                    cursor_position = pixels
+                        .window_pos_to_pixel((position.x as f32, position.y as f32))
+                        .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
+                }
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            virtual_keycode: Some(virtual_keycode),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                    ..
+                } => match virtual_keycode {
+                    VirtualKeyCode::Escape => control_flow.set_exit(),
+                    VirtualKeyCode::Space => paused = !paused,
+                    VirtualKeyCode::F3 => profiler.enabled = !profiler.enabled,
+                    VirtualKeyCode::Key1 => {
+                        (current_cell_type, field_brush) = (CellType::Sand, None)
+                    }
+                    // picks water, then ice
+                    VirtualKeyCode::Key2 => {
+                        let water = if current_cell_type == CellType::Water && field_brush.is_none()
+                        {
+                            CellType::Ice
+                        } else {
+                            CellType::Water
+                        };
+                        (current_cell_type, field_brush) = (water, None);
+                    }
+                    VirtualKeyCode::Key3 => {
+                        (current_cell_type, field_brush) = (CellType::Wood, None)
+                    }
+                    VirtualKeyCode::Key4 => {
+                        (current_cell_type, field_brush) = (CellType::Fire, None)
+                    }
+                    // picks smoke, then goes through hydrogen, toxic gas and methane
+                    VirtualKeyCode::Key5 => {
+                        let index = GASES
+                            .iter()
+                            .position(|&gas| gas == current_cell_type)
+                            .filter(|_| field_brush.is_none())
+                            .map_or(0, |index| (index + 1) % GASES.len());
+                        (current_cell_type, field_brush) = (GASES[index], None);
+                    }
+                    VirtualKeyCode::Key6 => {
+                        (current_cell_type, field_brush) = (CellType::Steam, None)
+                    }
+                    VirtualKeyCode::Key7 => {
+                        (current_cell_type, field_brush) = (CellType::Tnt, None)
+                    }
+                    VirtualKeyCode::Key8 => field_brush = Some(FieldBrush::AntiGravity),
+                    VirtualKeyCode::Key9 => field_brush = Some(FieldBrush::Vortex),
+                    VirtualKeyCode::Key0 => field_brush = Some(FieldBrush::Erase),
+                    VirtualKeyCode::LBracket => {
+                        tracking.wind = (tracking.wind - WIND_STEP).max(-MAX_WIND)
+                    }
+                    VirtualKeyCode::RBracket => {
+                        tracking.wind = (tracking.wind + WIND_STEP).min(MAX_WIND)
+                    }
+                    // picks fans, then turns the way new ones face
+                    VirtualKeyCode::F => {
+                        if current_cell_type == CellType::Fan && field_brush.is_none() {
+                            facing = rotate_clockwise(facing);
+                        }
+                        (current_cell_type, field_brush) = (CellType::Fan, None);
+                    }
+                    // picks gates, then goes through and, or and not
+                    VirtualKeyCode::N => {
+                        if current_cell_type == CellType::Gate && field_brush.is_none() {
+                            gate = next_cycle(&gate).unwrap();
+                        }
+                        (current_cell_type, field_brush) = (CellType::Gate, None);
+                    }
+                    // picks spouts pouring whatever was picked before, then turns the way new ones pour
+                    VirtualKeyCode::Comma => {
+                        if current_cell_type == CellType::Spout && field_brush.is_none() {
+                            facing = rotate_clockwise(facing);
+                        } else if field_brush.is_none() && !keeps_element(current_cell_type) {
+                            spout_element = current_cell_type;
+                        }
+                        (current_cell_type, field_brush) = (CellType::Spout, None);
+                    }
+                    // picks timers putting out whatever was picked before, charge after batteries, then goes through
+                    // how often new ones go off
+                    VirtualKeyCode::F1 => {
+                        if current_cell_type == CellType::Timer && field_brush.is_none() {
+                            timer_period = next_period(timer_period);
+                        } else if field_brush.is_none() && !keeps_element(current_cell_type) {
+                            spout_element = current_cell_type;
+                        }
+                        (current_cell_type, field_brush) = (CellType::Timer, None);
+                    }
+                    // picks delays the same way, then goes through how long new ones wait
+                    VirtualKeyCode::F2 => {
+                        if current_cell_type == CellType::Delay && field_brush.is_none() {
+                            timer_period = next_period(timer_period);
+                        } else if field_brush.is_none() && !keeps_element(current_cell_type) {
+                            spout_element = current_cell_type;
+                        }
+                        (current_cell_type, field_brush) = (CellType::Delay, None);
+                    }
+                    // picks sensors looking for whatever was picked before, then toggles to anything loose
+                    VirtualKeyCode::F4 => {
+                        if current_cell_type == CellType::Sensor && field_brush.is_none() {
+                            sensor_any = !sensor_any;
+                        } else if field_brush.is_none() && !keeps_element(current_cell_type) {
+                            spout_element = current_cell_type;
+                        }
+                        (current_cell_type, field_brush) = (CellType::Sensor, None);
+                    }
+                    VirtualKeyCode::Period => {
+                        (current_cell_type, field_brush) = (CellType::Drain, None)
+                    }
+                    VirtualKeyCode::Semicolon => {
+                        (current_cell_type, field_brush) = (CellType::Clone, None)
+                    }
+                    // picks voids, then antimatter
+                    VirtualKeyCode::Apostrophe => {
+                        let void = if current_cell_type == CellType::Void && field_brush.is_none() {
+                            CellType::Antimatter
+                        } else {
+                            CellType::Void
+                        };
+                        (current_cell_type, field_brush) = (void, None);
+                    }
+                    // picks ants, then toggles to fish
+                    VirtualKeyCode::F5 => {
+                        let creature =
+                            if current_cell_type == CellType::Ant && field_brush.is_none() {
+                                CellType::Fish
+                            } else {
+                                CellType::Ant
+                            };
+                        (current_cell_type, field_brush) = (creature, None);
+                    }
+                    VirtualKeyCode::F6 => {
+                        (current_cell_type, field_brush) = (CellType::Virus, None)
+                    }
+                    // goes through clear, rain and snow
+                    VirtualKeyCode::F7 => tracking.weather = next_cycle(&tracking.weather).unwrap(),
+                    VirtualKeyCode::F9 => lightning = true,
+                    // goes through how heavily it comes down, back to a drizzle after a downpour
+                    VirtualKeyCode::F8 => {
+                        let index = WEATHER_INTENSITIES
+                            .iter()
+                            .position(|&intensity| intensity == tracking.weather_intensity)
+                            .map_or(0, |index| (index + 1) % WEATHER_INTENSITIES.len());
+                        tracking.weather_intensity = WEATHER_INTENSITIES[index];
+                    }
+                    // picks heaters, then toggles to coolers
+                    VirtualKeyCode::Grave => {
+                        let temperature =
+                            if current_cell_type == CellType::Heater && field_brush.is_none() {
+                                CellType::Cooler
+                            } else {
+                                CellType::Heater
+                            };
+                        (current_cell_type, field_brush) = (temperature, None);
+                    }
+                    // picks detonators, then goes through how big new ones blow up
+                    VirtualKeyCode::Tab => {
+                        if current_cell_type == CellType::Detonator && field_brush.is_none() {
+                            let index = DETONATOR_RADII
+                                .iter()
+                                .position(|&radius| radius == detonator_radius)
+                                .unwrap();
+                            detonator_radius = DETONATOR_RADII[(index + 1) % DETONATOR_RADII.len()];
+                        }
+                        (current_cell_type, field_brush) = (CellType::Detonator, None);
+                    }
+                    VirtualKeyCode::Slash => {
+                        (current_cell_type, field_brush) = (CellType::Portal, None)
+                    }
+                    // picks conveyors, then flips the way new ones run
+                    VirtualKeyCode::Backslash => {
+                        if current_cell_type == CellType::Conveyor && field_brush.is_none() {
+                            conveyor_reversed = !conveyor_reversed;
+                        }
+                        (current_cell_type, field_brush) = (CellType::Conveyor, None);
+                    }
+                    VirtualKeyCode::R => facing = rotate_clockwise(facing),
+                    VirtualKeyCode::O => {
+                        (current_cell_type, field_brush) = (CellType::Switch, None)
+                    }
+                    VirtualKeyCode::P => (current_cell_type, field_brush) = (CellType::Plate, None),
+                    VirtualKeyCode::L => (current_cell_type, field_brush) = (CellType::Lamp, None),
+                    VirtualKeyCode::T => toggle = true,
+                    VirtualKeyCode::E => (current_cell_type, field_brush) = (CellType::Laser, None),
+                    // picks mirrors, then flips the way new ones lean
+                    VirtualKeyCode::M => {
+                        if current_cell_type == CellType::Mirror && field_brush.is_none() {
+                            mirror = next_cycle(&mirror).unwrap();
+                        }
+                        (current_cell_type, field_brush) = (CellType::Mirror, None);
+                    }
+                    VirtualKeyCode::C => (current_cell_type, field_brush) = (CellType::Glass, None),
+                    // picks seeds, then goes through vines, moss and fungus
+                    VirtualKeyCode::D => {
+                        let index = GROWTHS
+                            .iter()
+                            .position(|&growth| growth == current_cell_type)
+                            .filter(|_| field_brush.is_none())
+                            .map_or(0, |index| (index + 1) % GROWTHS.len());
+                        (current_cell_type, field_brush) = (GROWTHS[index], None);
+                    }
+                    // picks ash, then confetti
+                    VirtualKeyCode::H => {
+                        let ash = if current_cell_type == CellType::Ash && field_brush.is_none() {
+                            CellType::Confetti
+                        } else {
+                            CellType::Ash
+                        };
+                        (current_cell_type, field_brush) = (ash, None);
+                    }
+                    // picks sponge, then goes through soap and foam
+                    VirtualKeyCode::K => {
+                        let index = BATH
+                            .iter()
+                            .position(|&bath| bath == current_cell_type)
+                            .filter(|_| field_brush.is_none())
+                            .map_or(0, |index| (index + 1) % BATH.len());
+                        (current_cell_type, field_brush) = (BATH[index], None);
+                    }
+                    // picks goo, then slime
+                    VirtualKeyCode::J => {
+                        let goo = if current_cell_type == CellType::Goo && field_brush.is_none() {
+                            CellType::Slime
+                        } else {
+                            CellType::Goo
+                        };
+                        (current_cell_type, field_brush) = (goo, None);
+                    }
+                    // picks stone, then goes through lava and obsidian
+                    VirtualKeyCode::Q => {
+                        let index = ROCKS
+                            .iter()
+                            .position(|&rock| rock == current_cell_type)
+                            .filter(|_| field_brush.is_none())
+                            .map_or(0, |index| (index + 1) % ROCKS.len());
+                        (current_cell_type, field_brush) = (ROCKS[index], None);
+                    }
+                    // picks dirt, then mud
+                    VirtualKeyCode::V => {
+                        let soil = if current_cell_type == CellType::Dirt && field_brush.is_none() {
+                            CellType::Mud
+                        } else {
+                            CellType::Dirt
+                        };
+                        (current_cell_type, field_brush) = (soil, None);
+                    }
+                    // picks coal, then gold
+                    VirtualKeyCode::I => {
+                        let ore = if current_cell_type == CellType::Coal && field_brush.is_none() {
+                            CellType::Gold
+                        } else {
+                            CellType::Coal
+                        };
+                        (current_cell_type, field_brush) = (ore, None);
+                    }
+                    // picks uranium, then slag
+                    VirtualKeyCode::U => {
+                        let uranium =
+                            if current_cell_type == CellType::Uranium && field_brush.is_none() {
+                                CellType::Slag
+                            } else {
+                                CellType::Uranium
+                            };
+                        (current_cell_type, field_brush) = (uranium, None);
+                    }
+                    VirtualKeyCode::Y => {
+                        (current_cell_type, field_brush) = (CellType::Plutonium, None)
+                    }
+                    VirtualKeyCode::A => (current_cell_type, field_brush) = (CellType::Acid, None),
+                    // picks oil, then goes through nitro and honey
+                    VirtualKeyCode::F10 => {
+                        let index = LIQUIDS
+                            .iter()
+                            .position(|&liquid| liquid == current_cell_type)
+                            .filter(|_| field_brush.is_none())
+                            .map_or(0, |index| (index + 1) % LIQUIDS.len());
+                        (current_cell_type, field_brush) = (LIQUIDS[index], None);
+                    }
+                    // picks gunpowder, then fuse
+                    VirtualKeyCode::F11 => {
+                        let fuse =
+                            if current_cell_type == CellType::Gunpowder && field_brush.is_none() {
+                                CellType::Fuse
+                            } else {
+                                CellType::Gunpowder
+                            };
+                        (current_cell_type, field_brush) = (fuse, None);
+                    }
+                    // picks metal, then molten metal
+                    VirtualKeyCode::F12 => {
+                        let metal = if current_cell_type == CellType::Metal && field_brush.is_none()
+                        {
+                            CellType::MoltenMetal
+                        } else {
+                            CellType::Metal
+                        };
+                        (current_cell_type, field_brush) = (metal, None);
+                    }
+                    VirtualKeyCode::S => (current_cell_type, field_brush) = (CellType::Salt, None),
+                    VirtualKeyCode::W => (current_cell_type, field_brush) = (CellType::Wire, None),
+                    VirtualKeyCode::B => {
+                        (current_cell_type, field_brush) = (CellType::Battery, None)
+                    }
+                    VirtualKeyCode::X => spawn = Some(BodyShape::Crate),
+                    VirtualKeyCode::Z => spawn = Some(BodyShape::Ball),
+                    VirtualKeyCode::G => tracking.gravity = rotate_clockwise(tracking.gravity),
+                    VirtualKeyCode::Equals | VirtualKeyCode::Plus => {
+                        camera.zoom_at(cursor_position, true)
+                    }
+                    VirtualKeyCode::Minus => camera.zoom_at(cursor_position, false),
+                    VirtualKeyCode::Left
+                    | VirtualKeyCode::Right
+                    | VirtualKeyCode::Up
+                    | VirtualKeyCode::Down => {
+                        let direction = match virtual_keycode {
+                            VirtualKeyCode::Left => (-1, 0),
+                            VirtualKeyCode::Right => (1, 0),
+                            VirtualKeyCode::Up => (0, -1),
+                            _ => (0, 1),
+                        };
+                        let past = camera.pan(direction);
+
+                        if gpu_sim.is_none() {
+                            pan_past_grid = (pan_past_grid.0 + past.0, pan_past_grid.1 + past.1);
+                        }
+                    }
+                    _ => (),
+                },
                 _ => (),
-            },
-            _ => (),
-        },
-        Event::MainEventsCleared => window.request_redraw(),
+            }
+
+            // nothing is redrawing on its own while paused so anything that could change the picture has to ask
+            if paused || (pause_on_unfocus && !focused) {
+                window.request_redraw();
+            }
+        }
+        Event::MainEventsCleared => {
+            if paused || (pause_on_unfocus && !focused) {
+                // sleep until the next window event instead of spinning
+                control_flow.set_wait();
+            } else {
+                control_flow.set_poll();
+                window.request_redraw();
+            }
+        }
         Event::RedrawRequested(_) => {
-            let delta_micros = last_redraw.elapsed().as_micros() as u64;
+            if paused || (pause_on_unfocus && !focused) {
+                let render_result = if let Some(gpu_sim) = &gpu_sim {
+                    draw_overlay(
+                        overlay.frame_mut(),
+                        None,
+                        &camera,
+                        field_brush.is_none().then_some(current_cell_type),
+                        cursor_position,
+                        cursor_radius,
+                        true,
+                    );
 
-            if delta_micros > time_per_frame_micros || max_fps == 0 {
-                // tick the simulation
-                if lmb_down {
-                    put_cell(
-                        &mut cells,
-                        current_cell_type,
+                    gpu_sim.render(
+                        &pixels,
+                        &overlay,
+                        tick,
+                        true,
+                        None,
                         cursor_position,
                         cursor_radius,
+                    )
+                } else {
+                    draw_frame(pixels.frame_mut(), &cells, &tracking, &camera);
+                    draw_overlay(
+                        overlay.frame_mut(),
+                        Some(&tracking),
+                        &camera,
+                        field_brush.is_none().then_some(current_cell_type),
+                        cursor_position,
+                        cursor_radius * camera.zoom as f32,
+                        true,
+                    );
+
+                    overlay::render(&pixels, &overlay)
+                };
+
+                if let Err(error) = render_result {
+                    eprintln!("{error}");
+                    control_flow.set_exit();
+                }
+
+                window.set_title("Sand Sim: paused");
+
+                // dont count the time spent paused as one giant frame
+                last_redraw = Instant::now();
+                return;
+            }
+
+            let delta = last_redraw.elapsed();
+            last_redraw = Instant::now();
+
+            // tick the simulation
+            let render_result = if let Some(gpu_sim) = &gpu_sim {
+                let brush = if rmb_down {
+                    Some(Brush::Erase)
+                } else if lmb_down && field_brush.is_none() {
+                    Some(Brush::Put(current_cell_type))
+                } else {
+                    None
+                };
+
+                let draw_start = Instant::now();
+                draw_overlay(
+                    overlay.frame_mut(),
+                    None,
+                    &camera,
+                    field_brush.is_none().then_some(current_cell_type),
+                    cursor_position,
+                    cursor_radius,
+                    false,
+                );
+                if profiler.enabled {
+                    profiler.draw(overlay.frame_mut());
+                }
+                profiler.record(Scope::Draw, draw_start.elapsed());
+
+                // the sim itself runs inside the render on the gpu, theres no separate update to time
+                let render_start = Instant::now();
+                let render_result = gpu_sim.render(
+                    &pixels,
+                    &overlay,
+                    tick,
+                    false,
+                    brush,
+                    cursor_position,
+                    cursor_radius,
+                );
+                profiler.record(Scope::Render, render_start.elapsed());
+                tick += 1;
+
+                render_result
+            } else {
+                if pan_past_grid != (0, 0) && tick_progress.is_none() {
+                    let shift = world.follow(&mut cells, &mut tracking, pan_past_grid, &rng);
+                    // the grid moved whole chunks, the camera makes up the difference
+                    camera.move_by((pan_past_grid.0 - shift.0, pan_past_grid.1 - shift.1));
+                    pan_past_grid = (0, 0);
+                    // a drag across the move isnt a throw
+                    last_cursor_cell = None;
+                }
+
+                // the cursor is in frame pixels, the brush works on the cell under it
+                let cursor_cell = camera.cell_at(cursor_position);
+                // dragging the brush throws what it puts down in the direction it moved, cells only fly across gravity
+                let across = across(tracking.gravity);
+                let throw = last_cursor_cell.map_or(0.0, |(last_x, last_y)| {
+                    (cursor_cell.0 as f32 - last_x as f32) * across.0 as f32
+                        + (cursor_cell.1 as f32 - last_y as f32) * across.1 as f32
+                });
+                last_cursor_cell = Some(cursor_cell);
+
+                if lmb_down {
+                    if let Some(field_brush) = field_brush {
+                        paint_field(&mut tracking, field_brush, cursor_cell, cursor_radius);
+                    } else {
+                        put_cell(
+                            &mut cells.window(),
+                            &tracking.window(0),
+                            current_cell_type,
+                            cursor_cell,
+                            cursor_radius,
+                            throw,
+                            facing,
+                            gate,
+                            mirror,
+                            spout_element,
+                            portal,
+                            conveyor_reversed,
+                            detonator_radius,
+                            timer_period,
+                            sensor_any,
+                            &rng,
+                        );
+                    }
+                }
+
+                if detonate {
+                    explode(
+                        &mut cells.window(),
+                        &tracking.window(0),
+                        cursor_cell,
+                        (cursor_radius as usize).max(1),
+                        TNT_POWER,
                         &rng,
                     );
+                    detonate = false;
+                }
+
+                if lightning {
+                    tracking.strike = Some(cursor_cell);
+                    lightning = false;
+                }
+
+                if toggle {
+                    toggle_switches(
+                        &mut cells.window(),
+                        &tracking.window(0),
+                        cursor_cell,
+                        cursor_radius,
+                    );
+                    toggle = false;
+                }
+
+                if let Some(shape) = spawn.take() {
+                    rigid::spawn(&mut tracking, shape, cursor_cell, cursor_radius.max(2.0));
                 }
 
                 if rmb_down {
-                    remove_cells(&mut cells, cursor_position, cursor_radius, &rng)
+                    rigid::remove(&mut cells, &mut tracking, cursor_cell, cursor_radius, &rng);
+                    remove_cells(
+                        &mut cells.window(),
+                        &tracking.window(0),
+                        cursor_cell,
+                        cursor_radius,
+                        &rng,
+                    )
                 }
 
-                update_cells(&mut cells, &rng);
+                if double_buffer {
+                    if governor.level < Degradation::HalfRate || frame.is_multiple_of(2) {
+                        world.overflow(&mut cells, &tracking, &rng);
+                        let step_start = Instant::now();
+                        double_buffer::step(&cells, &mut back_cells, &tracking, seed, tick);
+                        std::mem::swap(&mut cells, &mut back_cells);
+                        profiler.record(Scope::BlockStep, step_start.elapsed());
 
-                draw_frame(
-                    &mut pixels,
-                    &cells,
-                    current_cell_type,
+                        governor.record(step_start.elapsed());
+                        tick += 1;
+                    }
+                }
+                // the last resort under load, only start a tick every other frame but keep drawing every frame
+                else if tick_progress.is_none()
+                    && (governor.level < Degradation::HalfRate || frame.is_multiple_of(2))
+                {
+                    world.overflow(&mut cells, &tracking, &rng);
+                    tick_progress = Some(start_tick(
+                        &mut cells,
+                        &mut tracking,
+                        &mut profiler,
+                        governor.level,
+                        seed,
+                        tick,
+                    ));
+                }
+
+                // a tick too slow for one frame is finished off over the next few, drawing whatever state it got to
+                if let Some(progress) = &mut tick_progress {
+                    if continue_tick(
+                        &mut cells,
+                        &tracking,
+                        &mut profiler,
+                        progress,
+                        seed,
+                        Some(TICK_FRAME_BUDGET),
+                    ) {
+                        governor.record(progress.elapsed);
+                        tick_progress = None;
+                        tick += 1;
+                    }
+                }
+
+                let draw_start = Instant::now();
+                draw_frame(pixels.frame_mut(), &cells, &tracking, &camera);
+                // the brush radius is in cells
+                draw_overlay(
+                    overlay.frame_mut(),
+                    Some(&tracking),
+                    &camera,
+                    field_brush.is_none().then_some(current_cell_type),
                     cursor_position,
-                    cursor_radius,
+                    cursor_radius * camera.zoom as f32,
+                    false,
                 );
-
-                if let Err(error) = pixels.render() {
-                    eprintln!("{error}");
-                    control_flow.set_exit();
+                if profiler.enabled {
+                    profiler.draw(overlay.frame_mut());
                 }
+                governor.draw(overlay.frame_mut());
+                draw_wind_indicator(overlay.frame_mut(), tracking.wind);
+                profiler.record(Scope::Draw, draw_start.elapsed());
+
+                let render_start = Instant::now();
+                let render_result = overlay::render(&pixels, &overlay);
+                profiler.record(Scope::Render, render_start.elapsed());
+
+                render_result
+            };
+
+            frame += 1;
+            profiler.end_frame();
+
+            if let Err(error) = render_result {
+                eprintln!("{error}");
+                control_flow.set_exit();
+            }
 
-                let delta_millis = delta_micros as f32 / 1000.0;
+            // exponential moving average so the readout doesnt jitter every frame
+            let delta_millis = delta.as_secs_f32() * 1000.0;
+            smoothed_frame_time = if smoothed_frame_time == 0.0 {
+                delta_millis
+            } else {
+                smoothed_frame_time * (1.0 - FRAME_TIME_SMOOTHING)
+                    + delta_millis * FRAME_TIME_SMOOTHING
+            };
+
+            if last_title_update.elapsed() > TITLE_UPDATE_INTERVAL {
                 window.set_title(
                     format!(
                         "Sand Sim: {:.2} FPS, {:.2} ms per frame",
-                        1000.0 / delta_millis,
-                        delta_millis
+                        1000.0 / smoothed_frame_time,
+                        smoothed_frame_time
                     )
                     .as_str(),
                 );
+                last_title_update = Instant::now();
+            }
 
-                last_redraw = Instant::now();
-            } else {
-                let deadline = last_redraw
-                    .checked_add(Duration::from_micros(time_per_frame_micros))
-                    .unwrap();
+            if let FrameMode::Limited(fps) = frame_mode {
+                next_frame_deadline += Duration::from_secs_f64(1.0 / fps as f64);
+
+                // fell behind, dont try to catch up by rushing the next few frames
+                if next_frame_deadline < Instant::now() {
+                    next_frame_deadline = Instant::now();
+                }
 
-                control_flow.set_wait_until(deadline);
+                sleep_until(next_frame_deadline);
             }
         }
+        Event::LoopDestroyed => world.remove_files(),
         _ => (),
     });
 }
@@ -0,0 +1,118 @@
+use enum_iterator::all;
+use fastrand::Rng;
+
+use crate::{
+    grid::GridWindow, set_cell, Cell, CellType, TrackingWindow, DIRECTIONS, NEIGHBOURS,
+    WATER_BOILING_TEMPERATURE,
+};
+
+// what moss grows on, it keeps which one it is growing on in its lifetime and leaves it behind when it dies
+pub const HOSTS: [CellType; 2] = [CellType::Stone, CellType::Wood];
+// chance a tick of a damp host with air or water beside it growing moss of its own, a wet wall greens over in a minute
+// or so
+const COLONIZE_CHANCE: f32 = 0.0002;
+// chance a tick of moss spreading to a host cell beside it that is on the surface
+const SPREAD_CHANCE: f32 = 0.005;
+// moss out of reach of water counts the ticks it has been dry in its fuel, one with this chance a tick, and dies once
+// it gets to DRY_LIMIT. about half a minute
+const DRY_CHANCE: f32 = 0.15;
+const DRY_LIMIT: u8 = u8::MAX;
+// heated this far it dies straight away, fire and lava touching it kill it too
+const SCORCH_TEMPERATURE: u8 = WATER_BOILING_TEMPERATURE;
+
+// damp hosts now and then grow moss, the chance is checked first so dry walls cost next to nothing
+pub fn colonize(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), rng: &Rng) {
+    if rng.f32() >= COLONIZE_CHANCE || !damp(cells, pos) || !on_surface(cells, pos) {
+        return;
+    }
+
+    set_cell(cells, tracking, pos, overgrown(&cells[pos], rng));
+}
+
+// spreads over the surface of the stone and wood beside it, and dies back to what it was growing on once it is burnt or
+// has been dry too long
+pub fn grow(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), rng: &Rng) {
+    if scorched(cells, pos) {
+        die(cells, tracking, pos, rng);
+        return;
+    }
+
+    if damp(cells, pos) {
+        cells[pos].fuel = 0;
+    } else if rng.f32() < DRY_CHANCE {
+        cells[pos].fuel += 1;
+        if cells[pos].fuel >= DRY_LIMIT {
+            die(cells, tracking, pos, rng);
+            return;
+        }
+    }
+
+    if rng.f32() >= SPREAD_CHANCE {
+        return;
+    }
+
+    let direction = NEIGHBOURS[rng.usize(0..NEIGHBOURS.len())];
+    let target = cells
+        .ray(pos, direction, 1)
+        .next()
+        .filter(|(_, neighbour)| HOSTS.contains(&neighbour.ty))
+        .map(|(target, _)| target);
+    if let Some(target) = target.filter(|&target| on_surface(cells, target)) {
+        let moss = overgrown(&cells[target], rng);
+        set_cell(cells, tracking, target, moss);
+        cells[target].set_moved(tracking.generation());
+    }
+}
+
+// the cell moss is growing on, moss drawn on its own grows on stone
+pub fn host(moss: &Cell) -> CellType {
+    all::<CellType>()
+        .nth(moss.lifetime as usize)
+        .filter(|host| HOSTS.contains(host))
+        .unwrap_or(CellType::Stone)
+}
+
+fn overgrown(host: &Cell, rng: &Rng) -> Cell {
+    let mut moss = Cell::from(CellType::Moss, rng);
+    moss.lifetime = host.ty as u8;
+    moss
+}
+
+fn die(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), rng: &Rng) {
+    let mut host = Cell::from(host(&cells[pos]), rng);
+    host.temperature = cells[pos].temperature;
+    set_cell(cells, tracking, pos, host);
+}
+
+// water or mud, or sand or dirt soaked with water, right beside pos
+fn damp(cells: &GridWindow, pos: (usize, usize)) -> bool {
+    NEIGHBOURS.into_iter().any(|direction| {
+        cells
+            .ray(pos, direction, 1)
+            .next()
+            .is_some_and(|(_, cell)| {
+                matches!(cell.ty, CellType::Water | CellType::Mud)
+                    || (matches!(cell.ty, CellType::Sand | CellType::Dirt) && cell.lifetime > 0)
+            })
+    })
+}
+
+// moss only grows on the outside of things, where there is air or water straight beside it
+fn on_surface(cells: &GridWindow, pos: (usize, usize)) -> bool {
+    DIRECTIONS.into_iter().any(|direction| {
+        cells
+            .ray(pos, direction, 1)
+            .next()
+            .is_some_and(|(_, cell)| matches!(cell.ty, CellType::Air | CellType::Water))
+    })
+}
+
+fn scorched(cells: &GridWindow, pos: (usize, usize)) -> bool {
+    cells[pos].temperature >= SCORCH_TEMPERATURE
+        || NEIGHBOURS.into_iter().any(|direction| {
+            cells
+                .ray(pos, direction, 1)
+                .next()
+                .is_some_and(|(_, cell)| matches!(cell.ty, CellType::Fire | CellType::Lava))
+        })
+}
@@ -0,0 +1,26 @@
+// draws the ui texture over the cells, the full screen triangle is squeezed into the same area of the window the
+// scaling renderer put the frame in so every ui pixel lands on top of the frame pixel it was drawn over
+
+struct VertexOutput {
+    @location(0) tex_coord: vec2<f32>,
+    @builtin(position) position: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // (-1, -1), (3, -1), (-1, 3)
+    let position = vec2<f32>(f32(index & 1u), f32(index >> 1u)) * 4.0 - 1.0;
+
+    var out: VertexOutput;
+    out.tex_coord = fma(position, vec2<f32>(0.5, -0.5), vec2<f32>(0.5, 0.5));
+    out.position = vec4<f32>(position, 0.0, 1.0);
+    return out;
+}
+
+@group(0) @binding(0) var ui_texture: texture_2d<f32>;
+@group(0) @binding(1) var ui_sampler: sampler;
+
+@fragment
+fn fs_main(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
+    return textureSample(ui_texture, ui_sampler, tex_coord);
+}
@@ -0,0 +1,181 @@
+use std::f32::consts::PI;
+
+use fastrand::Rng;
+
+use crate::{
+    debris, embers, fungus, goo, grid::Grid, radioactivity, CellType, Tracking, HEIGHT, WIDTH,
+};
+
+// any more and new ones are dropped, keeps a runaway chain reaction from eating the whole frame
+const MAX_PARTICLES: usize = 20_000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ParticleKind {
+    Neutron,
+    Ember,
+    // a cell thrown out of the grid by a blast, it lands back as the same type
+    Debris(CellType),
+    Spore,
+}
+
+// something too small to take up a cell of its own, it flies freely over the grid and only touches the cells it passes
+// through. particles dont collide with each other and arent saved in the grid so nothing else sees them
+#[derive(Clone, Copy)]
+pub struct Particle {
+    pub kind: ParticleKind,
+    pub position: (f32, f32),
+    // in cells a tick
+    pub velocity: (f32, f32),
+    // ticks left before it is gone
+    pub lifetime: u16,
+}
+
+impl Particle {
+    // flying at speed in a random direction from the middle of the cell at pos
+    pub fn from(
+        kind: ParticleKind,
+        pos: (usize, usize),
+        speed: f32,
+        lifetime: u16,
+        rng: &Rng,
+    ) -> Self {
+        let angle = rng.f32() * 2.0 * PI;
+        Particle::moving(
+            kind,
+            pos,
+            (angle.cos() * speed, angle.sin() * speed),
+            lifetime,
+        )
+    }
+
+    // flying at velocity from the middle of the cell at pos
+    pub fn moving(
+        kind: ParticleKind,
+        pos: (usize, usize),
+        velocity: (f32, f32),
+        lifetime: u16,
+    ) -> Self {
+        Particle {
+            kind,
+            position: (pos.0 as f32 + 0.5, pos.1 as f32 + 0.5),
+            velocity,
+            lifetime,
+        }
+    }
+
+    // None once it has left the grid
+    pub fn cell(&self) -> Option<(usize, usize)> {
+        let (x, y) = self.position;
+        (x >= 0.0 && y >= 0.0 && x < WIDTH as f32 && y < HEIGHT as f32)
+            .then_some((x as usize, y as usize))
+    }
+}
+
+// pull of gravity in cells a tick every tick, neutrons fly straight and spores drift instead
+fn particle_kind_gravity(kind: ParticleKind) -> f32 {
+    match kind {
+        ParticleKind::Neutron | ParticleKind::Spore => 0.0,
+        ParticleKind::Ember => embers::EMBER_GRAVITY,
+        ParticleKind::Debris(_) => debris::DEBRIS_GRAVITY,
+    }
+}
+
+pub fn emit(particles: &mut Vec<Particle>, particle: Particle) {
+    if particles.len() < MAX_PARTICLES {
+        particles.push(particle);
+    }
+}
+
+// moves every particle along its velocity at most a cell at a time so none can jump over a cell. each new cell it
+// enters gets a chance to act on it depending on its kind, which can use the particle up or give off new ones. embers
+// and debris that run out of lifetime leave something behind
+pub fn update(cells: &mut Grid, tracking: &mut Tracking, rng: &Rng) {
+    let mut particles = std::mem::take(&mut tracking.particles);
+    let mut emitted = Vec::new();
+
+    {
+        let mut cells = cells.window();
+        let tracking = tracking.window(0);
+
+        particles.retain_mut(|particle| {
+            let Some(pos) = particle.cell() else {
+                return false;
+            };
+            if particle.lifetime == 0 {
+                match particle.kind {
+                    ParticleKind::Ember => embers::burn_out(&mut cells, &tracking, pos, rng),
+                    ParticleKind::Debris(cell_type) => {
+                        debris::land(&mut cells, &tracking, pos, cell_type, rng)
+                    }
+                    ParticleKind::Neutron | ParticleKind::Spore => (),
+                }
+                return false;
+            }
+            particle.lifetime -= 1;
+
+            let gravity = tracking.gravity_at(pos);
+            let pull = particle_kind_gravity(particle.kind);
+            particle.velocity.0 += gravity.0 as f32 * pull;
+            particle.velocity.1 += gravity.1 as f32 * pull;
+            if particle.kind == ParticleKind::Spore {
+                particle.velocity = fungus::drift(gravity, tracking.wind(), rng);
+            }
+
+            let steps = particle
+                .velocity
+                .0
+                .abs()
+                .max(particle.velocity.1.abs())
+                .ceil() as usize;
+            let step = (
+                particle.velocity.0 / steps as f32,
+                particle.velocity.1 / steps as f32,
+            );
+
+            for _ in 0..steps {
+                let Some(from) = particle.cell() else {
+                    return false;
+                };
+                particle.position = (particle.position.0 + step.0, particle.position.1 + step.1);
+
+                let Some(pos) = particle.cell() else {
+                    return false;
+                };
+                if pos == from {
+                    continue;
+                }
+                // slime swallows embers, spores and debris, neutrons go straight through
+                if particle.kind != ParticleKind::Neutron && goo::absorbs(&cells, pos) {
+                    return false;
+                }
+
+                let used_up = match particle.kind {
+                    ParticleKind::Neutron => radioactivity::neutron_strikes(
+                        &mut cells,
+                        &tracking,
+                        pos,
+                        &mut emitted,
+                        rng,
+                    ),
+                    ParticleKind::Ember => {
+                        embers::ember_strikes(&mut cells, &tracking, pos, from, rng)
+                    }
+                    ParticleKind::Debris(cell_type) => {
+                        debris::debris_strikes(&mut cells, &tracking, pos, from, cell_type, rng)
+                    }
+                    ParticleKind::Spore => fungus::spore_strikes(&mut cells, &tracking, pos, rng),
+                };
+                if used_up {
+                    return false;
+                }
+            }
+
+            true
+        });
+    }
+
+    for particle in emitted {
+        emit(&mut particles, particle);
+    }
+    tracking.particles = particles;
+}
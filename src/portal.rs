@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use fastrand::Rng;
+
+use crate::{
+    across, blowable, grid::Grid, set_cell, Cell, CellType, Tracking, DIRECTIONS, HEIGHT, WIDTH,
+};
+
+// portals dont age so keep which pair they belong to in their lifetime, the bottom bit says which end of it they are.
+// counting up goes through both ends of a pair before the next pair
+pub fn portal_end(cell: &Cell) -> usize {
+    (cell.lifetime & 1) as usize
+}
+
+fn pair(cell: &Cell) -> u8 {
+    cell.lifetime >> 1
+}
+
+// anything loose going into a side of a portal comes straight out of the same side of the other end of its pair, keeping
+// its velocity. powders and liquids fall in, gases rise in and anything flying across gravity is thrown in. either end
+// works as the way in and it comes out of a random spot of the other end with room in front of it
+pub fn update(cells: &mut Grid, tracking: &mut Tracking, rng: &Rng) {
+    let mut cells = cells.window();
+    let tracking = tracking.window(0);
+
+    // both ends of every pair
+    let mut ends: HashMap<(u8, usize), Vec<(usize, usize)>> = HashMap::new();
+    for y in 0..HEIGHT {
+        // nothing but air
+        if tracking.row_count(y) == 0 {
+            continue;
+        }
+
+        for x in 0..WIDTH {
+            let cell = cells[(x, y)];
+            if cell.ty == CellType::Portal {
+                ends.entry((pair(&cell), portal_end(&cell)))
+                    .or_default()
+                    .push((x, y));
+            }
+        }
+    }
+
+    let mut entrances: Vec<_> = ends.keys().copied().collect();
+    // the same order every run for the same seed
+    entrances.sort_unstable();
+
+    for (pair, end) in entrances {
+        let Some(exits) = ends.get(&(pair, 1 - end)) else {
+            continue;
+        };
+
+        for &portal in &ends[&(pair, end)] {
+            for direction in DIRECTIONS {
+                // the cell on the other side of the portal from direction, going in direction
+                let Some((from, cell)) = cells.ray(portal, (-direction.0, -direction.1), 1).next()
+                else {
+                    continue;
+                };
+                if !blowable(cell.ty) || !heading(cell, tracking.gravity_at(from), direction) {
+                    continue;
+                }
+
+                let open: Vec<_> = exits
+                    .iter()
+                    .filter_map(|&exit| cells.ray(exit, direction, 1).next())
+                    .filter(|(_, cell)| cell.ty == CellType::Air)
+                    .map(|(to, _)| to)
+                    .collect();
+                if open.is_empty() {
+                    continue;
+                }
+
+                let cell = *cell;
+                let to = open[rng.usize(..open.len())];
+                set_cell(&mut cells, &tracking, from, Cell::from(CellType::Air, rng));
+                set_cell(&mut cells, &tracking, to, cell);
+            }
+        }
+    }
+}
+
+// whether a loose cell is on its way in direction
+fn heading(cell: &Cell, gravity: (isize, isize), direction: (isize, isize)) -> bool {
+    let gas = matches!(cell.ty, CellType::Smoke | CellType::Steam);
+    let across = across(gravity);
+    let sideways = cell.velocity_x.signum() as isize;
+
+    if sideways != 0 {
+        direction == (across.0 * sideways, across.1 * sideways)
+    } else if gas {
+        direction == (-gravity.0, -gravity.1)
+    } else {
+        direction == gravity
+    }
+}
@@ -0,0 +1,225 @@
+use std::sync::atomic::Ordering;
+
+use crate::{
+    furthest_by_vector,
+    grid::{Grid, GridWindow},
+    swap_cells, Cell, CellType, Tracking, HEIGHT, VELOCITY_SCALE, WIDTH,
+};
+
+// how much higher water somewhere else has to stand before it pushes a surface up, any less and level surfaces jitter
+const MIN_HEAD: u16 = 2;
+// the squirt height is worked out as head / 2 - 1
+const _: () = assert!(MIN_HEAD >= 2);
+// what pushed water can be squirted into
+const DISPLACEABLE_TYPES: [CellType; 3] = [CellType::Air, CellType::Smoke, CellType::Steam];
+
+// water pushing on water, worked out fresh at the start of every in place tick. the pressure on a cell is how many cells
+// of water are pushing down on it, found in two sweeps: down the grid adding up the water stacked on each cell, then
+// back up it so the pressure at the bottom of a bend carries up the other side. each sweep spreads the highest pressure
+// along every horizontal run of water, since water connected side by side pushes on itself.
+//
+// along with the pressure each cell remembers the top of the column of water it came from. a surface with more pressure
+// than its own weight is being pushed up by that column standing higher, so the top cell of the column is moved onto the
+// surface. moving it straight there keeps the amount of water the same and levels the two sides out over a few ticks,
+// which water flowing cell by cell through the body in between never manages. the harder the surface is pushed the
+// higher above it the water lands, so a hole in the top of a tank under a lot of pressure squirts
+pub fn update(cells: &mut Grid, tracking: &mut Tracking) {
+    let mut pushed_surfaces = Vec::new();
+    // rows with no water pushing on anything can be skipped on the way back up
+    let mut rows_pushing = vec![false; HEIGHT];
+
+    {
+        let Tracking {
+            pressure,
+            pressure_sources: sources,
+            row_counts,
+            ..
+        } = &mut *tracking;
+
+        // top to bottom, each cell carries the pressure of the cell above it plus its own weight
+        for y in 0..HEIGHT {
+            let (above, row) = pressure.split_at_mut(y * WIDTH);
+            let row = &mut row[..WIDTH];
+            let (sources_above, source_row) = sources.split_at_mut(y * WIDTH);
+            let source_row = &mut source_row[..WIDTH];
+
+            // nothing but air
+            if row_counts[y].load(Ordering::Relaxed) == 0 {
+                row.fill(0);
+                continue;
+            }
+
+            let above =
+                (y > 0).then(|| (&above[(y - 1) * WIDTH..], &sources_above[(y - 1) * WIDTH..]));
+
+            let cells_with_pressure = row
+                .iter_mut()
+                .zip(source_row.iter_mut())
+                .zip(cells.row(y))
+                .enumerate();
+
+            for (x, ((pressure, source), cell)) in cells_with_pressure {
+                if !pushes(cell) {
+                    *pressure = 0;
+                    continue;
+                }
+
+                rows_pushing[y] = true;
+
+                match above {
+                    Some((above, sources_above)) if above[x] > 0 => {
+                        *pressure = above[x].saturating_add(1);
+                        *source = sources_above[x];
+                    }
+                    // the top of a column is its own source
+                    _ => {
+                        *pressure = 1;
+                        *source = (y * WIDTH + x) as u32;
+                    }
+                }
+            }
+
+            if rows_pushing[y] {
+                spread_along_runs(row, source_row, cells.row(y));
+            }
+        }
+
+        // bottom to top, water pushed on from below is under at least the pressure below it less its own weight
+        for y in (0..HEIGHT).rev() {
+            if !rows_pushing[y] {
+                continue;
+            }
+
+            // below is empty for the bottom row
+            let (row, below) = pressure[y * WIDTH..].split_at_mut(WIDTH);
+            let (source_row, sources_below) = sources[y * WIDTH..].split_at_mut(WIDTH);
+
+            let cell_row = cells.row(y);
+            for x in 0..below.len().min(WIDTH) {
+                if pushes(&cell_row[x]) && below[x].saturating_sub(1) > row[x] {
+                    row[x] = below[x] - 1;
+                    source_row[x] = sources_below[x];
+                }
+            }
+
+            spread_along_runs(row, source_row, cell_row);
+
+            if y == 0 {
+                continue;
+            }
+
+            // the surfaces, anything above them can be pushed out of the way
+            let above_cell_row = cells.row(y - 1);
+            for x in 0..WIDTH {
+                if row[x] > MIN_HEAD && is_displaceable(&above_cell_row[x]) {
+                    // the surface holds up its own weight, the rest is how much higher the source stands
+                    let head = row[x] - 1;
+                    pushed_surfaces.push((x, y - 1, head, source_row[x] as usize));
+                }
+            }
+        }
+    }
+
+    let mut cells = cells.window();
+    let tracking = tracking.window(0);
+
+    for (x, y, head, source) in pushed_surfaces {
+        let source = (source % WIDTH, source / WIDTH);
+
+        if !is_displaceable(&cells[(x, y)]) {
+            continue;
+        }
+
+        let Some(source) = column_top(&cells, source, y) else {
+            continue;
+        };
+        // the column may have been taken down to below where its pressure was worked out from
+        let head = head.min((y + 1 - source.1) as u16);
+
+        // up to half the head above the surface, the scan already covers one cell more than it is asked to
+        let target = furthest_by_vector(
+            &cells,
+            (x, y),
+            (head / 2 - 1) as usize,
+            &DISPLACEABLE_TYPES,
+            (0, -1),
+        )
+        .furthest
+        .unwrap_or((x, y));
+
+        swap_cells(&mut cells, &tracking, source, target);
+    }
+}
+
+// water still falling, flying or flowing under its own speed doesnt push on anything, otherwise a waterfall would lift
+// the pool it lands in. compares the raw velocity against 1 cell a tick, this runs for every cell twice a tick
+fn pushes(cell: &Cell) -> bool {
+    cell.ty == CellType::Water && cell.velocity < VELOCITY_SCALE as u8 && cell.velocity_x == 0
+}
+
+// earlier surfaces may have taken the top of the column already, then the next cell down feeds this surface instead as
+// long as it still stands high enough above it. lets one tall column level out a whole staircase of lower surfaces
+// in a single tick rather than a cell at a time
+fn column_top(
+    cells: &GridWindow,
+    (x, mut y): (usize, usize),
+    surface_y: usize,
+) -> Option<(usize, usize)> {
+    while y < surface_y && is_displaceable(&cells[(x, y)]) {
+        y += 1;
+    }
+
+    (pushes(&cells[(x, y)]) && y + MIN_HEAD as usize <= surface_y + 1).then_some((x, y))
+}
+
+fn is_displaceable(cell: &Cell) -> bool {
+    DISPLACEABLE_TYPES.contains(&cell.ty)
+}
+
+// every cell of a run takes on the highest pressure in it, sharing out the sources of the cells that have it so a wide
+// column can feed a wide surface a whole row at a time
+fn spread_along_runs(row: &mut [u16], source_row: &mut [u32], cell_row: &[Cell]) {
+    let mut start = 0;
+    let mut peak_sources = Vec::new();
+
+    while start < WIDTH {
+        if !pushes(&cell_row[start]) {
+            start += 1;
+            continue;
+        }
+
+        let end = cell_row[start..]
+            .iter()
+            .position(|cell| !pushes(cell))
+            .map_or(WIDTH, |length| start + length);
+
+        let (lowest, highest) = row[start..end]
+            .iter()
+            .fold((u16::MAX, 0), |(lowest, highest), &pressure| {
+                (lowest.min(pressure), highest.max(pressure))
+            });
+
+        // already even, the usual case for still water
+        if lowest == highest {
+            start = end;
+            continue;
+        }
+
+        peak_sources.clear();
+        peak_sources.extend(
+            (start..end)
+                .filter(|&x| row[x] == highest)
+                .map(|x| source_row[x]),
+        );
+
+        let mut peak_sources_cycle = peak_sources.iter().cycle();
+        for x in start..end {
+            if row[x] < highest {
+                row[x] = highest;
+                source_row[x] = *peak_sources_cycle.next().unwrap();
+            }
+        }
+
+        start = end;
+    }
+}
@@ -0,0 +1,206 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use enum_iterator::{all, Sequence};
+
+use crate::{
+    cell_type_color_fixed, to_1d_index_pixel_buffer, write_to_pixel_buffer, CellType, HEIGHT, WIDTH,
+};
+
+const SMOOTHING: f32 = 0.05;
+const PIXELS_PER_MILLI: f32 = 20.0;
+const ROW_HEIGHT: usize = 4;
+const ROW_SPACING: usize = 1;
+const MARGIN: usize = 4;
+const KEY_SIZE: usize = ROW_HEIGHT;
+const BACKGROUND_COLOR: [u8; 3] = [0x10, 0x10, 0x10];
+const TICK_COLOR: [u8; 3] = [0x60, 0x60, 0x60];
+
+// skip 1 = air never gets updated
+const ROWS: usize = Scope::CARDINALITY + CellType::CARDINALITY - 1;
+// wraps into more columns side by side once the rows would reach the top, balanced so the last isnt nearly empty
+const MAX_ROWS_PER_COLUMN: usize =
+    (HEIGHT - MARGIN * 2 - ROW_SPACING - 2) / (ROW_HEIGHT + ROW_SPACING);
+const COLUMNS: usize = ROWS.div_ceil(MAX_ROWS_PER_COLUMN);
+const ROWS_PER_COLUMN: usize = ROWS.div_ceil(COLUMNS);
+const PANEL_HEIGHT: usize = ROWS_PER_COLUMN * (ROW_HEIGHT + ROW_SPACING) + ROW_SPACING + 2;
+const _: () = assert!(PANEL_HEIGHT + MARGIN <= HEIGHT);
+
+#[derive(Clone, Copy, Sequence)]
+pub enum Scope {
+    UpdateEvens,
+    UpdateOdds,
+    ClearMoved,
+    Pressure,
+    Electricity,
+    Light,
+    Particles,
+    Blasts,
+    Bodies,
+    Goo,
+    Portals,
+    BlockStep,
+    Draw,
+    Render,
+}
+
+fn scope_color(scope: Scope) -> [u8; 3] {
+    match scope {
+        Scope::UpdateEvens => [0xff, 0xff, 0xff],
+        Scope::UpdateOdds => [0xc0, 0xc0, 0xc0],
+        Scope::ClearMoved => [0x80, 0x80, 0x80],
+        Scope::Pressure => [0x40, 0x90, 0xe0],
+        Scope::Electricity => [0x60, 0xe0, 0xd0],
+        Scope::Light => [0xe0, 0x50, 0x50],
+        Scope::Particles => [0xb4, 0xff, 0x8a],
+        Scope::Blasts => [0xff, 0x8c, 0x28],
+        Scope::Bodies => [0xb0, 0x7a, 0x3c],
+        Scope::Goo => [0x4c, 0xc8, 0x64],
+        Scope::Portals => [0x3c, 0x8c, 0xf0],
+        Scope::BlockStep => [0xe0, 0xc0, 0x40],
+        Scope::Draw => [0x3c, 0xd0, 0x5a],
+        Scope::Render => [0xd0, 0x3c, 0xc8],
+    }
+}
+
+// one bar per scope then one per element, each element bar is the total time spent in its update function across all
+// the strip threads
+pub struct Profiler {
+    pub enabled: bool,
+    scope_nanos: [u64; Scope::CARDINALITY],
+    // added to from the rayon threads
+    element_nanos: [AtomicU64; CellType::CARDINALITY],
+    smoothed_scope_millis: [f32; Scope::CARDINALITY],
+    smoothed_element_millis: [f32; CellType::CARDINALITY],
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            enabled: false,
+            scope_nanos: [0; Scope::CARDINALITY],
+            element_nanos: std::array::from_fn(|_| AtomicU64::new(0)),
+            smoothed_scope_millis: [0.0; Scope::CARDINALITY],
+            smoothed_element_millis: [0.0; CellType::CARDINALITY],
+        }
+    }
+
+    pub fn record(&mut self, scope: Scope, duration: Duration) {
+        if self.enabled {
+            self.scope_nanos[scope as usize] += duration.as_nanos() as u64;
+        }
+    }
+
+    // strips keep their own totals and add them once at the end so the threads arent fighting over the atomics
+    pub fn add_element_nanos(&self, element_nanos: &[u64; CellType::CARDINALITY]) {
+        for (total, nanos) in self.element_nanos.iter().zip(element_nanos) {
+            total.fetch_add(*nanos, Ordering::Relaxed);
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        for (smoothed, nanos) in self
+            .smoothed_scope_millis
+            .iter_mut()
+            .zip(&mut self.scope_nanos)
+        {
+            *smoothed = smooth(*smoothed, *nanos);
+            *nanos = 0;
+        }
+
+        for (smoothed, nanos) in self
+            .smoothed_element_millis
+            .iter_mut()
+            .zip(&self.element_nanos)
+        {
+            *smoothed = smooth(*smoothed, nanos.swap(0, Ordering::Relaxed));
+        }
+    }
+
+    // bottom left corner
+    pub fn draw(&self, frame: &mut [u8]) {
+        let rows = all::<Scope>()
+            .map(|scope| {
+                (
+                    scope_color(scope),
+                    self.smoothed_scope_millis[scope as usize],
+                )
+            })
+            .chain(all::<CellType>().skip(1).map(|cell_type| {
+                (
+                    cell_type_color_fixed(cell_type),
+                    self.smoothed_element_millis[cell_type as usize],
+                )
+            }))
+            .collect::<Vec<_>>();
+
+        let width = WIDTH / 2;
+        let column_width = width / COLUMNS;
+        let top_left = (MARGIN, HEIGHT - MARGIN - PANEL_HEIGHT);
+        let bottom_right = (top_left.0 + width, top_left.1 + PANEL_HEIGHT);
+
+        fill_rect(frame, top_left, bottom_right, &BACKGROUND_COLOR);
+
+        for column in 0..COLUMNS {
+            let left = top_left.0 + column * column_width;
+            let right = left + column_width;
+            let bars_left = left + ROW_SPACING + KEY_SIZE + ROW_SPACING * 2;
+            let max_bar_width = right - bars_left - ROW_SPACING;
+
+            // a tick along the top for every millisecond
+            for milli in 0.. {
+                let x = bars_left + (milli as f32 * PIXELS_PER_MILLI) as usize;
+                if x >= right {
+                    break;
+                }
+
+                fill_rect(frame, (x, top_left.1), (x + 1, top_left.1 + 2), &TICK_COLOR);
+            }
+
+            let column_rows = rows
+                .iter()
+                .skip(column * ROWS_PER_COLUMN)
+                .take(ROWS_PER_COLUMN);
+            for (row, (color, millis)) in column_rows.enumerate() {
+                let y = top_left.1 + 2 + ROW_SPACING + row * (ROW_HEIGHT + ROW_SPACING);
+                let bar_width = ((millis * PIXELS_PER_MILLI) as usize).min(max_bar_width);
+
+                fill_rect(
+                    frame,
+                    (left + ROW_SPACING, y),
+                    (left + ROW_SPACING + KEY_SIZE, y + ROW_HEIGHT),
+                    color,
+                );
+                fill_rect(
+                    frame,
+                    (bars_left, y),
+                    (bars_left + bar_width, y + ROW_HEIGHT),
+                    color,
+                );
+            }
+        }
+    }
+}
+
+fn smooth(smoothed_millis: f32, nanos: u64) -> f32 {
+    let millis = nanos as f32 / 1_000_000.0;
+    smoothed_millis * (1.0 - SMOOTHING) + millis * SMOOTHING
+}
+
+// bottom right is exclusive
+fn fill_rect(
+    frame: &mut [u8],
+    top_left: (usize, usize),
+    bottom_right: (usize, usize),
+    color: &[u8; 3],
+) {
+    for y in top_left.1..bottom_right.1 {
+        for x in top_left.0..bottom_right.0 {
+            write_to_pixel_buffer(frame, to_1d_index_pixel_buffer(x, y), color);
+        }
+    }
+}
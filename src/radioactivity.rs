@@ -0,0 +1,162 @@
+use std::sync::atomic::Ordering;
+
+use fastrand::Rng;
+
+use crate::{
+    cell_type_radioactivity, explode,
+    grid::{Grid, GridWindow},
+    heat_cell,
+    particles::{self, Particle, ParticleKind},
+    set_cell, Cell, CellType, Tracking, TrackingWindow, HEIGHT, NEIGHBOURS, ROOM_TEMPERATURE,
+    TNT_POWER, WIDTH,
+};
+
+const NEUTRON_SPEED: f32 = 3.0;
+const NEUTRON_LIFETIME: u16 = 60;
+// a neutron that doesnt split or breed what it strikes is soaked up by it with this chance, otherwise it carries on
+// through. anything soaking one up is heated a little
+const ABSORB_CHANCE: f32 = 0.5;
+const NEUTRON_HEAT: u8 = 2;
+// a split heats everything around it by this much, fuel this hot melts down and blows up
+const FISSION_HEAT: u8 = 16;
+const MELTDOWN_TEMPERATURE: u8 = ROOM_TEMPERATURE + 160;
+const MELTDOWN_RADIUS: usize = 6;
+
+// something that gives off neutrons, and splits giving off more when one strikes it. a lump is critical once on
+// average every neutron in it causes another, a small lump of plutonium is and uranium has to be piled up far bigger,
+// --criticality makes splits more or less likely
+pub struct Radioactive {
+    // chance a tick of giving off a neutron by itself
+    pub decay_chance: f32,
+    // chance at a criticality of 1 that a neutron striking it splits it, giving off this many more
+    pub fission_chance: f32,
+    pub fission_neutrons: usize,
+    // chance a neutron that didnt split it is taken in and turns it into something else
+    pub breeds: Option<(f32, CellType)>,
+    // decay keeps it this warm, heat above it is slowly shed
+    pub warmth: u8,
+    // what fuel melts into once a chain reaction gets it too hot, None for fuel that blows up instead
+    pub melts: Option<CellType>,
+}
+
+// decay gives off neutrons and warms it, and fuel slowly sheds heat above that so only a chain reaction builds up enough
+// to melt down
+pub fn update(cells: &mut Grid, tracking: &mut Tracking, rng: &Rng) {
+    let Tracking {
+        particles,
+        row_counts,
+        ..
+    } = &mut *tracking;
+
+    let mut cells = cells.window();
+
+    for y in 0..HEIGHT {
+        // nothing but air
+        if row_counts[y].load(Ordering::Relaxed) == 0 {
+            continue;
+        }
+
+        for x in 0..WIDTH {
+            let Some(radioactive) = cell_type_radioactivity(cells[(x, y)].ty) else {
+                continue;
+            };
+
+            let cell = &mut cells[(x, y)];
+            match cell.temperature.cmp(&radioactive.warmth) {
+                std::cmp::Ordering::Greater => cell.temperature -= 1,
+                std::cmp::Ordering::Less => cell.temperature += 1,
+                std::cmp::Ordering::Equal => (),
+            }
+
+            if rng.f32() < radioactive.decay_chance {
+                emit_neutrons(particles, (x, y), 1, rng);
+            }
+        }
+    }
+}
+
+fn emit_neutrons(particles: &mut Vec<Particle>, pos: (usize, usize), count: usize, rng: &Rng) {
+    for _ in 0..count {
+        let neutron = Particle::from(
+            ParticleKind::Neutron,
+            pos,
+            NEUTRON_SPEED,
+            NEUTRON_LIFETIME,
+            rng,
+        );
+        particles::emit(particles, neutron);
+    }
+}
+
+// a neutron entering the cell at pos, it flies straight through air, gases and fire. true if it was used up
+pub fn neutron_strikes(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    emitted: &mut Vec<Particle>,
+    rng: &Rng,
+) -> bool {
+    let cell_type = cells[pos].ty;
+    if matches!(
+        cell_type,
+        CellType::Air | CellType::Smoke | CellType::Steam | CellType::Fire
+    ) {
+        return false;
+    }
+
+    if let Some(radioactive) = cell_type_radioactivity(cell_type) {
+        if rng.f32() < radioactive.fission_chance * tracking.criticality() {
+            fission(cells, tracking, pos, radioactive, emitted, rng);
+            return true;
+        }
+
+        if let Some((breed_chance, bred)) = radioactive.breeds {
+            if rng.f32() < breed_chance {
+                let mut bred = Cell::from(bred, rng);
+                bred.temperature = cells[pos].temperature;
+                set_cell(cells, tracking, pos, bred);
+                return true;
+            }
+        }
+    }
+
+    if rng.f32() < ABSORB_CHANCE {
+        heat_cell(cells, tracking, pos, NEUTRON_HEAT, rng);
+        return true;
+    }
+
+    false
+}
+
+// the split heats the fuel and everything around it and gives off more neutrons, fuel left too hot melts down or is used
+// up in a blast
+fn fission(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    radioactive: &Radioactive,
+    emitted: &mut Vec<Particle>,
+    rng: &Rng,
+) {
+    emit_neutrons(emitted, pos, radioactive.fission_neutrons, rng);
+
+    heat_cell(cells, tracking, pos, FISSION_HEAT, rng);
+    for direction in NEIGHBOURS {
+        let Some((neighbour_pos, _)) = cells.ray(pos, direction, 1).next() else {
+            continue;
+        };
+        heat_cell(cells, tracking, neighbour_pos, FISSION_HEAT, rng);
+    }
+
+    if cells[pos].temperature < MELTDOWN_TEMPERATURE {
+        return;
+    }
+
+    match radioactive.melts {
+        Some(melted) => set_cell(cells, tracking, pos, Cell::from(melted, rng)),
+        None => {
+            set_cell(cells, tracking, pos, Cell::from(CellType::Air, rng));
+            explode(cells, tracking, pos, MELTDOWN_RADIUS, TNT_POWER, rng);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use enum_iterator::Sequence;
+
+use crate::{spout, CellType};
+
+// a cell of the first type touching a cell the second matches turns the pair into the two products, each tick it has the
+// chance of probability. heat is added to every burnable around the first cell when it happens. only the first type
+// goes looking for the second so put whichever is rarer first, it keeps the number of cells searching their
+// neighbours down. the in place and double buffer updates both go through this table, the gpu shader still has its own
+// fire and water rule
+pub struct Reaction {
+    pub reactants: (CellType, Reactant),
+    pub products: (CellType, CellType),
+    pub probability: f32,
+    pub heat: u8,
+}
+
+// what the first cell of a reaction has to be touching, one cell type or any matter at all. matter is everything but
+// air, fire, antimatter and the fixtures voids dont eat either
+pub enum Reactant {
+    Cell(CellType),
+    Matter,
+}
+
+impl Reactant {
+    fn matches(&self, cell_type: CellType) -> bool {
+        match self {
+            Reactant::Cell(reactant) => *reactant == cell_type,
+            Reactant::Matter => {
+                !spout::fixture(cell_type)
+                    && !matches!(cell_type, CellType::Fire | CellType::Antimatter)
+            }
+        }
+    }
+}
+
+pub const REACTIONS: [Reaction; 5] = [
+    // fire is put out by water, the water is left where it is
+    Reaction {
+        reactants: (CellType::Fire, Reactant::Cell(CellType::Water)),
+        products: (CellType::Steam, CellType::Water),
+        probability: 1.0,
+        heat: 0,
+    },
+    // lava is quenched into obsidian by water, which flashes into steam. not every touch so a flow can push a little way
+    // into a pool before it sets
+    Reaction {
+        reactants: (CellType::Lava, Reactant::Cell(CellType::Water)),
+        products: (CellType::Obsidian, CellType::Steam),
+        probability: 0.5,
+        heat: 0,
+    },
+    // molten metal is quenched the same way
+    Reaction {
+        reactants: (CellType::MoltenMetal, Reactant::Cell(CellType::Water)),
+        products: (CellType::Metal, CellType::Steam),
+        probability: 0.5,
+        heat: 0,
+    },
+    // burning charred wood is put out by water and left as charcoal
+    Reaction {
+        reactants: (CellType::CharredWood, Reactant::Cell(CellType::Water)),
+        products: (CellType::Coal, CellType::Steam),
+        probability: 0.5,
+        heat: 0,
+    },
+    // antimatter and whatever matter it touches annihilate each other, leaving nothing but a flash that heats the
+    // burnables around it
+    Reaction {
+        reactants: (CellType::Antimatter, Reactant::Matter),
+        products: (CellType::Air, CellType::Fire),
+        probability: 1.0,
+        heat: 32,
+    },
+];
+
+// which cell types start a reaction, looked up before bothering to search the neighbours
+const STARTS_REACTION: [bool; CellType::CARDINALITY] = {
+    let mut starts = [false; CellType::CARDINALITY];
+    let mut i = 0;
+    while i < REACTIONS.len() {
+        starts[REACTIONS[i].reactants.0 as usize] = true;
+        i += 1;
+    }
+    starts
+};
+
+pub fn starts_reaction(cell_type: CellType) -> bool {
+    STARTS_REACTION[cell_type as usize]
+}
+
+pub fn reaction(a: CellType, b: CellType) -> Option<&'static Reaction> {
+    REACTIONS
+        .iter()
+        .find(|reaction| reaction.reactants.0 == a && reaction.reactants.1.matches(b))
+}
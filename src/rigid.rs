@@ -0,0 +1,522 @@
+use std::collections::HashSet;
+
+use fastrand::Rng;
+use rapier2d::prelude::*;
+
+use crate::{
+    across, blowable, cell_type_liquid_density,
+    grid::{Grid, GridWindow},
+    set_cell, Cell, CellType, Tracking, TrackingWindow, ACCELERATION, HEIGHT, WIDTH,
+};
+
+// physics steps a tick at a time in cells and seconds, and the sim ticks 60 times a second
+const TICKS_PER_SECOND: f32 = 60.0;
+// falls as fast as sand does
+const GRAVITY: f32 = ACCELERATION * TICKS_PER_SECOND * TICKS_PER_SECOND;
+// about the size of a body in cells, rapier scales its tolerances by it
+const LENGTH_UNIT: f32 = 8.0;
+// solid cells this far around a body, plus however far it moves in a tick, are turned into colliders every tick
+const TERRAIN_MARGIN: f32 = 2.0;
+// how far a liquid or powder cell under a body looks against gravity for somewhere to be pushed out to
+const DISPLACE_REACH: usize = 64;
+// how fast a body with all of it in liquid slows down, as rapier damping so about the fraction of its velocity lost a
+// tick times the tick rate
+const LIQUID_DAMPING: f32 = 8.0;
+const MAX_BODIES: usize = 64;
+
+const CRATE_DENSITY: f32 = 0.5;
+const CRATE_RESTITUTION: f32 = 0.1;
+const BALL_DENSITY: f32 = 0.8;
+const BALL_RESTITUTION: f32 = 0.6;
+const FRICTION: f32 = 0.6;
+
+// crates float high in water and balls low
+#[derive(Clone, Copy, PartialEq)]
+pub enum BodyShape {
+    Crate,
+    Ball,
+}
+
+// a blast set off somewhere in the grid since the last step, it throws every body in reach away from its centre
+pub struct Blast {
+    pub center: (usize, usize),
+    pub radius: usize,
+    pub power: f32,
+}
+
+struct Body {
+    handle: RigidBodyHandle,
+    shape: BodyShape,
+    // half the width of a crate or the radius of a ball
+    size: f32,
+    // every cell inside the body after the last step, and the ones of those it was drawn into
+    footprint: Vec<(usize, usize)>,
+    stamped: Vec<(usize, usize)>,
+}
+
+// rigid bodies simulated by rapier alongside the cells. every in place tick each body is cut back out of the grid, the
+// solid cells around it become colliders, liquid beside it holds it up, then after the step it is drawn back into the
+// grid as body cells that everything else treats as a wall. liquid and powder where it lands is pushed out above it
+pub struct Bodies {
+    pipeline: PhysicsPipeline,
+    integration_parameters: IntegrationParameters,
+    islands: IslandManager,
+    broad_phase: DefaultBroadPhase,
+    narrow_phase: NarrowPhase,
+    rigid_bodies: RigidBodySet,
+    colliders: ColliderSet,
+    impulse_joints: ImpulseJointSet,
+    multibody_joints: MultibodyJointSet,
+    ccd_solver: CCDSolver,
+    // the solid cells around the bodies, rebuilt every tick
+    terrain: Option<ColliderHandle>,
+    bodies: Vec<Body>,
+}
+
+impl Bodies {
+    fn new() -> Self {
+        let integration_parameters = IntegrationParameters {
+            dt: 1.0 / TICKS_PER_SECOND,
+            length_unit: LENGTH_UNIT,
+            ..IntegrationParameters::default()
+        };
+
+        let mut colliders = ColliderSet::new();
+        // the edges of the grid are walls to bodies like they are to cells
+        let (width, height) = (WIDTH as f32, HEIGHT as f32);
+        for (center, half_extents) in [
+            ((width / 2.0, -1.0), (width, 1.0)),
+            ((width / 2.0, height + 1.0), (width, 1.0)),
+            ((-1.0, height / 2.0), (1.0, height)),
+            ((width + 1.0, height / 2.0), (1.0, height)),
+        ] {
+            colliders.insert(
+                ColliderBuilder::cuboid(half_extents.0, half_extents.1)
+                    .translation(vector![center.0, center.1])
+                    .build(),
+            );
+        }
+
+        Bodies {
+            pipeline: PhysicsPipeline::new(),
+            integration_parameters,
+            islands: IslandManager::new(),
+            broad_phase: DefaultBroadPhase::new(),
+            narrow_phase: NarrowPhase::new(),
+            rigid_bodies: RigidBodySet::new(),
+            colliders,
+            impulse_joints: ImpulseJointSet::new(),
+            multibody_joints: MultibodyJointSet::new(),
+            ccd_solver: CCDSolver::new(),
+            terrain: None,
+            bodies: Vec::new(),
+        }
+    }
+
+    // the cells the body covers stop being body cells
+    fn erase(&mut self, cells: &mut GridWindow, tracking: &TrackingWindow, rng: &Rng) {
+        for body in &mut self.bodies {
+            for pos in body.stamped.drain(..) {
+                if cells[pos].ty == CellType::Body {
+                    set_cell(cells, tracking, pos, Cell::from(CellType::Air, rng));
+                }
+            }
+        }
+    }
+
+    // runs of solid cells along each row near a body become boxes in one fixed collider
+    fn build_terrain(&mut self, cells: &GridWindow) {
+        if let Some(terrain) = self.terrain.take() {
+            self.colliders
+                .remove(terrain, &mut self.islands, &mut self.rigid_bodies, false);
+        }
+
+        let mut runs = HashSet::new();
+        let mut shapes = Vec::new();
+
+        for body in &self.bodies {
+            let rigid_body = &self.rigid_bodies[body.handle];
+            let aabb = self.colliders[rigid_body.colliders()[0]].compute_aabb();
+            let margin = TERRAIN_MARGIN + rigid_body.linvel().norm() / TICKS_PER_SECOND;
+            let (x_range, y_range) = cell_range(&aabb, margin);
+
+            for y in y_range {
+                let mut x = x_range.start;
+                while x < x_range.end {
+                    if !blocks_bodies(cells[(x, y)].ty) {
+                        x += 1;
+                        continue;
+                    }
+
+                    let start = x;
+                    while x < x_range.end && blocks_bodies(cells[(x, y)].ty) {
+                        x += 1;
+                    }
+
+                    if runs.insert((start, x, y)) {
+                        let half_width = (x - start) as f32 / 2.0;
+                        shapes.push((
+                            Isometry::translation(start as f32 + half_width, y as f32 + 0.5),
+                            SharedShape::cuboid(half_width, 0.5),
+                        ));
+                    }
+                }
+            }
+        }
+
+        if !shapes.is_empty() {
+            let terrain = ColliderBuilder::compound(shapes).friction(FRICTION).build();
+            self.terrain = Some(self.colliders.insert(terrain));
+        }
+    }
+
+    // every cell of a body with liquid level with it across gravity is under the surface and pushed up by the weight of
+    // the liquid it displaces, so a body lighter than the liquid settles with just enough of it under. a side open to
+    // air or gas means it is above the surface, so drops clinging to one side of it dont hold it up
+    fn float(&mut self, cells: &GridWindow, gravity: (isize, isize)) {
+        let across = across(gravity);
+        let dt = self.integration_parameters.dt;
+
+        for body in &self.bodies {
+            let inside: HashSet<_> = body.footprint.iter().copied().collect();
+            let rigid_body = &mut self.rigid_bodies[body.handle];
+            let mut submerged = 0;
+
+            for &pos in &body.footprint {
+                let sides = [across, (-across.0, -across.1)].map(|direction| {
+                    cells
+                        .ray(pos, direction, WIDTH.max(HEIGHT))
+                        .find(|(pos, _)| !inside.contains(pos))
+                        .map(|(_, cell)| cell.ty)
+                });
+                if sides.iter().flatten().any(|&ty| open(ty)) {
+                    continue;
+                }
+                let Some(density) = sides
+                    .into_iter()
+                    .flatten()
+                    .filter_map(cell_type_liquid_density)
+                    .reduce(f32::max)
+                else {
+                    continue;
+                };
+
+                submerged += 1;
+                let lift = -GRAVITY * density * dt;
+                rigid_body.apply_impulse_at_point(
+                    vector![gravity.0 as f32 * lift, gravity.1 as f32 * lift],
+                    point![pos.0 as f32 + 0.5, pos.1 as f32 + 0.5],
+                    true,
+                );
+            }
+
+            let damping = submerged as f32 / body.footprint.len().max(1) as f32 * LIQUID_DAMPING;
+            rigid_body.set_linear_damping(damping);
+            rigid_body.set_angular_damping(damping);
+        }
+    }
+
+    // throws bodies the same way explode throws cells, the further from the centre the weaker
+    fn blast(&mut self, blasts: &[Blast]) {
+        for blast in blasts {
+            let center = vector![blast.center.0 as f32 + 0.5, blast.center.1 as f32 + 0.5];
+
+            for body in &self.bodies {
+                let rigid_body = &mut self.rigid_bodies[body.handle];
+                let offset = rigid_body.translation() - center;
+                let distance = offset.norm();
+                let reach = blast.radius as f32 + body.size;
+                if distance >= reach || distance == 0.0 {
+                    continue;
+                }
+
+                let speed = blast.power * (1.0 - distance / reach) * TICKS_PER_SECOND;
+                let impulse = offset / distance * speed * rigid_body.mass();
+                rigid_body.apply_impulse(impulse, true);
+            }
+        }
+    }
+
+    fn step(&mut self, gravity: (isize, isize)) {
+        self.pipeline.step(
+            &vector![gravity.0 as f32 * GRAVITY, gravity.1 as f32 * GRAVITY],
+            &self.integration_parameters,
+            &mut self.islands,
+            &mut self.broad_phase,
+            &mut self.narrow_phase,
+            &mut self.rigid_bodies,
+            &mut self.colliders,
+            &mut self.impulse_joints,
+            &mut self.multibody_joints,
+            &mut self.ccd_solver,
+            None,
+            &(),
+            &(),
+        );
+    }
+
+    // draws each body into the cells it covers now. gases and fire make way, liquid and powder are pushed out above it
+    // and anything solid in the way stays, leaving a hole in the body
+    fn stamp(
+        &mut self,
+        cells: &mut GridWindow,
+        tracking: &TrackingWindow,
+        gravity: (isize, isize),
+        rng: &Rng,
+    ) {
+        for body in &mut self.bodies {
+            let collider = &self.colliders[self.rigid_bodies[body.handle].colliders()[0]];
+            let (x_range, y_range) = cell_range(&collider.compute_aabb(), 0.0);
+
+            body.footprint.clear();
+            for y in y_range {
+                for x in x_range.clone() {
+                    let center = point![x as f32 + 0.5, y as f32 + 0.5];
+                    if collider
+                        .shape()
+                        .contains_point(collider.position(), &center)
+                    {
+                        body.footprint.push((x, y));
+                    }
+                }
+            }
+
+            let inside: HashSet<_> = body.footprint.iter().copied().collect();
+            for &pos in &body.footprint {
+                let cell_type = cells[pos].ty;
+                let clear = match cell_type {
+                    CellType::Air | CellType::Fire => true,
+                    _ if blowable(cell_type) => displace(cells, tracking, pos, &inside, gravity),
+                    _ => false,
+                };
+                if !clear {
+                    continue;
+                }
+
+                let mut body_cell = Cell::from(CellType::Body, rng);
+                body_cell.lifetime = body.shape as u8;
+                set_cell(cells, tracking, pos, body_cell);
+                body.stamped.push(pos);
+            }
+        }
+    }
+}
+
+// drops a new body centred on pos, size is half the width of a crate or the radius of a ball
+pub fn spawn(tracking: &mut Tracking, shape: BodyShape, pos: (usize, usize), size: f32) {
+    let bodies = tracking
+        .bodies
+        .get_or_insert_with(|| Box::new(Bodies::new()));
+    if bodies.bodies.len() >= MAX_BODIES {
+        return;
+    }
+
+    let rigid_body = RigidBodyBuilder::dynamic()
+        .translation(vector![pos.0 as f32 + 0.5, pos.1 as f32 + 0.5])
+        .ccd_enabled(true)
+        .build();
+    let collider = match shape {
+        BodyShape::Crate => ColliderBuilder::cuboid(size, size)
+            .density(CRATE_DENSITY)
+            .restitution(CRATE_RESTITUTION),
+        BodyShape::Ball => ColliderBuilder::ball(size)
+            .density(BALL_DENSITY)
+            .restitution(BALL_RESTITUTION),
+    }
+    .friction(FRICTION)
+    .build();
+
+    let handle = bodies.rigid_bodies.insert(rigid_body);
+    bodies
+        .colliders
+        .insert_with_parent(collider, handle, &mut bodies.rigid_bodies);
+
+    bodies.bodies.push(Body {
+        handle,
+        shape,
+        size,
+        footprint: Vec::new(),
+        stamped: Vec::new(),
+    });
+}
+
+// removes every body whose centre is within radius of pos
+pub fn remove(
+    cells: &mut Grid,
+    tracking: &mut Tracking,
+    pos: (usize, usize),
+    radius: f32,
+    rng: &Rng,
+) {
+    let Some(mut bodies) = tracking.bodies.take() else {
+        return;
+    };
+
+    {
+        let mut cells = cells.window();
+        let tracking = tracking.window(0);
+        let center = vector![pos.0 as f32 + 0.5, pos.1 as f32 + 0.5];
+
+        let Bodies {
+            rigid_bodies,
+            colliders,
+            islands,
+            impulse_joints,
+            multibody_joints,
+            bodies,
+            ..
+        } = &mut *bodies;
+
+        bodies.retain_mut(|body| {
+            if (rigid_bodies[body.handle].translation() - center).norm() > radius {
+                return true;
+            }
+
+            for &pos in &body.stamped {
+                if cells[pos].ty == CellType::Body {
+                    set_cell(&mut cells, &tracking, pos, Cell::from(CellType::Air, rng));
+                }
+            }
+            rigid_bodies.remove(
+                body.handle,
+                islands,
+                colliders,
+                impulse_joints,
+                multibody_joints,
+                true,
+            );
+            false
+        });
+    }
+
+    tracking.bodies = Some(bodies);
+}
+
+// the grid moved over the world by offset cells, see World. bodies are cut out of the grid and moved with it, the ones
+// whose centre is no longer over it are dropped since the chunks dont keep them
+pub fn shift(cells: &mut Grid, tracking: &mut Tracking, offset: (isize, isize), rng: &Rng) {
+    let Some(mut bodies) = tracking.bodies.take() else {
+        return;
+    };
+
+    {
+        let mut cells = cells.window();
+        let tracking = tracking.window(0);
+        bodies.erase(&mut cells, &tracking, rng);
+    }
+
+    let Bodies {
+        rigid_bodies,
+        colliders,
+        islands,
+        impulse_joints,
+        multibody_joints,
+        bodies: list,
+        ..
+    } = &mut *bodies;
+    let offset = vector![offset.0 as f32, offset.1 as f32];
+
+    list.retain_mut(|body| {
+        let rigid_body = &mut rigid_bodies[body.handle];
+        let translation = rigid_body.translation() - offset;
+        // rebuilt at the next stamp
+        body.footprint.clear();
+
+        if (0.0..WIDTH as f32).contains(&translation.x)
+            && (0.0..HEIGHT as f32).contains(&translation.y)
+        {
+            rigid_body.set_translation(translation, true);
+            return true;
+        }
+
+        rigid_bodies.remove(
+            body.handle,
+            islands,
+            colliders,
+            impulse_joints,
+            multibody_joints,
+            true,
+        );
+        false
+    });
+
+    tracking.bodies = Some(bodies);
+}
+
+// one physics step, see Bodies. does nothing until the first body is spawned
+pub fn update(cells: &mut Grid, tracking: &mut Tracking, rng: &Rng) {
+    // strips push these in whatever order they finish, sorted so the bodies are thrown the same way every run
+    let mut blasts = std::mem::take(tracking.blasts.get_mut().unwrap());
+    blasts.sort_unstable_by(|a, b| {
+        (a.center, a.radius)
+            .cmp(&(b.center, b.radius))
+            .then(a.power.total_cmp(&b.power))
+    });
+    let Some(mut bodies) = tracking.bodies.take() else {
+        return;
+    };
+
+    {
+        let mut cells = cells.window();
+        let gravity = tracking.gravity;
+        let tracking = tracking.window(0);
+
+        bodies.erase(&mut cells, &tracking, rng);
+        bodies.build_terrain(&cells);
+        bodies.float(&cells, gravity);
+        bodies.blast(&blasts);
+        bodies.step(gravity);
+        bodies.stamp(&mut cells, &tracking, gravity, rng);
+    }
+
+    tracking.bodies = Some(bodies);
+}
+
+// anything a body cant pass through, liquids, gases and fire it moves through and other bodies are handled by rapier
+fn blocks_bodies(cell_type: CellType) -> bool {
+    cell_type_liquid_density(cell_type).is_none() && !open(cell_type) && cell_type != CellType::Body
+}
+
+// somewhere a body can move into freely
+fn open(cell_type: CellType) -> bool {
+    matches!(
+        cell_type,
+        CellType::Air | CellType::Smoke | CellType::Steam | CellType::Fire
+    )
+}
+
+// moves the cell at pos to the first air against gravity outside the body, through anything else loose. false if
+// there was nowhere for it to go
+fn displace(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    inside: &HashSet<(usize, usize)>,
+    gravity: (isize, isize),
+) -> bool {
+    let target = cells
+        .ray(pos, (-gravity.0, -gravity.1), DISPLACE_REACH)
+        .take_while(|(ray_pos, cell)| {
+            inside.contains(ray_pos) || blowable(cell.ty) || cell.ty == CellType::Air
+        })
+        .find(|(ray_pos, cell)| !inside.contains(ray_pos) && cell.ty == CellType::Air)
+        .map(|(target, _)| target);
+    let Some(target) = target else {
+        return false;
+    };
+
+    let cell = cells[pos];
+    set_cell(cells, tracking, target, cell);
+    true
+}
+
+// the cells an aabb grown by margin covers, clamped to the grid
+fn cell_range(aabb: &Aabb, margin: f32) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+    let start = |min: f32| (min - margin).floor().max(0.0) as usize;
+    let end = |max: f32, limit: usize| ((max + margin).ceil().max(0.0) as usize).min(limit);
+
+    (
+        start(aabb.mins.x)..end(aabb.maxs.x, WIDTH),
+        start(aabb.mins.y)..end(aabb.maxs.y, HEIGHT),
+    )
+}
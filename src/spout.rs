@@ -0,0 +1,117 @@
+use enum_iterator::{all, Sequence};
+use fastrand::Rng;
+
+use crate::{
+    blowable, direction_index, grid::GridWindow, set_cell, Cell, CellType, TrackingWindow,
+    DIRECTIONS, NEIGHBOURS,
+};
+
+// spouts dont age so keep the element they pour in their lifetime, and they never move so keep the index of the
+// direction they pour in their velocity
+pub fn set_spout(cell: &mut Cell, element: CellType, facing: (isize, isize)) {
+    cell.lifetime = element as u8;
+    cell.velocity = direction_index(facing);
+}
+
+// the element has to fit in a byte, clones and sensors keep theirs the same way
+const _: () = assert!(CellType::CARDINALITY <= 1 << 8);
+
+pub fn spout_element(cell: &Cell) -> CellType {
+    all::<CellType>().nth(cell.lifetime as usize).unwrap()
+}
+
+pub fn spout_facing(cell: &Cell) -> (isize, isize) {
+    DIRECTIONS[cell.velocity as usize]
+}
+
+// a new cell of the element every tick into the air in front of the spout, it has already moved so the stream comes out
+// a cell at a time
+pub fn pour(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), rng: &Rng) {
+    let spout = cells[pos];
+    let Some((front, cell)) = cells.ray(pos, spout_facing(&spout), 1).next() else {
+        return;
+    };
+    if cell.ty != CellType::Air {
+        return;
+    }
+
+    let mut poured = Cell::from(spout_element(&spout), rng);
+    poured.set_moved(tracking.generation());
+    set_cell(cells, tracking, front, poured);
+}
+
+// anything loose touching a drain is gone, whatever flows in after it goes the same way
+pub fn drain(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), rng: &Rng) {
+    for direction in NEIGHBOURS {
+        let Some((neighbour, cell)) = cells.ray(pos, direction, 1).next() else {
+            continue;
+        };
+        if blowable(cell.ty) {
+            set_cell(cells, tracking, neighbour, Cell::from(CellType::Air, rng));
+        }
+    }
+}
+
+// what makes, takes or moves cells itself, or is part of a rigid body. clones dont copy it, voids dont eat it and
+// antimatter doesnt annihilate it
+pub fn fixture(cell_type: CellType) -> bool {
+    matches!(
+        cell_type,
+        CellType::Air
+            | CellType::Spout
+            | CellType::Drain
+            | CellType::Clone
+            | CellType::Void
+            | CellType::Portal
+            | CellType::Timer
+            | CellType::Delay
+            | CellType::Body
+    )
+}
+
+// clones keep the element they copy in their lifetime, air until the first thing touches them. after that they put a new
+// cell of it in a free spot around them every tick
+pub fn duplicate(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    rng: &Rng,
+) {
+    let around = || {
+        NEIGHBOURS
+            .into_iter()
+            .filter_map(|direction| cells.ray(pos, direction, 1).next())
+    };
+
+    if cells[pos].lifetime == CellType::Air as u8 {
+        if let Some(touching) = around().map(|(_, cell)| cell.ty).find(|&ty| !fixture(ty)) {
+            cells[pos].lifetime = touching as u8;
+        }
+        return;
+    }
+
+    let free: Vec<_> = around()
+        .filter(|(_, cell)| cell.ty == CellType::Air)
+        .map(|(neighbour, _)| neighbour)
+        .collect();
+    if free.is_empty() {
+        return;
+    }
+
+    let element = all::<CellType>().nth(cells[pos].lifetime as usize).unwrap();
+    let mut copy = Cell::from(element, rng);
+    copy.set_moved(tracking.generation());
+    set_cell(cells, tracking, free[rng.usize(..free.len())], copy);
+}
+
+// unlike a drain a void eats anything touching it, walls and all
+pub fn consume(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), rng: &Rng) {
+    for direction in NEIGHBOURS {
+        let Some((neighbour, cell)) = cells.ray(pos, direction, 1).next() else {
+            continue;
+        };
+        if !fixture(cell.ty) {
+            set_cell(cells, tracking, neighbour, Cell::from(CellType::Air, rng));
+        }
+    }
+}
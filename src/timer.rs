@@ -0,0 +1,103 @@
+use fastrand::Rng;
+
+use crate::{
+    electricity,
+    grid::GridWindow,
+    spout::{self, spout_element, spout_facing},
+    Cell, CellType, TrackingWindow, TIMER_PERIODS,
+};
+
+// the countdown is the bottom 6 bits of the fuel and the index of the period the top 2. delays waiting to be set off or
+// for what set them off to stop sit past the end of any countdown
+const COUNTDOWN_MASK: u8 = 0x3f;
+const DELAY_ARMED: u8 = COUNTDOWN_MASK;
+const DELAY_SPENT: u8 = COUNTDOWN_MASK - 1;
+const _: () = assert!(TIMER_PERIODS[TIMER_PERIODS.len() - 1] <= DELAY_SPENT);
+
+// timers and delays keep what they put out and the way they face like spouts, see spout::set_spout, and count down in
+// their fuel. new timers go off straight away and new delays are ready to be set off
+pub fn timer_fuel(cell_type: CellType, period: u8) -> u8 {
+    let index = TIMER_PERIODS.iter().position(|&p| p == period).unwrap() as u8;
+    let countdown = if cell_type == CellType::Delay {
+        DELAY_ARMED
+    } else {
+        0
+    };
+
+    index << 6 | countdown
+}
+
+fn period(cell: &Cell) -> u8 {
+    TIMER_PERIODS[(cell.fuel >> 6) as usize]
+}
+
+fn countdown(cell: &Cell) -> u8 {
+    cell.fuel & COUNTDOWN_MASK
+}
+
+fn set_countdown(cell: &mut Cell, countdown: u8) {
+    cell.fuel = cell.fuel & !COUNTDOWN_MASK | countdown;
+}
+
+// ones put down straight after picking batteries give off charge instead of cells, out of their front for the tick they
+// go off. see electricity::update
+pub fn charging(cell: &Cell) -> bool {
+    countdown(cell) == 0 && spout_element(cell) == CellType::Battery
+}
+
+// goes off every period ticks, timers put down together go off together
+pub fn tick_timer(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    rng: &Rng,
+) {
+    if countdown(&cells[pos]) == 0 {
+        go_off(cells, tracking, pos, rng);
+        let period = period(&cells[pos]);
+        set_countdown(&mut cells[pos], period);
+    }
+
+    let countdown = countdown(&cells[pos]) - 1;
+    set_countdown(&mut cells[pos], countdown);
+}
+
+// goes off once period ticks after charge first reaches its back, then waits for that charge to go away before it can
+// be set off again. a row of them facing along a wire is a sequencer
+pub fn tick_delay(
+    cells: &mut GridWindow,
+    tracking: &TrackingWindow,
+    pos: (usize, usize),
+    rng: &Rng,
+) {
+    let countdown = match countdown(&cells[pos]) {
+        DELAY_ARMED if charged_behind(cells, tracking, pos) => period(&cells[pos]) - 1,
+        DELAY_SPENT if !charged_behind(cells, tracking, pos) => DELAY_ARMED,
+        DELAY_ARMED | DELAY_SPENT => return,
+        0 => {
+            go_off(cells, tracking, pos, rng);
+            DELAY_SPENT
+        }
+        countdown => countdown - 1,
+    };
+
+    set_countdown(&mut cells[pos], countdown);
+}
+
+// charge is given off by the electricity pass, anything else is poured into the air in front
+fn go_off(cells: &mut GridWindow, tracking: &TrackingWindow, pos: (usize, usize), rng: &Rng) {
+    if spout_element(&cells[pos]) != CellType::Battery {
+        spout::pour(cells, tracking, pos, rng);
+    }
+}
+
+// charge on the cell behind, unless it is a gate, timer or delay giving it off some other way
+fn charged_behind(cells: &GridWindow, tracking: &TrackingWindow, pos: (usize, usize)) -> bool {
+    let facing = spout_facing(&cells[pos]);
+    let Some((behind, cell)) = cells.ray(pos, (-facing.0, -facing.1), 1).next() else {
+        return false;
+    };
+    let source = matches!(cell.ty, CellType::Gate | CellType::Timer | CellType::Delay);
+
+    tracking.charge_at(behind) > 0 && (!source || electricity::one_way_facing(cell) == facing)
+}
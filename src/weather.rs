@@ -0,0 +1,57 @@
+use enum_iterator::Sequence;
+use fastrand::Rng;
+
+use crate::{grid::Grid, set_cell, Cell, CellType, Tracking, HEIGHT, WIDTH};
+
+// how often each cell along the top edge drops something a tick, from a drizzle up to a downpour
+pub const WEATHER_INTENSITIES: [f32; 4] = [0.002, 0.01, 0.03, 0.1];
+// rain is heavy enough to mostly fall straight, snow is carried along by the wind
+const RAIN_DRIFT: f32 = 0.5;
+const SNOW_DRIFT: f32 = 2.0;
+
+#[derive(Clone, Copy, PartialEq, Sequence)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Snow,
+}
+
+// what falls out of the sky and how much the wind throws it sideways as it comes in
+fn precipitation(weather: Weather) -> Option<(CellType, f32)> {
+    match weather {
+        Weather::Clear => None,
+        Weather::Rain => Some((CellType::Water, RAIN_DRIFT)),
+        Weather::Snow => Some((CellType::Snow, SNOW_DRIFT)),
+    }
+}
+
+// rain or snow comes in along whichever edge of the grid gravity pulls away from, into any air there
+pub fn update(cells: &mut Grid, tracking: &Tracking, rng: &Rng) {
+    let Some((cell_type, drift)) = precipitation(tracking.weather) else {
+        return;
+    };
+
+    let mut cells = cells.window();
+    let gravity = tracking.gravity;
+    let tracking = tracking.window(0);
+    let wind = tracking.wind();
+
+    for pos in top_edge(gravity) {
+        if cells[pos].ty != CellType::Air || rng.f32() >= tracking.weather_intensity() {
+            continue;
+        }
+
+        let mut cell = Cell::from(cell_type, rng);
+        cell.set_velocity_x(wind * drift);
+        set_cell(&mut cells, &tracking, pos, cell);
+    }
+}
+
+fn top_edge(gravity: (isize, isize)) -> Vec<(usize, usize)> {
+    match gravity {
+        (0, 1) => (0..WIDTH).map(|x| (x, 0)).collect(),
+        (0, _) => (0..WIDTH).map(|x| (x, HEIGHT - 1)).collect(),
+        (1, _) => (0..HEIGHT).map(|y| (0, y)).collect(),
+        _ => (0..HEIGHT).map(|y| (WIDTH - 1, y)).collect(),
+    }
+}
@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+
+use enum_iterator::all;
+use fastrand::Rng;
+
+use crate::{
+    across, cell_type_liquid_density, cell_type_viscosity,
+    generation::{self, Terrain},
+    grid::Grid,
+    rigid, set_cell, stream_seed, Cell, CellType, Tracking, HEIGHT, NO_FIELD, WIDTH,
+};
+
+// cells along each side of a chunk of the world, the grid moves over the world this many cells at a time
+const CHUNK_SIZE: usize = 64;
+// chunks further than this many chunks from the grid are written out to disk and dropped
+const KEEP_DISTANCE: i64 = 4;
+// a cell's type, velocities, color, lifetime, fuel and temperature then the field painted over it
+const CELL_BYTES: usize = 8;
+// chance a tick of a runny liquid or a rising gas on the edge of the grid going on out past it, thicker liquids less
+const OVERFLOW_CHANCE: f32 = 0.5;
+// steam condenses on the edge of the grid instead and toxic gas sinks
+const RISING: [CellType; 3] = [CellType::Smoke, CellType::Hydrogen, CellType::Methane];
+
+// the part of a chunk the grid is over, as the columns and rows of the grid
+struct Overlap {
+    key: (i64, i64),
+    columns: Range<usize>,
+    rows: Range<usize>,
+}
+
+// a square of the world with the force fields painted over it
+struct Chunk {
+    cells: Vec<Cell>,
+    fields: Vec<u8>,
+    // changed since it was last written to disk, clean chunks are already there so can just be dropped
+    dirty: bool,
+}
+
+// the world goes on forever in every direction as chunks, the grid is the part of it around the camera that is loaded
+// and simulated. once the camera pans past the edge of the grid the grid follows it a whole number of chunks at a time:
+// everything in it is put back into the chunks it was over and the chunks it moves over are read back in, made from the
+// terrain or as empty air the first time anything reaches them. the world outside the grid stands still until the grid
+// comes back
+pub struct World {
+    // world position of the top left cell of the grid, always the corner of a chunk
+    origin: (i64, i64),
+    chunks: HashMap<(i64, i64), Chunk>,
+    // chunks far from the grid are kept here, one file each
+    directory: PathBuf,
+    // --gen, otherwise new chunks are air
+    terrain: Option<&'static Terrain>,
+    seed: u64,
+}
+
+impl World {
+    pub fn new(terrain: Option<&'static Terrain>, seed: u64) -> Self {
+        World {
+            origin: (0, 0),
+            chunks: HashMap::new(),
+            directory: std::env::temp_dir().join(format!("sand-sim-{}", std::process::id())),
+            terrain,
+            seed,
+        }
+    }
+
+    // fills the grid from the chunks under it, before anything is put in it
+    pub fn fill(&mut self, cells: &mut Grid, tracking: &mut Tracking) {
+        self.load(cells, &mut tracking.fields);
+        shift_tracking(cells, tracking, (0, 0));
+    }
+
+    // moves the grid over the world by whole chunks, far enough to take in offset cells past its edges. returns how far
+    // it moved in cells
+    pub fn follow(
+        &mut self,
+        cells: &mut Grid,
+        tracking: &mut Tracking,
+        offset: (isize, isize),
+        rng: &Rng,
+    ) -> (isize, isize) {
+        let chunks =
+            |offset: isize| offset.signum() * offset.unsigned_abs().div_ceil(CHUNK_SIZE) as isize;
+        let shift = (
+            chunks(offset.0) * CHUNK_SIZE as isize,
+            chunks(offset.1) * CHUNK_SIZE as isize,
+        );
+        if shift == (0, 0) {
+            return shift;
+        }
+
+        // bodies arent kept in the chunks, they are cut out of the grid before it is stored
+        rigid::shift(cells, tracking, shift, rng);
+        self.store(cells, &tracking.fields);
+
+        self.origin = (
+            self.origin.0 + shift.0 as i64,
+            self.origin.1 + shift.1 as i64,
+        );
+        self.load(cells, &mut tracking.fields);
+        shift_tracking(cells, tracking, shift);
+        self.unload();
+
+        shift
+    }
+
+    // material reaching the edge of the grid goes on into the world instead of stopping there: liquids run off the
+    // sides, rising gases drift off the top and sides and anything thrown flies off the side it is heading for. it lands
+    // in the chunk just past the edge, made then if nothing has been there yet, and stands still like the rest of the
+    // world outside the grid. the edge gravity pulls towards stays a floor, otherwise an empty world would drain away
+    pub fn overflow(&mut self, cells: &mut Grid, tracking: &Tracking, rng: &Rng) {
+        let mut cells = cells.window();
+        let tracking = tracking.window(0);
+
+        for (pos, out) in edges() {
+            let cell = cells[pos];
+            if !leaves(&cell, out, tracking.gravity_at(pos), rng) {
+                continue;
+            }
+
+            let beyond = (
+                self.origin.0 + pos.0 as i64 + out.0 as i64,
+                self.origin.1 + pos.1 as i64 + out.1 as i64,
+            );
+            if self.put(beyond, cell) {
+                set_cell(&mut cells, &tracking, pos, Cell::from(CellType::Air, rng));
+            }
+        }
+    }
+
+    // removes the chunks written out this run
+    pub fn remove_files(&self) {
+        if self.directory.exists() {
+            if let Err(error) = std::fs::remove_dir_all(&self.directory) {
+                eprintln!("{}: {error}", self.directory.display());
+            }
+        }
+    }
+
+    // every chunk the grid covers some of
+    fn covered(&self) -> Vec<Overlap> {
+        let size = CHUNK_SIZE as i64;
+        let grid_range = |origin: i64, length: usize| {
+            origin.div_euclid(size)..=(origin + length as i64 - 1).div_euclid(size)
+        };
+        // the origin is on a chunk corner so every chunk starts at or after it
+        let cell_range = |origin: i64, chunk: i64, length: usize| {
+            let start = (chunk * size - origin) as usize;
+            start..(start + CHUNK_SIZE).min(length)
+        };
+
+        let mut covered = Vec::new();
+        for chunk_y in grid_range(self.origin.1, HEIGHT) {
+            for chunk_x in grid_range(self.origin.0, WIDTH) {
+                covered.push(Overlap {
+                    key: (chunk_x, chunk_y),
+                    columns: cell_range(self.origin.0, chunk_x, WIDTH),
+                    rows: cell_range(self.origin.1, chunk_y, HEIGHT),
+                });
+            }
+        }
+
+        covered
+    }
+
+    // read back from disk if it was written out, otherwise nothing has been there yet. one that cant be read back is
+    // made again as if nothing had been there
+    fn chunk(&mut self, key: (i64, i64)) -> &mut Chunk {
+        let path = self.path(key);
+        let (terrain, seed) = (self.terrain, self.seed);
+
+        self.chunks.entry(key).or_insert_with(|| {
+            if !path.exists() {
+                return new_chunk(terrain, seed, key);
+            }
+
+            match std::fs::read(&path) {
+                Ok(bytes) => read_chunk(&bytes).unwrap_or_else(|| {
+                    eprintln!("{}: not a chunk", path.display());
+                    new_chunk(terrain, seed, key)
+                }),
+                Err(error) => {
+                    eprintln!("{}: {error}", path.display());
+                    new_chunk(terrain, seed, key)
+                }
+            }
+        })
+    }
+
+    // into the air at a world position outside the grid, false if something is already there
+    fn put(&mut self, (x, y): (i64, i64), mut cell: Cell) -> bool {
+        let size = CHUNK_SIZE as i64;
+        let chunk = self.chunk((x.div_euclid(size), y.div_euclid(size)));
+        let index = (y.rem_euclid(size) * size + x.rem_euclid(size)) as usize;
+        if chunk.cells[index].ty != CellType::Air {
+            return false;
+        }
+
+        cell.clear_moved();
+        chunk.cells[index] = cell;
+        chunk.dirty = true;
+        true
+    }
+
+    fn path(&self, (chunk_x, chunk_y): (i64, i64)) -> PathBuf {
+        self.directory.join(format!("{chunk_x}_{chunk_y}"))
+    }
+
+    // puts the grid back into the chunks it is over
+    fn store(&mut self, cells: &Grid, fields: &[u8]) {
+        for Overlap { key, columns, rows } in self.covered() {
+            let chunk = self.chunk(key);
+
+            for (chunk_y, y) in rows.enumerate() {
+                for (chunk_x, x) in columns.clone().enumerate() {
+                    let index = chunk_y * CHUNK_SIZE + chunk_x;
+                    let mut cell = cells.row(y)[x];
+                    // the stamps mean nothing by the time the grid comes back
+                    cell.clear_moved();
+                    let field = fields[y * WIDTH + x];
+
+                    if chunk.cells[index] != cell || chunk.fields[index] != field {
+                        chunk.cells[index] = cell;
+                        chunk.fields[index] = field;
+                        chunk.dirty = true;
+                    }
+                }
+            }
+        }
+    }
+
+    // fills the grid from the chunks it has moved over
+    fn load(&mut self, cells: &mut Grid, fields: &mut [u8]) {
+        let cells = cells.cells_mut();
+
+        for Overlap { key, columns, rows } in self.covered() {
+            let chunk = self.chunk(key);
+
+            for (chunk_y, y) in rows.enumerate() {
+                for (chunk_x, x) in columns.clone().enumerate() {
+                    let index = chunk_y * CHUNK_SIZE + chunk_x;
+                    cells[y * WIDTH + x] = chunk.cells[index];
+                    fields[y * WIDTH + x] = chunk.fields[index];
+                }
+            }
+        }
+    }
+
+    // chunks far from the grid leave memory, the ones changed since they were last written out are written first. ones
+    // that cant be written out stay in memory to be tried again next time
+    fn unload(&mut self) {
+        let size = CHUNK_SIZE as i64;
+        let (left, top) = (
+            self.origin.0.div_euclid(size),
+            self.origin.1.div_euclid(size),
+        );
+        let right = (self.origin.0 + WIDTH as i64 - 1).div_euclid(size);
+        let bottom = (self.origin.1 + HEIGHT as i64 - 1).div_euclid(size);
+        let distance = |(chunk_x, chunk_y): (i64, i64)| {
+            (left - chunk_x)
+                .max(chunk_x - right)
+                .max(top - chunk_y)
+                .max(chunk_y - bottom)
+        };
+
+        let distant: Vec<_> = self
+            .chunks
+            .keys()
+            .copied()
+            .filter(|&key| distance(key) > KEEP_DISTANCE)
+            .collect();
+
+        for key in distant {
+            if self.chunks[&key].dirty {
+                if let Err(error) = self.write(key) {
+                    eprintln!("{}: {error}", self.path(key).display());
+                    continue;
+                }
+            }
+
+            self.chunks.remove(&key);
+        }
+    }
+
+    fn write(&self, key: (i64, i64)) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.directory)?;
+        std::fs::write(self.path(key), write_chunk(&self.chunks[&key]))
+    }
+}
+
+// every cell along the edges of the grid and the way out of the grid there, corners twice
+fn edges() -> impl Iterator<Item = ((usize, usize), (isize, isize))> {
+    let rows = (0..WIDTH).flat_map(|x| [((x, 0), (0, -1)), ((x, HEIGHT - 1), (0, 1))]);
+    let columns = (0..HEIGHT).flat_map(|y| [((0, y), (-1, 0)), ((WIDTH - 1, y), (1, 0))]);
+    rows.chain(columns)
+}
+
+// whether a cell on the edge of the grid goes on past it in direction out
+fn leaves(cell: &Cell, out: (isize, isize), gravity: (isize, isize), rng: &Rng) -> bool {
+    if out == gravity {
+        return false;
+    }
+    if RISING.contains(&cell.ty) {
+        return rng.f32() < OVERFLOW_CHANCE;
+    }
+    // only gases leave through the top
+    if out == (-gravity.0, -gravity.1) {
+        return false;
+    }
+
+    let across = across(gravity);
+    let heading = cell.velocity_x.signum() as isize;
+    if heading != 0 {
+        return (across.0 * heading, across.1 * heading) == out;
+    }
+
+    cell_type_liquid_density(cell.ty)
+        .is_some_and(|_| rng.f32() < OVERFLOW_CHANCE * (1.0 - cell_type_viscosity(cell.ty)))
+}
+
+// the same every time for the same seed so a chunk that never changed doesnt need writing out
+fn new_chunk(terrain: Option<&Terrain>, seed: u64, (chunk_x, chunk_y): (i64, i64)) -> Chunk {
+    let rng = Rng::with_seed(stream_seed(seed, chunk_x as u64, chunk_y as u64));
+    let size = CHUNK_SIZE as i64;
+
+    let cells = (0..size)
+        .flat_map(|y| (0..size).map(move |x| (chunk_x * size + x, chunk_y * size + y)))
+        .map(|pos| {
+            let cell_type = terrain.map_or(CellType::Air, |terrain| {
+                generation::generate(terrain, seed, pos)
+            });
+            Cell::from(cell_type, &rng)
+        })
+        .collect();
+
+    Chunk {
+        cells,
+        fields: vec![NO_FIELD; CHUNK_SIZE * CHUNK_SIZE],
+        dirty: false,
+    }
+}
+
+fn write_chunk(chunk: &Chunk) -> Vec<u8> {
+    chunk
+        .cells
+        .iter()
+        .zip(&chunk.fields)
+        .flat_map(|(cell, &field)| {
+            [
+                cell.ty as u8,
+                cell.velocity,
+                cell.velocity_x as u8,
+                cell.color_index,
+                cell.lifetime,
+                cell.fuel,
+                cell.temperature,
+                field,
+            ]
+        })
+        .collect()
+}
+
+// None if it was cut short or isnt a chunk at all
+fn read_chunk(bytes: &[u8]) -> Option<Chunk> {
+    if bytes.len() != CHUNK_SIZE * CHUNK_SIZE * CELL_BYTES {
+        return None;
+    }
+
+    let cell_types: Vec<CellType> = all().collect();
+    let (cells, fields) = bytes
+        .chunks_exact(CELL_BYTES)
+        .map(|bytes| {
+            let cell = Cell {
+                ty: *cell_types.get(bytes[0] as usize)?,
+                moved_generation: 0,
+                velocity: bytes[1],
+                velocity_x: bytes[2] as i8,
+                color_index: bytes[3],
+                lifetime: bytes[4],
+                fuel: bytes[5],
+                temperature: bytes[6],
+            };
+            Some((cell, bytes[7]))
+        })
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .unzip();
+
+    Some(Chunk {
+        cells,
+        fields,
+        dirty: false,
+    })
+}
+
+// the grid moved over the world by offset cells and was filled from the chunks. charge still over the grid moves with
+// it, particles too, and the rest is worked out again from the cells
+fn shift_tracking(cells: &Grid, tracking: &mut Tracking, offset: (isize, isize)) {
+    for (y, row_count) in tracking.row_counts.iter().enumerate() {
+        let count = cells
+            .row(y)
+            .iter()
+            .filter(|cell| cell.ty != CellType::Air)
+            .count();
+        row_count.store(count as u32, Ordering::Relaxed);
+    }
+
+    for dirty in &tracking.dirty_chunks {
+        dirty.store(true, Ordering::Relaxed);
+    }
+
+    shift_plane(&mut tracking.charge, offset);
+    shift_plane(&mut tracking.previous_charge, offset);
+    // these are rebuilt every tick
+    tracking.pressure.fill(0);
+    tracking.pressure_sources.fill(0);
+    tracking.beams.clear();
+    tracking.blasts.get_mut().unwrap().clear();
+    tracking.detonations.get_mut().unwrap().clear();
+    tracking.ignitions.get_mut().unwrap().clear();
+
+    for particle in &mut tracking.particles {
+        particle.position.0 -= offset.0 as f32;
+        particle.position.1 -= offset.1 as f32;
+    }
+    tracking
+        .particles
+        .retain(|particle| particle.cell().is_some());
+}
+
+// what was at (x, y) ends up at (x - offset.0, y - offset.1), anything moved in from past the edges is 0
+fn shift_plane(plane: &mut [u8], offset: (isize, isize)) {
+    let shifted: Vec<_> = (0..HEIGHT)
+        .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let (from_x, from_y) = (x as isize + offset.0, y as isize + offset.1);
+            if (0..WIDTH as isize).contains(&from_x) && (0..HEIGHT as isize).contains(&from_y) {
+                plane[from_y as usize * WIDTH + from_x as usize]
+            } else {
+                0
+            }
+        })
+        .collect();
+
+    plane.copy_from_slice(&shifted);
+}